@@ -0,0 +1,78 @@
+//! The baseline of parsing and serializing the dns packets.
+//!
+//! run: `cargo bench --bench parse`, the throughput is reported in messages(elements)/sec.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use rsdns::dns::{rdata::encode_domain_name_wrap, CompressList, Labels, DNS};
+use std::{fs, path::Path};
+
+/// load all the raw dns packets in the test_dns_raw corpus which can be parsed.
+fn corpus() -> Vec<Vec<u8>> {
+    fn visit(dir: &Path, list: &mut Vec<Vec<u8>>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                visit(&path, list);
+            } else {
+                let raw = fs::read(&path).unwrap();
+                if DNS::from(&raw).is_ok() {
+                    list.push(raw);
+                }
+            }
+        }
+    }
+
+    let mut list = vec![];
+    visit(Path::new("./test_dns_raw"), &mut list);
+    list
+}
+
+fn bench_dns(c: &mut Criterion) {
+    let corpus = corpus();
+    let mut group = c.benchmark_group("dns");
+    group.throughput(Throughput::Elements(corpus.len() as u64));
+
+    group.bench_function("from", |b| {
+        b.iter(|| {
+            for raw in &corpus {
+                black_box(DNS::from(black_box(raw)).unwrap());
+            }
+        })
+    });
+
+    let mut parsed: Vec<DNS> = corpus.iter().map(|raw| DNS::from(raw).unwrap()).collect();
+    group.bench_function("encode", |b| {
+        b.iter(|| {
+            for dns in parsed.iter_mut() {
+                black_box(dns.encode(true).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+fn bench_labels(c: &mut Criterion) {
+    // the question name start at the offset 12
+    let raw = fs::read("./test_dns_raw/google.com/1_1").unwrap();
+    let mut group = c.benchmark_group("labels");
+    group.throughput(Throughput::Elements(1));
+
+    group.bench_function("parse", |b| {
+        b.iter(|| {
+            let mut offset = 12;
+            black_box(Labels::parse(black_box(&raw), &mut offset).unwrap());
+        })
+    });
+
+    group.bench_function("encode_domain_name_wrap", |b| {
+        b.iter(|| {
+            let mut cl = CompressList::new();
+            black_box(encode_domain_name_wrap("www.google.com", &mut cl, true, 12).unwrap());
+            black_box(encode_domain_name_wrap("mail.google.com", &mut cl, true, 40).unwrap());
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_dns, bench_labels);
+criterion_main!(benches);
@@ -0,0 +1,12 @@
+//! DNS::from on the arbitrary bytes must return an error instead of panic for the malformed ones.
+//!
+//! run: `cargo fuzz run parse fuzz/corpus/parse test_dns_raw`, see the fuzz/README.md.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rsdns::DNS;
+
+fuzz_target!(|raw: &[u8]| {
+    let _ = DNS::from(raw);
+});
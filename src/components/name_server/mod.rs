@@ -28,8 +28,10 @@
     dt is a shortening of domian_tree.
 */
 
+pub mod observer;
+pub mod rrl;
 mod server;
-mod zones;
+pub mod zones;
 
 use self::zones::zone::Zones;
 pub use server::NameServer;
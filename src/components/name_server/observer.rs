@@ -0,0 +1,109 @@
+/*!
+QueryObserver: the hooks of the name server on each query and its response, for the query logs and the
+metrics such as the qps, the rcode distribution and the per-type counts.
+
+The hooks are called by the serve loop in the order of the query, so they must be cheap and never block.
+ */
+
+use crate::{
+    dns::{Class, Type},
+    DNS,
+};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+pub trait QueryObserver: Send + Sync {
+    /// on_query: the query received from the client.
+    fn on_query(&self, client: SocketAddr, q: &DNS);
+
+    /// on_response: the response to the client, the elapsed is the time taken to answer the query.
+    fn on_response(&self, client: SocketAddr, r: &DNS, elapsed: Duration);
+}
+
+impl<T: QueryObserver> QueryObserver for Arc<T> {
+    fn on_query(&self, client: SocketAddr, q: &DNS) {
+        self.as_ref().on_query(client, q)
+    }
+
+    fn on_response(&self, client: SocketAddr, r: &DNS, elapsed: Duration) {
+        self.as_ref().on_response(client, r, elapsed)
+    }
+}
+
+/// NoopObserver: the default observer of the name server, does nothing.
+pub struct NoopObserver;
+
+impl QueryObserver for NoopObserver {
+    fn on_query(&self, _client: SocketAddr, _q: &DNS) {}
+
+    fn on_response(&self, _client: SocketAddr, _r: &DNS, _elapsed: Duration) {}
+}
+
+/// CountingObserver: count the queries by the type and class of the first question, and the responses
+/// by the rcode.
+pub struct CountingObserver {
+    queries: AtomicU64,
+    responses: AtomicU64,
+    types: Mutex<HashMap<(Type, Class), u64>>,
+    rcodes: Mutex<HashMap<u8, u64>>,
+}
+
+impl CountingObserver {
+    pub fn new() -> Self {
+        Self {
+            queries: AtomicU64::new(0),
+            responses: AtomicU64::new(0),
+            types: Mutex::new(HashMap::new()),
+            rcodes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn queries(&self) -> u64 {
+        self.queries.load(Ordering::Relaxed)
+    }
+
+    pub fn responses(&self) -> u64 {
+        self.responses.load(Ordering::Relaxed)
+    }
+
+    /// the count of the queries of the type and class.
+    pub fn type_count(&self, typ: Type, class: Class) -> u64 {
+        *self.types.lock().unwrap().get(&(typ, class)).unwrap_or(&0)
+    }
+
+    /// the count of the responses of the rcode.
+    pub fn rcode_count(&self, rcode: u8) -> u64 {
+        *self.rcodes.lock().unwrap().get(&rcode).unwrap_or(&0)
+    }
+}
+
+impl QueryObserver for CountingObserver {
+    fn on_query(&self, _client: SocketAddr, q: &DNS) {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        if let Some(ques) = q.ques().0.first() {
+            *self
+                .types
+                .lock()
+                .unwrap()
+                .entry((ques.qtype(), ques.qclass()))
+                .or_insert(0) += 1;
+        }
+    }
+
+    fn on_response(&self, _client: SocketAddr, r: &DNS, _elapsed: Duration) {
+        self.responses.fetch_add(1, Ordering::Relaxed);
+        *self
+            .rcodes
+            .lock()
+            .unwrap()
+            .entry(r.header().rcode())
+            .or_insert(0) += 1;
+    }
+}
@@ -0,0 +1,243 @@
+/*!
+ref: https://kb.isc.org/docs/aa-00994
+
+# Response Rate Limiting
+
+The UDP responses can be sent to a spoofed address, so the name server may be abused to reflect and
+amplify the traffic to a victim. The response rate limiting counts the responses to each client subnet
+by the kind of the response, the responses beyond the rate are suppressed: most of them are dropped,
+and every `slip`th one is sent truncated (TC=1 without records), so the legitimate client behind the
+subnet can retry over TCP, which can not be spoofed.
+
+The rate is a token bucket of each `(subnet, kind)`, it holds at most the tokens of one second and is
+refilled by the rate per second, each response takes one token.
+ */
+
+use crate::{
+    dns::{RCODE_NOERROR, RCODE_NXDOMAIN, TYPE_ANY},
+    DNS,
+};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::Mutex,
+    time::Instant,
+};
+
+/// the count of the buckets to check the stale ones.
+const MAX_BUCKETS: usize = 10000;
+
+/// RrlConfig: the rates of the responses per second to a client subnet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RrlConfig {
+    pub responses_per_second: u32,
+    pub nxdomains_per_second: u32,
+    pub errors_per_second: u32,
+    /// every slip-th suppressed response is sent truncated, the others are dropped. 0 drops all.
+    pub slip: u32,
+    /// the prefix length of the IPv4 client subnet.
+    pub ipv4_prefix_len: u8,
+    /// the prefix length of the IPv6 client subnet.
+    pub ipv6_prefix_len: u8,
+}
+
+impl RrlConfig {
+    pub fn new() -> Self {
+        Self {
+            responses_per_second: 5,
+            nxdomains_per_second: 5,
+            errors_per_second: 5,
+            slip: 2,
+            ipv4_prefix_len: 24,
+            ipv6_prefix_len: 56,
+        }
+    }
+
+    pub fn with_responses_per_second(&mut self, rate: u32) -> &mut Self {
+        self.responses_per_second = rate;
+        return self;
+    }
+
+    pub fn with_nxdomains_per_second(&mut self, rate: u32) -> &mut Self {
+        self.nxdomains_per_second = rate;
+        return self;
+    }
+
+    pub fn with_errors_per_second(&mut self, rate: u32) -> &mut Self {
+        self.errors_per_second = rate;
+        return self;
+    }
+
+    pub fn with_slip(&mut self, slip: u32) -> &mut Self {
+        self.slip = slip;
+        return self;
+    }
+
+    pub fn with_prefix_len(&mut self, ipv4: u8, ipv6: u8) -> &mut Self {
+        self.ipv4_prefix_len = ipv4.min(32);
+        self.ipv6_prefix_len = ipv6.min(128);
+        return self;
+    }
+}
+
+/// ResponseKind: the responses of the kinds are counted separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResponseKind {
+    Normal,
+    NxDomain,
+    /// the response to the ANY query, which is the usual one for the amplification.
+    Any,
+    Error,
+}
+
+impl ResponseKind {
+    pub fn from(resp: &DNS) -> Self {
+        match resp.header().rcode() {
+            RCODE_NXDOMAIN => ResponseKind::NxDomain,
+            RCODE_NOERROR if resp.ques().0.iter().any(|q| q.qtype() == TYPE_ANY) => {
+                ResponseKind::Any
+            }
+            RCODE_NOERROR => ResponseKind::Normal,
+            _ => ResponseKind::Error,
+        }
+    }
+}
+
+/// RrlAction: what to do with the response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RrlAction {
+    Send,
+    Truncate,
+    Drop,
+}
+
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+    suppressed: u32,
+}
+
+pub struct RateLimiter {
+    config: RrlConfig,
+    buckets: Mutex<HashMap<(IpAddr, ResponseKind), Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RrlConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// check: take a token of the bucket of the client subnet and the kind of the response.
+    pub fn check(&self, client: IpAddr, resp: &DNS) -> RrlAction {
+        self.check_at(client, ResponseKind::from(resp), Instant::now())
+    }
+
+    /// check_at: the same as [`RateLimiter::check`] at the time of `now`.
+    pub(crate) fn check_at(&self, client: IpAddr, kind: ResponseKind, now: Instant) -> RrlAction {
+        let rate = match kind {
+            ResponseKind::Normal | ResponseKind::Any => self.config.responses_per_second,
+            ResponseKind::NxDomain => self.config.nxdomains_per_second,
+            ResponseKind::Error => self.config.errors_per_second,
+        } as f64;
+
+        let mut buckets = self.buckets.lock().unwrap();
+        // the buckets refilled to the full are the same as the absent ones
+        if buckets.len() >= MAX_BUCKETS {
+            buckets.retain(|_, bucket| now.duration_since(bucket.last).as_secs() < 1);
+        }
+        let bucket = buckets
+            .entry((self.subnet(client), kind))
+            .or_insert(Bucket {
+                tokens: rate,
+                last: now,
+                suppressed: 0,
+            });
+        let elapsed = now.duration_since(bucket.last).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(rate);
+        bucket.last = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return RrlAction::Send;
+        }
+
+        bucket.suppressed = bucket.suppressed.wrapping_add(1);
+        match self.config.slip {
+            0 => RrlAction::Drop,
+            slip if bucket.suppressed % slip == 0 => RrlAction::Truncate,
+            _ => RrlAction::Drop,
+        }
+    }
+
+    /// the client address masked by the prefix length.
+    fn subnet(&self, client: IpAddr) -> IpAddr {
+        match client {
+            IpAddr::V4(ip) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - self.config.ipv4_prefix_len as u32)
+                    .unwrap_or(0);
+                IpAddr::V4(Ipv4Addr::from(u32::from(ip) & mask))
+            }
+            IpAddr::V6(ip) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - self.config.ipv6_prefix_len as u32)
+                    .unwrap_or(0);
+                IpAddr::V6(Ipv6Addr::from(u128::from(ip) & mask))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RateLimiter, ResponseKind, RrlAction, RrlConfig};
+    use std::{
+        net::IpAddr,
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn test_rate_limiter() {
+        let mut config = RrlConfig::new();
+        config.with_nxdomains_per_second(2).with_slip(2);
+        let limiter = RateLimiter::new(config);
+        let client: IpAddr = "192.0.2.1".parse().unwrap();
+        let neighbour: IpAddr = "192.0.2.200".parse().unwrap();
+        let now = Instant::now();
+
+        let check = |client, kind, now| limiter.check_at(client, kind, now);
+        assert_eq!(RrlAction::Send, check(client, ResponseKind::NxDomain, now));
+        assert_eq!(
+            RrlAction::Send,
+            check(neighbour, ResponseKind::NxDomain, now)
+        );
+        // the same subnet
+        assert_eq!(RrlAction::Drop, check(client, ResponseKind::NxDomain, now));
+        assert_eq!(
+            RrlAction::Truncate,
+            check(client, ResponseKind::NxDomain, now)
+        );
+        assert_eq!(RrlAction::Drop, check(client, ResponseKind::NxDomain, now));
+        // the other kind and subnet
+        assert_eq!(RrlAction::Send, check(client, ResponseKind::Normal, now));
+        let other: IpAddr = "198.51.100.1".parse().unwrap();
+        assert_eq!(RrlAction::Send, check(other, ResponseKind::NxDomain, now));
+
+        // refilled
+        let later = now + Duration::from_millis(500);
+        assert_eq!(
+            RrlAction::Send,
+            check(client, ResponseKind::NxDomain, later)
+        );
+        assert_ne!(
+            RrlAction::Send,
+            check(client, ResponseKind::NxDomain, later)
+        );
+
+        let v6 = |ip: &str| limiter.subnet(ip.parse().unwrap());
+        assert_eq!(v6("2001:db8:0:1::1"), v6("2001:db8:0:ff::2"));
+        assert_ne!(v6("2001:db8:0:100::1"), v6("2001:db8:0:ff::2"));
+    }
+}
@@ -1,10 +1,59 @@
-use super::zones::{zone::Zones, DefaultZones, ZonesOperation};
-use crate::{dns::VecRcRf, DNS};
+use super::{
+    observer::{NoopObserver, QueryObserver},
+    rrl::{RateLimiter, ResponseKind, RrlAction, RrlConfig},
+    zones::{zone::Zones, DefaultZones, ZonesOperation},
+};
+use crate::{
+    dns::{
+        rdata::{
+            hinfo::HInfo,
+            tsig::{self, TsigFailure, TsigKey, TsigVerification},
+            txt::TXT,
+            RDataType,
+        },
+        tcp::{encode_tcp, read_tcp},
+        Question, RRs, Type, VecArcRf, CLASS_ANY, CLASS_CH, DEFAULT_UDP_PAYLOAD, RCODE_NOERROR,
+        RCODE_NOTAUTH, RCODE_REFUSED, RCODE_SERVFAIL, RR, TYPE_ANY, TYPE_DS, TYPE_HINFO, TYPE_NSEC,
+        TYPE_NSEC3, TYPE_RRSIG, TYPE_TXT,
+    },
+    DNS,
+};
 use anyhow::{Error, Result};
-use bytes::{Bytes, BytesMut};
-use nom::AsBytes;
-use std::{cell::RefCell, fmt::format, io::Cursor, rc::Rc};
-use tokio::{self, io::AsyncReadExt};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    cell::RefCell,
+    fmt::format,
+    io::Cursor,
+    net::{IpAddr, SocketAddr},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+use std::{collections::HashSet, path::Path};
+use tokio::{self, io::AsyncWriteExt};
+
+/// AnswerPolicy: whether the server performs the additional section processing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnswerPolicy {
+    /// only the answers, omit the additional section glue to reduce the size. (RFC 9156 style)
+    Minimal,
+    /// the A and AAAA records of the NS/MX targets are attached to the additional section.
+    Full,
+}
+
+/// AnyPolicy: how the server answers the ANY query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnyPolicy {
+    /// a synthesized HINFO instead of all the RRsets. (RFC 8482)
+    Hinfo,
+    /// all the RRsets of the name.
+    AllRecords,
+    /// REFUSED.
+    Refuse,
+}
 
 /**
   The domain system provides:
@@ -16,11 +65,26 @@ use tokio::{self, io::AsyncReadExt};
 pub struct NameServer {
     protocol: String,
     port: String,
-    zones: VecRcRf<Zones>,
-}
+    zones: VecArcRf<Zones>,
 
-unsafe impl Sync for NameServer {}
-unsafe impl Send for NameServer {}
+    /// answer the `version.bind TXT CH` query, each one is a <character-string> of the TXT.
+    version_strings: Option<Vec<String>>,
+    /// answer the `hostname.bind TXT CH` and `id.server TXT CH` queries
+    server_id: Option<String>,
+    answer_policy: AnswerPolicy,
+    any_policy: AnyPolicy,
+    /// strip the DNSSEC records from the responses to the clients without the DO bit.
+    dnssec_stripping: bool,
+    /// the keys to verify the TSIG of the requests.
+    tsig_keys: Vec<TsigKey>,
+    /// rotate the RRsets of the answers on each query.
+    round_robin: bool,
+    rotation: AtomicUsize,
+    /// the hooks on each query served.
+    observer: Box<dyn QueryObserver>,
+    /// limit the rate of the UDP responses to each client subnet.
+    rate_limiter: Option<RateLimiter>,
+}
 
 impl NameServer {
     pub fn new() -> Self {
@@ -28,11 +92,21 @@ impl NameServer {
             zones: vec![],
             protocol: "udp".to_string(),
             port: "53".to_string(),
+            version_strings: None,
+            server_id: None,
+            answer_policy: AnswerPolicy::Full,
+            any_policy: AnyPolicy::Hinfo,
+            dnssec_stripping: true,
+            tsig_keys: vec![],
+            round_robin: false,
+            rotation: AtomicUsize::new(0),
+            observer: Box::new(NoopObserver),
+            rate_limiter: None,
         };
 
         let zones: Vec<Zones> = DefaultZones::new().calalog_zones();
         for zone in zones {
-            ns.zones.push(Rc::new(RefCell::new(zone)));
+            ns.zones.push(Arc::new(RwLock::new(zone)));
         }
 
         ns
@@ -43,14 +117,215 @@ impl NameServer {
             zones: vec![],
             protocol: String::new(),
             port: "53".to_string(),
+            version_strings: None,
+            server_id: None,
+            answer_policy: AnswerPolicy::Full,
+            any_policy: AnyPolicy::Hinfo,
+            dnssec_stripping: true,
+            tsig_keys: vec![],
+            round_robin: false,
+            rotation: AtomicUsize::new(0),
+            observer: Box::new(NoopObserver),
+            rate_limiter: None,
         };
         let zones = zoneser.calalog_zones();
         for zone in zones {
-            ns.zones.push(Rc::new(RefCell::new(zone)));
+            ns.zones.push(Arc::new(RwLock::new(zone)));
         }
         return ns;
     }
 
+    pub fn with_version_string(&mut self, version: &str) -> &mut Self {
+        self.version_strings = Some(vec![version.to_string()]);
+        return self;
+    }
+
+    /// with_version_strings: answer the `version.bind TXT CH` query with the multiple <character-string>s
+    /// of a TXT, such as the name and the build of the server.
+    pub fn with_version_strings(&mut self, versions: Vec<String>) -> &mut Self {
+        self.version_strings = Some(versions);
+        return self;
+    }
+
+    pub fn with_server_id(&mut self, id: &str) -> &mut Self {
+        self.server_id = Some(id.to_string());
+        return self;
+    }
+
+    pub fn with_answer_policy(&mut self, policy: AnswerPolicy) -> &mut Self {
+        self.answer_policy = policy;
+        return self;
+    }
+
+    /// with_any_policy: how to answer the ANY query, the [`AnyPolicy::Hinfo`] by default.
+    pub fn with_any_policy(&mut self, policy: AnyPolicy) -> &mut Self {
+        self.any_policy = policy;
+        return self;
+    }
+
+    /**
+    with_dnssec_stripping: whether to strip the RRSIG/NSEC/NSEC3/DS records from the response when the
+    query has no DO bit, unless the type is queried explicitly. Enabled by default.
+
+    ref: https://www.rfc-editor.org/rfc/rfc4035#section-3.2.1
+     */
+    pub fn with_dnssec_stripping(&mut self, stripping: bool) -> &mut Self {
+        self.dnssec_stripping = stripping;
+        return self;
+    }
+
+    /**
+    with_tsig_key: add the key to verify the TSIG of the requests. The request failed the verification
+    is answered by NOTAUTH with the TSIG RR carrying the error, see [`tsig::verify`], and the
+    response to the verified request is signed by its key.
+
+    ref: https://www.rfc-editor.org/rfc/rfc8945#section-5.2
+     */
+    pub fn with_tsig_key(&mut self, key: TsigKey) -> &mut Self {
+        self.tsig_keys.push(key);
+        return self;
+    }
+
+    /// with_round_robin: rotate the records of each RRset in the answers on each query to spread the
+    /// load over the addresses, see [`RRs::rotate`]. Disabled by default.
+    pub fn with_round_robin(&mut self, round_robin: bool) -> &mut Self {
+        self.round_robin = round_robin;
+        return self;
+    }
+
+    /// with_observer: the hooks called on each query served and its response, the [`NoopObserver`] by
+    /// default.
+    pub fn with_observer(&mut self, observer: Box<dyn QueryObserver>) -> &mut Self {
+        self.observer = observer;
+        return self;
+    }
+
+    /// with_rate_limit: limit the rate of the UDP responses to each client subnet, the responses
+    /// beyond the rate are dropped or truncated, see [`RateLimiter`]. Disabled by default.
+    pub fn with_rate_limit(&mut self, config: RrlConfig) -> &mut Self {
+        self.rate_limiter = Some(RateLimiter::new(config));
+        return self;
+    }
+
+    /**
+    watch_zones: reload the master file when it is changed, the reloaded zone is swapped in atomically.
+    If the changed file fails to parse, the old zone is kept.
+
+    The errors of the watching and of the reloading are passed to `on_error`, the watching goes on.
+    The watching stops when the returned watcher is dropped.
+     */
+    pub fn watch_zones<F>(&self, on_error: F) -> Result<RecommendedWatcher, Error>
+    where
+        F: Fn(Error) + Send + 'static,
+    {
+        let zones = self.zones.clone();
+        let mut watcher =
+            notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+                let event = match res {
+                    Ok(event) => event,
+                    Err(e) => {
+                        on_error(Error::new(e).context("watch the zones failed"));
+                        return;
+                    }
+                };
+                if !event.kind.is_modify() && !event.kind.is_create() {
+                    return;
+                }
+                for path in &event.paths {
+                    if let Err(e) = reload_zone_file(&zones, path) {
+                        on_error(e);
+                    }
+                }
+            })?;
+
+        // watch the directories, the editors may replace the file instead of writing it
+        let mut dirs = HashSet::new();
+        for zone in &self.zones {
+            for file in zone.read().unwrap().files() {
+                if let Some(dir) = Path::new(&file).parent() {
+                    dirs.insert(dir.to_path_buf());
+                }
+            }
+        }
+        for dir in dirs {
+            watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+        }
+
+        Ok(watcher)
+    }
+
+    /// lookup: the answer of the question from the first zones that knows the name, see
+    /// [`Zones::answer`]. The answer must echo the question, see [`Question::matches`].
+    fn lookup(&self, ques: &Question) -> DNS {
+        let mut query = DNS::new();
+        query.ques_mut().push(ques.clone());
+
+        let mut resp = None;
+        for zone in &self.zones {
+            let answer = zone.read().unwrap().answer(&query);
+            if !answer
+                .ques()
+                .0
+                .first()
+                .is_some_and(|echo| echo.matches(ques))
+            {
+                continue;
+            }
+            let header = answer.header();
+            if header.aa() || header.rcode() != RCODE_NOERROR || !answer.answers().0.is_empty() {
+                return answer;
+            }
+            // the referral
+            if resp.is_none() && !answer.authority().0.is_empty() {
+                resp = Some(answer);
+            }
+        }
+
+        resp.unwrap_or_else(|| {
+            query.into_response();
+            query
+        })
+    }
+
+    /// the synthesized HINFO answering the ANY query of the existing name, the TTL is the minimum of the RRsets.
+    ///
+    /// ref: https://www.rfc-editor.org/rfc/rfc8482#section-4.2
+    fn synthesized_any(ques: &Question, found: &[RR]) -> Option<RR> {
+        let ttl = found.iter().map(|rr| rr.ttl()).min()?;
+
+        let mut rr = RR::new();
+        rr.with_name(&ques.qname().encode_to_str())
+            .with_type(TYPE_HINFO)
+            .with_class(found[0].class())
+            .with_ttl(ttl)
+            .with_rdata(RDataType::HInfo(HInfo::synthesized_rfc8482()));
+        Some(rr)
+    }
+
+    /// answer the CHAOS class queries of the server itself, such as `version.bind TXT CH`.
+    fn chaos_answer(&self, ques: &Question) -> Option<RR> {
+        if ques.qclass() != CLASS_CH && ques.qclass() != CLASS_ANY {
+            return None;
+        }
+        if ques.qtype() != TYPE_TXT && ques.qtype() != TYPE_ANY {
+            return None;
+        }
+        let name = ques.qname().encode_to_str().to_ascii_lowercase();
+        let texts = match name.trim_end_matches('.') {
+            "version.bind" | "version.server" => self.version_strings.clone()?,
+            "hostname.bind" | "id.server" => vec![self.server_id.clone()?],
+            _ => return None,
+        };
+
+        let mut rr = RR::new();
+        rr.with_name(&name)
+            .with_type(TYPE_TXT)
+            .with_class(CLASS_CH)
+            .with_ttl(0)
+            .with_rdata(RDataType::TXT(TXT(texts)));
+        Some(rr)
+    }
+
     // start serve, it will block till the progress quit
     pub async fn serve(&'static self) -> Result<()> {
         match self.protocol.as_str() {
@@ -59,14 +334,7 @@ impl NameServer {
                 let sock = tokio::net::UdpSocket::bind(format!("0.0.0.0:{}", port))
                     .await
                     .expect("bind udp failed");
-                loop {
-                    let mut bts = bytes::BytesMut::new();
-                    let size = sock.recv(bts.as_mut()).await.unwrap();
-                    unsafe { bts.set_len(size) };
-
-                    let dns_query = DNS::from(bts.as_bytes()).expect("parse dns packet err");
-                    tokio::spawn(async move { self.query(dns_query) });
-                }
+                self.serve_udp(sock).await
             }
 
             "tcp" => {
@@ -77,17 +345,19 @@ impl NameServer {
                 loop {
                     let (tcp_stream, sock_addr) = sock.accept().await.unwrap();
                     tokio::spawn(async move {
-                        let (mut rh, wh) = tcp_stream.into_split();
-                        let mut buf = Vec::new();
-                        if let Ok(n) = rh.read_to_end(&mut buf).await {
-                            if n == 0 {
-                                // socket closed
-                                return;
+                        let (mut rh, mut wh) = tcp_stream.into_split();
+                        // the messages are length prefixed, the queries of the connection are answered
+                        // in order until it is closed
+                        while let Ok(dns_query) = read_tcp(&mut rh).await {
+                            let (mut resp, verified) = self.handle(sock_addr, dns_query).await;
+                            let raw = match self.encode_tcp(&mut resp, &verified) {
+                                Ok(raw) => raw,
+                                Err(_) => break,
+                            };
+                            if wh.write_all(&raw).await.is_err() {
+                                break;
                             }
                         }
-                        let bts = Bytes::from(buf);
-                        let dns_query = DNS::from(bts.as_bytes()).expect("parse dns packet err");
-                        tokio::spawn(async move { self.query(dns_query) });
                     });
                 }
             }
@@ -95,28 +365,277 @@ impl NameServer {
         }
     }
 
+    /// serve_udp: serve the queries received by the bound socket, it blocks till the progress quit.
+    pub async fn serve_udp(&'static self, sock: tokio::net::UdpSocket) -> Result<()> {
+        let sock = Arc::new(sock);
+        // the datagram is received whole, the part beyond the buffer would be discarded
+        let mut buff = vec![0u8; u16::MAX as usize];
+        loop {
+            let (size, client) = match sock.recv_from(&mut buff).await {
+                Ok(received) => received,
+                Err(_) => continue,
+            };
+            // the unparsable packet is skipped, it may not carry a valid header to answer
+            let dns_query = match DNS::from(&buff[..size]) {
+                Ok(dns_query) => dns_query,
+                Err(_) => continue,
+            };
+            let sock = sock.clone();
+            tokio::spawn(async move {
+                let payload = dns_query.request_udp_payload();
+                let (resp, verified) = self.handle(client, dns_query).await;
+                let raw = match self.rate_limit(client.ip(), resp) {
+                    Some(mut resp) => self.encode_udp(payload, &mut resp, &verified),
+                    None => return,
+                };
+                if let Ok(raw) = raw {
+                    let _ = sock.send_to(&raw, client).await;
+                }
+            });
+        }
+    }
+
+    /**
+    rate_limit: apply the response rate limiting to the UDP response to the client. Return None if the
+    response is dropped, or the truncated response (TC=1 without records) so the client retries over
+    TCP. The response is returned unchanged when the rate limiting is disabled.
+     */
+    pub fn rate_limit(&self, client: IpAddr, resp: DNS) -> Option<DNS> {
+        self.rate_limit_at(client, resp, Instant::now())
+    }
+
+    fn rate_limit_at(&self, client: IpAddr, mut resp: DNS, now: Instant) -> Option<DNS> {
+        let limiter = match self.rate_limiter.as_ref() {
+            Some(limiter) => limiter,
+            None => return Some(resp),
+        };
+        match limiter.check_at(client, ResponseKind::from(&resp), now) {
+            RrlAction::Send => Some(resp),
+            RrlAction::Drop => None,
+            RrlAction::Truncate => {
+                let rcode = resp.header().rcode();
+                resp.into_response();
+                resp.head().with_tc(true).with_rcode(rcode);
+                Some(resp)
+            }
+        }
+    }
+
+    /**
+    encode_udp: encode the UDP response within the smaller one of the payload of the request and the
+    [`DEFAULT_UDP_PAYLOAD`] of the server. The response too large is sent as the header and the question
+    with the TC bit, the OPT is kept, so the client retries over TCP.
+
+    The response is signed by the verification of the request right before encoded, so the MAC
+    covers the bytes sent, and the truncated response keeps its TSIG RR by signing it again.
+
+    ref: https://www.rfc-editor.org/rfc/rfc2181#section-9
+     */
+    pub fn encode_udp(
+        &self,
+        payload: u16,
+        resp: &mut DNS,
+        verified: &TsigVerification,
+    ) -> Result<Vec<u8>> {
+        let (aa, rcode, opt) = (resp.header().aa(), resp.header().rcode(), resp.edns());
+        self.sign(resp, verified);
+        let raw = resp.encode(true)?;
+        if raw.len() <= payload.min(DEFAULT_UDP_PAYLOAD) as usize {
+            return Ok(raw);
+        }
+
+        resp.into_response();
+        resp.head().with_aa(aa).with_tc(true).with_rcode(rcode);
+        if let Some(opt) = opt {
+            resp.add_additional_rr(opt);
+        }
+        self.sign(resp, verified);
+        resp.encode(true)
+    }
+
+    /// encode_tcp: sign the response as [`NameServer::encode_udp`], then frame it by
+    /// [`encode_tcp`].
+    pub fn encode_tcp(&self, resp: &mut DNS, verified: &TsigVerification) -> Result<Vec<u8>> {
+        self.sign(resp, verified);
+        encode_tcp(resp)
+    }
+
+    /**
+    handle: answer the query of the client, the observer is notified of the query and the response.
+    The response is unsigned, it is signed by the returned verification of the request TSIG when
+    encoded by [`NameServer::encode_udp`] or [`NameServer::encode_tcp`].
+     */
+    pub async fn handle(&self, client: SocketAddr, dns_packet: DNS) -> (DNS, TsigVerification) {
+        let start = Instant::now();
+        self.observer.on_query(client, &dns_packet);
+        let verified = tsig::verify(&dns_packet, &self.tsig_keys, unix_now());
+        let resp = self.answer(dns_packet, verified.as_ref().err());
+        self.observer.on_response(client, &resp, start.elapsed());
+
+        (resp, verified)
+    }
+
+    /// query: answer the query, the response to the signed request is signed, so it must be sent by
+    /// `encode(true)` without any change.
     pub async fn query(&self, dns_packet: DNS) -> DNS {
-        let mut new_dns = DNS::new();
-        for ques in &dns_packet.ques().0 {
-            new_dns.with_ques(
-                ques.qname().encode_to_str().as_str(),
-                ques.qtype(),
-                ques.qclass(),
-            )
+        let verified = tsig::verify(&dns_packet, &self.tsig_keys, unix_now());
+        let mut resp = self.answer(dns_packet, verified.as_ref().err());
+        self.sign(&mut resp, &verified);
+
+        resp
+    }
+
+    /// sign the response by [`tsig::sign_verified`]. The response failed to sign is replaced by the
+    /// SERVFAIL without the records, rather than sent unsigned as if it were answered.
+    fn sign(&self, resp: &mut DNS, verified: &TsigVerification) {
+        if tsig::sign_verified(resp, verified, unix_now()).is_err() {
+            resp.into_response();
+            resp.head().with_rcode(RCODE_SERVFAIL);
         }
+    }
 
-        let mut rrs = vec![];
-        for zone in &self.zones {
-            rrs.extend(zone.clone().borrow().get_rr(dns_packet.ques()))
+    fn answer(&self, dns_packet: DNS, tsig_failure: Option<&TsigFailure>) -> DNS {
+        let mut new_dns = dns_packet;
+        let (edns, dnssec_ok) = (new_dns.edns().is_some(), new_dns.requests_dnssec());
+        let strip_dnssec = self.dnssec_stripping && !dnssec_ok;
+        new_dns.into_response();
+        // the response to the EDNS query advertises the payload of the server, and echoes the DO bit
+        if edns {
+            new_dns.with_edns();
         }
-        for rr in rrs {
-            new_dns.with_additional(rr.clone())
+        if dnssec_ok {
+            new_dns.set_dnssec_requested();
+        }
+        // the error TSIG RR is appended as the last one of the additional section when signing
+        if tsig_failure.is_some() {
+            new_dns.head().with_rcode(RCODE_NOTAUTH);
+            return new_dns;
+        }
+        let questions: Vec<_> = new_dns.ques().0.iter().map(|q| q.clone()).collect();
+        // the DNSSEC records are returned to the client without DO only when queried explicitly
+        let keep = |rr: &RR| {
+            !strip_dnssec
+                || !is_dnssec_type(rr.typ())
+                || questions.iter().any(|q| q.qtype() == rr.typ())
+        };
+
+        let mut chaos = false;
+        for ques in &questions {
+            if let Some(rr) = self.chaos_answer(ques) {
+                new_dns.add_answer_rr(Rc::new(RefCell::new(rr)));
+                chaos = true;
+            }
+        }
+        if chaos {
+            return new_dns;
+        }
+
+        if self.any_policy == AnyPolicy::Refuse && questions.iter().any(|q| q.qtype() == TYPE_ANY) {
+            new_dns.head().with_rcode(RCODE_REFUSED);
+            return new_dns;
+        }
+
+        let (mut answers, mut authority, mut additional) = (vec![], vec![], vec![]);
+        for ques in &questions {
+            let resp = self.lookup(ques);
+            let header = resp.header();
+            if header.rcode() != RCODE_NOERROR {
+                new_dns.head().with_rcode(header.rcode());
+            }
+            if header.aa() {
+                new_dns.head().with_aa(true);
+            }
+            let found: Vec<RR> = resp
+                .answers()
+                .0
+                .iter()
+                .map(|rr| rr.borrow().clone())
+                .collect();
+            if self.any_policy == AnyPolicy::Hinfo && ques.qtype() == TYPE_ANY {
+                if let Some(rr) = Self::synthesized_any(ques, &found) {
+                    answers.push(rr);
+                    continue;
+                }
+            }
+            answers.extend(found.into_iter().filter(|rr| keep(rr)));
+            for (section, rrs) in [
+                (&mut authority, resp.authority()),
+                (&mut additional, resp.additional()),
+            ] {
+                section.extend(
+                    rrs.0
+                        .iter()
+                        .map(|rr| rr.borrow().clone())
+                        .filter(|rr| keep(rr)),
+                );
+            }
+        }
+        let mut rrs = RRs(answers
+            .into_iter()
+            .map(|rr| Rc::new(RefCell::new(rr)))
+            .collect());
+        if self.round_robin && !rrs.0.is_empty() {
+            let rotation = self.rotation.fetch_add(1, Ordering::Relaxed) % rrs.len();
+            for _ in 0..rotation {
+                rrs.rotate();
+            }
+        }
+        for rr in rrs.0 {
+            new_dns.add_answer_rr(rr);
+        }
+        for rr in authority {
+            new_dns.add_authority_rr(Rc::new(RefCell::new(rr)));
+        }
+        if self.answer_policy == AnswerPolicy::Full {
+            for rr in additional {
+                new_dns.add_additional_rr(Rc::new(RefCell::new(rr)));
+            }
         }
 
         return new_dns;
     }
 }
 
+/// reload the master file of the path in the zones which loaded it.
+fn reload_zone_file(zones: &VecArcRf<Zones>, path: &Path) -> Result<(), Error> {
+    for zone in zones {
+        let file = zone
+            .read()
+            .unwrap()
+            .files()
+            .into_iter()
+            .find(|file| Path::new(file) == path);
+        let Some(file) = file else {
+            continue;
+        };
+        match Zones::from_file(&file) {
+            // the file is truncated before written, wait for the content
+            Ok(reloaded) if reloaded.is_empty() => {}
+            Ok(reloaded) => zone.write().unwrap().merge(reloaded),
+            Err(e) => {
+                return Err(e.context(format!(
+                    "reload the zone file {} failed, keep the old one",
+                    file
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// the seconds since the UNIX epoch, the time of the TSIG.
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// the DNSSEC records should not be returned to the client without the DO bit.
+fn is_dnssec_type(typ: Type) -> bool {
+    matches!(typ, TYPE_RRSIG | TYPE_NSEC | TYPE_NSEC3 | TYPE_DS)
+}
+
 // impl NameServerOperation for NameServer {
 //     fn find(&mut self, ques: &Question) -> Option<RcRf<RR>> {
 //         for zone in &self.zones {
@@ -129,3 +648,763 @@ impl NameServer {
 //         return None;
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::name_server::{observer::CountingObserver, zones::MemZones};
+    use crate::dns::{
+        rdata::{
+            opt::OPT,
+            sec::rrsig::RRSig,
+            soa::SOA,
+            tsig::{TSig, TsigRequest},
+        },
+        Class, CLASS_IN, ERR_BADKEY, ERR_BADSIG, ERR_BADTIME, RCODE_NXDOMAIN, TYPE_A, TYPE_AAAA,
+        TYPE_MX, TYPE_OPT, TYPE_SOA, TYPE_TSIG,
+    };
+    use std::{fs, net::Ipv4Addr, sync::Mutex};
+
+    /// build a question of the dotted name.
+    fn question(name: &str, qtype: Type, qclass: Class) -> Question {
+        let mut ques = Question::new();
+        for label in name.trim_end_matches('.').split('.') {
+            ques.with_name(label);
+        }
+        ques.with_qtype(qtype).with_qclass(qclass);
+
+        ques
+    }
+
+    struct DirZones(String);
+
+    impl ZonesOperation for DirZones {
+        fn calalog_zones(&mut self) -> Vec<Zones> {
+            vec![Zones::from_dir(&self.0).unwrap()]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watch_zones() {
+        let dir = std::env::temp_dir().join("rsdns_test_watch_zones");
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("example.com");
+        fs::write(&file, "www.example.com 1 1 60 10.0.0.1").unwrap();
+        let ns = NameServer::from(Box::new(DirZones(dir.to_str().unwrap().to_string())));
+        let errors = Arc::new(Mutex::new(vec![]));
+        let reported = errors.clone();
+        let _watcher = ns
+            .watch_zones(move |e| reported.lock().unwrap().push(e.to_string()))
+            .unwrap();
+
+        let query = || async {
+            let mut query = DNS::new();
+            query.with_ques("www.example.com", TYPE_A, CLASS_IN);
+            let resp = ns.query(query).await;
+            resp.answers()
+                .0
+                .iter()
+                .filter_map(|rr| match rr.borrow().rdata() {
+                    RDataType::A(a) => Some(a.0),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec![Ipv4Addr::new(10, 0, 0, 1)], query().await);
+
+        let wait_for = |ip: Ipv4Addr| async move {
+            for _ in 0..50 {
+                if query().await == vec![ip] {
+                    return true;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            }
+            false
+        };
+        fs::write(&file, "www.example.com 1 1 60 10.0.0.9").unwrap();
+        assert_eq!(true, wait_for(Ipv4Addr::new(10, 0, 0, 9)).await);
+
+        // the broken file is not loaded
+        fs::write(&file, "www.example.com 1 1 sixty 10.0.0.7").unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        assert_eq!(vec![Ipv4Addr::new(10, 0, 0, 9)], query().await);
+        assert_eq!(
+            true,
+            errors
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|e| e.contains("reload the zone file"))
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_chaos_query() {
+        let dir = std::env::temp_dir().join("rsdns_test_chaos_query");
+        fs::create_dir_all(&dir).unwrap();
+        let mut ns = NameServer::from(Box::new(DirZones(dir.to_str().unwrap().to_string())));
+        ns.with_version_string("rsdns 0.1.0").with_server_id("ns1");
+
+        let mut query = DNS::new();
+        query.with_ques("version.bind", TYPE_TXT, CLASS_CH);
+        let resp = ns.query(query).await;
+        assert_eq!(1, resp.answers().len());
+        let rr = resp.answers().0[0].borrow();
+        assert_eq!(CLASS_CH, rr.class());
+        assert_eq!(
+            &RDataType::TXT(TXT(vec!["rsdns 0.1.0".to_string()])),
+            rr.rdata()
+        );
+
+        let mut query = DNS::new();
+        query.with_ques("id.server", TYPE_TXT, CLASS_CH);
+        let resp = ns.query(query).await;
+        assert_eq!(
+            &RDataType::TXT(TXT(vec!["ns1".to_string()])),
+            resp.answers().0[0].borrow().rdata()
+        );
+
+        // the question is echoed as it was asked
+        let mut query = DNS::new();
+        query.with_ques("VERSION.Bind", TYPE_TXT, CLASS_CH);
+        let resp = ns.query(query).await;
+        assert_eq!(1, resp.answers().len());
+        assert_eq!("VERSION.Bind", resp.ques().0[0].qname().encode_to_str());
+        assert_eq!(
+            true,
+            resp.ques().0[0].matches(&question("version.bind", TYPE_TXT, CLASS_CH))
+        );
+
+        // the IN class is not answered by the responders
+        let mut query = DNS::new();
+        query.with_ques("version.bind", TYPE_TXT, CLASS_IN);
+        assert_eq!(0, ns.query(query).await.answers().len());
+
+        // the multiple version strings are the separate <character-string>s
+        ns.with_version_strings(vec!["rsdns".to_string(), "0.1.0".to_string()]);
+        let mut query = DNS::new();
+        query.with_ques("version.bind", TYPE_TXT, CLASS_CH);
+        let raw = ns.query(query).await.encode(false).unwrap();
+        let rdata = [
+            5, b'r', b's', b'd', b'n', b's', 5, b'0', b'.', b'1', b'.', b'0',
+        ];
+        assert_eq!(true, raw.ends_with(&rdata));
+        assert_eq!(
+            &RDataType::TXT(TXT(vec!["rsdns".to_string(), "0.1.0".to_string()])),
+            DNS::from(&raw).unwrap().answers().0[0].borrow().rdata()
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_answer_policy() {
+        let dir = std::env::temp_dir().join("rsdns_test_answer_policy");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("example.com"),
+            "example.com 15 1 60 10 mail.example.com\nmail.example.com 1 1 60 10.0.0.2",
+        )
+        .unwrap();
+        let mut ns = NameServer::from(Box::new(DirZones(dir.to_str().unwrap().to_string())));
+
+        let query = || {
+            let mut query = DNS::new();
+            query.with_ques("example.com", TYPE_MX, CLASS_IN);
+            query
+        };
+        let resp = ns.query(query()).await;
+        assert_eq!(1, resp.answers().len());
+        assert_eq!(TYPE_MX, resp.answers().0[0].borrow().typ());
+        assert_eq!(1, resp.additional().len());
+        match resp.additional().0[0].borrow().rdata() {
+            RDataType::A(a) => assert_eq!(Ipv4Addr::new(10, 0, 0, 2), a.0),
+            _ => panic!("not A record"),
+        }
+
+        ns.with_answer_policy(AnswerPolicy::Minimal);
+        let resp = ns.query(query()).await;
+        assert_eq!(1, resp.answers().len());
+        assert_eq!(0, resp.additional().len());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_any_policy() {
+        let dir = std::env::temp_dir().join("rsdns_test_any_policy");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("example.com"),
+            "example.com 15 1 60 10 mail.example.com\nexample.com 1 1 60 10.0.0.1",
+        )
+        .unwrap();
+        let mut ns = NameServer::from(Box::new(DirZones(dir.to_str().unwrap().to_string())));
+
+        let query = || {
+            let mut query = DNS::new();
+            query.with_ques("example.com", TYPE_ANY, CLASS_IN);
+            query
+        };
+        let resp = ns.query(query()).await;
+        assert_eq!(1, resp.answers().len());
+        let rr = resp.answers().0[0].borrow();
+        assert_eq!(TYPE_HINFO, rr.typ());
+        assert_eq!(60, rr.ttl());
+        assert_eq!(&RDataType::HInfo(HInfo::synthesized_rfc8482()), rr.rdata());
+        drop(rr);
+
+        ns.with_any_policy(AnyPolicy::AllRecords);
+        let mut resp = ns.query(query()).await;
+        assert_eq!(RCODE_NOERROR, resp.head().rcode());
+        assert_eq!(false, resp.answers().0.is_empty());
+        assert_eq!(
+            true,
+            resp.answers()
+                .0
+                .iter()
+                .all(|rr| rr.borrow().typ() != TYPE_HINFO)
+        );
+
+        ns.with_any_policy(AnyPolicy::Refuse);
+        let mut resp = ns.query(query()).await;
+        assert_eq!(RCODE_REFUSED, resp.head().rcode());
+        assert_eq!(0, resp.answers().len());
+
+        // the other types are not affected
+        let mut query = DNS::new();
+        query.with_ques("example.com", TYPE_A, CLASS_IN);
+        let mut resp = ns.query(query).await;
+        assert_eq!(RCODE_NOERROR, resp.head().rcode());
+        assert_eq!(1, resp.answers().len());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_nodata_soa() {
+        let dir = std::env::temp_dir().join("rsdns_test_nodata_soa");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("example.com"),
+            "example.com 6 1 3600 ns1.example.com admin.example.com 1 7200 3600 1209600 300\nwww.example.com 1 1 60 10.0.0.1",
+        )
+        .unwrap();
+        let ns = NameServer::from(Box::new(DirZones(dir.to_str().unwrap().to_string())));
+
+        let mut query = DNS::new();
+        query.with_ques("www.example.com", TYPE_AAAA, CLASS_IN);
+        let mut resp = ns.query(query).await;
+        assert_eq!(RCODE_NOERROR, resp.head().rcode());
+        assert_eq!(0, resp.answers().len());
+        assert_eq!(1, resp.authority().len());
+        let soa = resp.authority().0[0].borrow();
+        assert_eq!("example.com", soa.name());
+        assert_eq!(TYPE_SOA, soa.typ());
+        assert_eq!(300, soa.ttl());
+        drop(soa);
+
+        assert_eq!(true, resp.head().aa());
+
+        // the A record is still answered without SOA
+        let mut query = DNS::new();
+        query.with_ques("www.example.com", TYPE_A, CLASS_IN);
+        let resp = ns.query(query).await;
+        assert_eq!(1, resp.answers().len());
+        assert_eq!(0, resp.authority().len());
+
+        // the name of the other case is answered, and the question is echoed as it was asked
+        let mut query = DNS::new();
+        query.with_ques("WWW.Example.com", TYPE_A, CLASS_IN);
+        let resp = ns.query(query).await;
+        assert_eq!(1, resp.answers().len());
+        assert_eq!("WWW.Example.com", resp.ques().0[0].qname().encode_to_str());
+        assert_eq!(
+            true,
+            resp.ques().0[0].matches(&question("www.example.com", TYPE_A, CLASS_IN))
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_nxdomain_soa() {
+        let dir = std::env::temp_dir().join("rsdns_test_nxdomain_soa");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("example.com"),
+            "example.com 6 1 3600 ns1.example.com admin.example.com 1 7200 3600 1209600 300\nwww.example.com 1 1 60 10.0.0.1",
+        )
+        .unwrap();
+        let ns = NameServer::from(Box::new(DirZones(dir.to_str().unwrap().to_string())));
+
+        let mut query = DNS::new();
+        query.with_ques("nope.example.com", TYPE_A, CLASS_IN);
+        let mut resp = ns.query(query).await;
+        assert_eq!(RCODE_NXDOMAIN, resp.head().rcode());
+        assert_eq!(true, resp.head().aa());
+        assert_eq!(0, resp.answers().len());
+        assert_eq!(1, resp.authority().len());
+        assert_eq!(TYPE_SOA, resp.authority().0[0].borrow().typ());
+        assert_eq!("example.com", resp.authority().0[0].borrow().name());
+
+        // out of the served zones
+        let mut query = DNS::new();
+        query.with_ques("www.example.org", TYPE_A, CLASS_IN);
+        let mut resp = ns.query(query).await;
+        assert_eq!(RCODE_NOERROR, resp.head().rcode());
+        assert_eq!(0, resp.authority().len());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_query() {
+        let dir = std::env::temp_dir().join("rsdns_test_concurrent_query");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("baidu.com"), "baidu.com 1 1 60 10.0.0.1").unwrap();
+        let ns = Arc::new(NameServer::from(Box::new(DirZones(
+            dir.to_str().unwrap().to_string(),
+        ))));
+
+        let mut handles = vec![];
+        for _ in 0..8 {
+            let ns = ns.clone();
+            handles.push(tokio::spawn(async move {
+                let mut query = DNS::new();
+                query.with_ques("baidu.com", TYPE_A, CLASS_IN);
+                ns.query(query).await
+            }));
+        }
+        for handle in handles {
+            let resp = handle.await.unwrap();
+            let rrs = &resp.answers().0;
+            assert_eq!(1, rrs.len());
+            let ip = match rrs[0].borrow().rdata() {
+                RDataType::A(a) => a.0,
+                _ => panic!("not A record"),
+            };
+            assert_eq!(Ipv4Addr::new(10, 0, 0, 1), ip);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dnssec_stripping() {
+        let mut rrsig = RR::new();
+        rrsig
+            .with_name("www.example.com")
+            .with_type(TYPE_RRSIG)
+            .with_class(CLASS_IN)
+            .with_ttl(60)
+            .with_rdata(RDataType::RRSig(RRSig::new()));
+        let mut ns = NameServer::from(Box::new(MemZones(vec![
+            RR::a("www.example.com", 60, Ipv4Addr::new(10, 0, 0, 1)),
+            rrsig,
+        ])));
+        ns.with_any_policy(AnyPolicy::AllRecords);
+
+        let query = |qtype, dnssec_ok| {
+            let mut query = DNS::new();
+            query.with_ques("www.example.com", qtype, CLASS_IN);
+            if dnssec_ok {
+                let mut opt = RR::new();
+                opt.with_type(TYPE_OPT)
+                    .with_rdata(RDataType::OPT(OPT::new()))
+                    .convert_pseudo()
+                    .unwrap()
+                    .with_udp_payload(1232)
+                    .with_dnssec_ok(true);
+                query.add_additional_rr(Rc::new(RefCell::new(opt)));
+            }
+            query
+        };
+        let types = |resp: &DNS| -> Vec<Type> {
+            resp.answers()
+                .0
+                .iter()
+                .map(|rr| rr.borrow().typ())
+                .collect()
+        };
+
+        // no DO bit, the RRSIG is stripped
+        let resp = ns.query(query(TYPE_ANY, false)).await;
+        assert_eq!(vec![TYPE_A], types(&resp));
+        assert_eq!(true, resp.edns().is_none());
+
+        let resp = ns.query(query(TYPE_ANY, true)).await;
+        assert_eq!(vec![TYPE_A, TYPE_RRSIG], types(&resp));
+        assert_eq!(true, resp.dnssec_ok());
+        let opt = resp.edns().unwrap();
+        assert_eq!(
+            DEFAULT_UDP_PAYLOAD,
+            opt.borrow_mut().convert_pseudo().unwrap().udp_payload()
+        );
+
+        // queried explicitly
+        let resp = ns.query(query(TYPE_RRSIG, false)).await;
+        assert_eq!(vec![TYPE_RRSIG], types(&resp));
+
+        ns.with_dnssec_stripping(false);
+        let resp = ns.query(query(TYPE_ANY, false)).await;
+        assert_eq!(vec![TYPE_A, TYPE_RRSIG], types(&resp));
+    }
+
+    #[tokio::test]
+    async fn test_tsig_error_response() {
+        let key = TsigKey::new("key.example.com", tsig::HMAC_SHA256, b"secret");
+        let mut ns = NameServer::from(Box::new(MemZones(vec![RR::a(
+            "www.example.com",
+            60,
+            Ipv4Addr::new(10, 0, 0, 1),
+        )])));
+        ns.with_tsig_key(key.clone());
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let query = |key: &TsigKey, time_signed: u64| {
+            let mut dns = DNS::new();
+            dns.head().with_id(0x2845);
+            dns.with_ques("www.example.com", TYPE_A, CLASS_IN);
+            let mut tsig = TSig::new();
+            tsig.time_signed = time_signed;
+            tsig::sign(&mut dns, key, &mut tsig).unwrap();
+            (DNS::from(&dns.encode(true).unwrap()).unwrap(), tsig.mac)
+        };
+
+        // the response to the verified request is signed with the request MAC
+        let (signed, request_mac) = query(&key, now);
+        let mut resp = ns.query(signed).await;
+        let mut resp = DNS::from(&resp.encode(true).unwrap()).unwrap();
+        assert_eq!(RCODE_NOERROR, resp.head().rcode());
+        assert_eq!(1, resp.answers().len());
+        assert_eq!(
+            TYPE_TSIG,
+            resp.additional().0.last().unwrap().borrow().typ()
+        );
+        assert_eq!(
+            true,
+            tsig::verify_response(&resp, &key, &request_mac).is_ok()
+        );
+
+        let other_secret = TsigKey::new("key.example.com", tsig::HMAC_SHA256, b"other");
+        let other_name = TsigKey::new("other.example.com", tsig::HMAC_SHA256, b"secret");
+        for (query, error) in [
+            (query(&other_name, now), ERR_BADKEY),
+            (query(&other_secret, now), ERR_BADSIG),
+            (query(&key, now - 3600), ERR_BADTIME),
+        ] {
+            let (query, request_mac) = query;
+            let mut resp = ns.query(query).await;
+            let mut resp = DNS::from(&resp.encode(true).unwrap()).unwrap();
+            assert_eq!(RCODE_NOTAUTH, resp.head().rcode());
+            assert_eq!(true, resp.answers().0.is_empty());
+            let rr = resp.additional().0.last().unwrap().borrow();
+            assert_eq!(TYPE_TSIG, rr.typ());
+            assert_eq!(CLASS_ANY, rr.class());
+            let tsig = match rr.rdata() {
+                RDataType::TSig(tsig) => tsig,
+                rdata => panic!("not a TSIG rdata: {:?}", rdata),
+            };
+            assert_eq!(error, tsig.error);
+            assert_eq!(tsig::HMAC_SHA256, tsig.algorithm_name);
+            assert_eq!(0x2845, tsig.original_id);
+            if error == ERR_BADTIME {
+                // signed by the request key, the time signed is the request's
+                assert_eq!(now - 3600, tsig.time_signed);
+                let mut server_time = [0_u8; 8];
+                server_time[2..].copy_from_slice(&tsig.other_data);
+                assert_eq!(true, u64::from_be_bytes(server_time) >= now);
+                drop(rr);
+                assert_eq!(
+                    true,
+                    tsig::verify_response(&resp, &key, &request_mac).is_ok()
+                );
+            } else {
+                assert_eq!(true, tsig.mac.is_empty());
+                assert_eq!(true, tsig.other_data.is_empty());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tsig_encode_udp() {
+        let key = TsigKey::new("key.example.com", tsig::HMAC_SHA256, b"secret");
+        let rrs = (0..50)
+            .map(|i| RR::a("www.example.com", 60, Ipv4Addr::new(192, 0, 2, i)))
+            .collect();
+        let mut ns = NameServer::from(Box::new(MemZones(rrs)));
+        ns.with_tsig_key(key.clone());
+        let client: SocketAddr = "127.0.0.1:5353".parse().unwrap();
+
+        let query = || {
+            let mut dns = DNS::new();
+            dns.with_ques("www.example.com", TYPE_A, CLASS_IN);
+            let mut tsig = TSig::new();
+            tsig.with_time_signed();
+            tsig::sign(&mut dns, &key, &mut tsig).unwrap();
+            (DNS::from(&dns.encode(true).unwrap()).unwrap(), tsig.mac)
+        };
+
+        // the compressed response sent is verified
+        let (signed, request_mac) = query();
+        let (mut resp, verified) = ns.handle(client, signed).await;
+        let raw = ns.encode_udp(4096, &mut resp, &verified).unwrap();
+        let resp = DNS::from(&raw).unwrap();
+        assert_eq!(false, resp.header().tc());
+        assert_eq!(50, resp.answers().len());
+        assert_eq!(
+            true,
+            tsig::verify_response(&resp, &key, &request_mac).is_ok()
+        );
+
+        // the truncated response keeps the TSIG RR signed again
+        let (signed, request_mac) = query();
+        let (mut resp, verified) = ns.handle(client, signed).await;
+        let raw = ns.encode_udp(512, &mut resp, &verified).unwrap();
+        assert_eq!(true, raw.len() <= 512);
+        let resp = DNS::from(&raw).unwrap();
+        assert_eq!(true, resp.header().tc());
+        assert_eq!(true, resp.answers().0.is_empty());
+        assert_eq!(
+            true,
+            tsig::verify_response(&resp, &key, &request_mac).is_ok()
+        );
+
+        // the response of the failed signing is SERVFAIL instead of the unsigned answer
+        let mut resp = DNS::new();
+        resp.with_ques("www.example.com", TYPE_A, CLASS_IN);
+        resp.add_answer_rr(Rc::new(RefCell::new(RR::a(
+            "www.example.com",
+            60,
+            Ipv4Addr::new(192, 0, 2, 1),
+        ))));
+        let unknown = TsigKey::new("key.example.com", "hmac-unknown", b"secret");
+        let verified = Ok(Some(TsigRequest {
+            key: unknown,
+            request: TSig::new(),
+        }));
+        let resp = DNS::from(&ns.encode_udp(512, &mut resp, &verified).unwrap()).unwrap();
+        assert_eq!(RCODE_SERVFAIL, resp.header().rcode());
+        assert_eq!(true, resp.answers().0.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_round_robin() {
+        let a = |last: u8| RR::a("www.example.com", 60, Ipv4Addr::new(10, 0, 0, last));
+        let mut ns = NameServer::from(Box::new(MemZones(vec![a(1), a(2), a(3)])));
+        ns.with_round_robin(true);
+
+        let mut firsts = vec![];
+        for _ in 0..3 {
+            let mut query = DNS::new();
+            query.with_ques("www.example.com", TYPE_A, CLASS_IN);
+            let resp = ns.query(query).await;
+            let mut addrs = resp.a_records();
+            firsts.push(addrs[0]);
+            addrs.sort();
+            assert_eq!(
+                vec![
+                    Ipv4Addr::new(10, 0, 0, 1),
+                    Ipv4Addr::new(10, 0, 0, 2),
+                    Ipv4Addr::new(10, 0, 0, 3)
+                ],
+                addrs
+            );
+        }
+        firsts.sort();
+        firsts.dedup();
+        assert_eq!(3, firsts.len());
+    }
+
+    #[tokio::test]
+    async fn test_query_observer() {
+        let observer = Arc::new(CountingObserver::new());
+        let mut ns = NameServer::from(Box::new(MemZones(vec![RR::a(
+            "www.example.com",
+            60,
+            Ipv4Addr::new(10, 0, 0, 1),
+        )])));
+        ns.with_observer(Box::new(observer.clone()));
+
+        let mut query = DNS::new();
+        query.with_ques("www.example.com", TYPE_A, CLASS_IN);
+        let client: SocketAddr = "127.0.0.1:5353".parse().unwrap();
+        let (resp, _) = ns.handle(client, query).await;
+        assert_eq!(1, resp.answers().len());
+
+        assert_eq!(1, observer.queries());
+        assert_eq!(1, observer.responses());
+        assert_eq!(1, observer.type_count(TYPE_A, CLASS_IN));
+        assert_eq!(0, observer.type_count(TYPE_MX, CLASS_IN));
+        assert_eq!(1, observer.rcode_count(RCODE_NOERROR));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        let mut soa = SOA::new();
+        soa.mname = "ns1.example.com".to_string();
+        soa.rname = "admin.example.com".to_string();
+        soa.minimum = 300;
+        let mut ns = NameServer::from(Box::new(MemZones(vec![RR::soa("example.com", 3600, soa)])));
+        let mut config = RrlConfig::new();
+        config.with_nxdomains_per_second(5).with_slip(2);
+        ns.with_rate_limit(config);
+
+        let client: IpAddr = "192.0.2.1".parse().unwrap();
+        let (mut sent, mut truncated, mut dropped) = (0, 0, 0);
+        for _ in 0..20 {
+            let mut query = DNS::new();
+            query.with_ques("nx.example.com", TYPE_A, CLASS_IN);
+            let resp = ns.query(query).await;
+            match ns.rate_limit(client, resp) {
+                Some(mut resp) if resp.header().tc() => {
+                    assert_eq!(RCODE_NXDOMAIN, resp.head().rcode());
+                    assert_eq!(true, resp.authority().0.is_empty());
+                    truncated += 1;
+                }
+                Some(mut resp) => {
+                    assert_eq!(RCODE_NXDOMAIN, resp.head().rcode());
+                    sent += 1;
+                }
+                None => dropped += 1,
+            }
+        }
+        assert_eq!(5, sent);
+        assert_eq!(true, truncated > 0);
+        assert_eq!(true, dropped > 0);
+        assert_eq!(15, truncated + dropped);
+
+        // the other client is not limited
+        let mut query = DNS::new();
+        query.with_ques("nx.example.com", TYPE_A, CLASS_IN);
+        let resp = ns.query(query).await;
+        assert_eq!(
+            true,
+            ns.rate_limit("198.51.100.1".parse().unwrap(), resp)
+                .is_some_and(|resp| !resp.header().tc())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_encode_udp() {
+        let mut rrs = vec![];
+        for i in 0..150 {
+            let ip = Ipv4Addr::new(192, 0, 2, i);
+            if i < 50 {
+                rrs.push(RR::a("medium.example.com", 300, ip));
+            }
+            rrs.push(RR::a("large.example.com", 300, ip));
+        }
+        let ns = NameServer::from(Box::new(MemZones(rrs)));
+        let query = |name: &str, payload: Option<u16>| {
+            let mut query = DNS::new();
+            query.with_ques(name, TYPE_A, CLASS_IN);
+            if let Some(payload) = payload {
+                query.with_edns();
+                let opt = query.edns().unwrap();
+                opt.borrow_mut()
+                    .convert_pseudo()
+                    .unwrap()
+                    .with_udp_payload(payload);
+            }
+            query
+        };
+
+        // beyond the 512 of the query without EDNS
+        let mut resp = ns.query(query("medium.example.com", None)).await;
+        let raw = ns.encode_udp(512, &mut resp, &Ok(None)).unwrap();
+        assert_eq!(true, raw.len() <= 512);
+        let mut resp = DNS::from(&raw).unwrap();
+        assert_eq!(true, resp.head().tc());
+        assert_eq!(true, resp.answers().0.is_empty());
+        assert_eq!(1, resp.ques().0.len());
+
+        // fits in the payload of the EDNS query
+        let mut resp = ns.query(query("medium.example.com", Some(4096))).await;
+        let mut resp = DNS::from(&ns.encode_udp(4096, &mut resp, &Ok(None)).unwrap()).unwrap();
+        assert_eq!(false, resp.head().tc());
+        assert_eq!(50, resp.answers().0.len());
+
+        // beyond the payload of the server, the OPT is kept in the truncated response
+        let mut resp = ns.query(query("large.example.com", Some(4096))).await;
+        let raw = ns.encode_udp(4096, &mut resp, &Ok(None)).unwrap();
+        assert_eq!(true, raw.len() <= DEFAULT_UDP_PAYLOAD as usize);
+        let mut resp = DNS::from(&raw).unwrap();
+        assert_eq!(true, resp.head().tc());
+        assert_eq!(true, resp.answers().0.is_empty());
+        assert_eq!(true, resp.edns().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_serve_udp_rate_limit() {
+        let mut soa = SOA::new();
+        soa.mname = "ns1.example.com".to_string();
+        soa.rname = "admin.example.com".to_string();
+        soa.minimum = 300;
+        let mut ns = NameServer::from(Box::new(MemZones(vec![RR::soa("example.com", 3600, soa)])));
+        let mut config = RrlConfig::new();
+        config.with_nxdomains_per_second(1).with_slip(2);
+        ns.with_rate_limit(config);
+
+        let client: IpAddr = "192.0.2.1".parse().unwrap();
+        let nxdomain = || async {
+            let mut query = DNS::new();
+            query.with_ques("nx.example.com", TYPE_A, CLASS_IN);
+            ns.query(query).await
+        };
+        let now = Instant::now();
+        let (mut sent, mut truncated) = (0, 0);
+        for _ in 0..10 {
+            let Some(mut resp) = ns.rate_limit_at(client, nxdomain().await, now) else {
+                continue;
+            };
+            assert_eq!(RCODE_NXDOMAIN, resp.head().rcode());
+            if resp.header().tc() {
+                assert_eq!(0, resp.authority().len());
+                truncated += 1;
+            } else {
+                sent += 1;
+            }
+        }
+        assert_eq!(1, sent);
+        // every second suppressed one is truncated, the others are dropped
+        assert_eq!(4, truncated);
+        // refilled
+        let later = now + std::time::Duration::from_secs(1);
+        let resp = ns.rate_limit_at(client, nxdomain().await, later).unwrap();
+        assert_eq!(false, resp.header().tc());
+
+        // the server socket is bound before serving, so no query is lost
+        let mut config = RrlConfig::new();
+        config.with_nxdomains_per_second(1).with_slip(1);
+        ns.with_rate_limit(config);
+        let server = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let ns: &'static NameServer = Box::leak(Box::new(ns));
+        tokio::spawn(ns.serve_udp(server));
+
+        let sock = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        sock.connect(addr).await.unwrap();
+        // the unparsable packet is skipped, the server keeps serving
+        sock.send(&[0u8; 3]).await.unwrap();
+        let mut buff = vec![0u8; u16::MAX as usize];
+        for id in 0..3 {
+            let mut query = DNS::new();
+            query.with_ques("nx.example.com", TYPE_A, CLASS_IN);
+            query.head().with_id(id);
+            sock.send(&query.encode(false).unwrap()).await.unwrap();
+            // the suppressed responses are all truncated with the slip of 1, none is dropped
+            let size =
+                tokio::time::timeout(std::time::Duration::from_secs(5), sock.recv(&mut buff))
+                    .await
+                    .unwrap()
+                    .unwrap();
+            let mut resp = DNS::from(&buff[..size]).unwrap();
+            assert_eq!(id, resp.head().id());
+            assert_eq!(RCODE_NXDOMAIN, resp.head().rcode());
+            // the bucket of the client starts full
+            if id == 0 {
+                assert_eq!(false, resp.header().tc());
+            }
+        }
+    }
+}
@@ -120,14 +120,20 @@ impl DomainTree {
 
     pub fn push(&mut self, domain: &str) {
         if !domain.contains(".") {
-            self.leaves.push(Rc::new(RefCell::new(DomainTree {
-                owner: domain.to_string(),
-                leaves: vec![],
-                rr: None,
-            })));
-            // 排序
-            self.leaves
-                .sort_by(|a, b| a.borrow().owner.cmp(&b.borrow().owner));
+            // the pushed leaf is kept, the sorted position is found instead of sorting on every push
+            if let Err(pos) = self
+                .leaves
+                .binary_search_by(|probe| probe.borrow().owner.as_str().cmp(domain))
+            {
+                self.leaves.insert(
+                    pos,
+                    Rc::new(RefCell::new(DomainTree {
+                        owner: domain.to_string(),
+                        leaves: vec![],
+                        rr: None,
+                    })),
+                );
+            }
             return;
         }
 
@@ -151,7 +157,7 @@ impl DomainTree {
                         .unwrap()
                         .push(names.next().unwrap());
                 }
-                Err(_) => {
+                Err(pos) => {
                     // 未找到，新起一个DomainTree
                     let mut _leaf = DomainTree {
                         owner: first.unwrap().to_string(),
@@ -159,10 +165,7 @@ impl DomainTree {
                         rr: None,
                     };
                     _leaf.push(names.next().unwrap());
-                    self.leaves.push(Rc::new(RefCell::new(_leaf)));
-                    // 排序
-                    self.leaves
-                        .sort_by(|a, b| a.borrow().owner.cmp(&b.borrow().owner));
+                    self.leaves.insert(pos, Rc::new(RefCell::new(_leaf)));
                 }
             }
         }
@@ -258,18 +261,16 @@ impl DomainTree {
         None
     }
 
+    /// get_all_rrs: the RRs of the node and all its subdomains in the canonical name order, the node
+    /// comes before its subdomains, and the subdomains are ordered by the leaves which are sorted by the label.
+    ///
+    /// ref: https://www.rfc-editor.org/rfc/rfc4034#section-6.1
     pub fn get_all_rrs(&self) -> VecRcRf<RR> {
-        let mut list = vec![];
+        let mut list: VecRcRf<RR> = self.rr.iter().cloned().collect();
         for leaf in &self.leaves {
-            let leaf_rrs = leaf.clone().borrow().get_all_rrs();
-            if leaf_rrs.len() == 0 {
-                continue;
-            }
-            list.extend(leaf.clone().borrow().get_all_rrs());
-        }
-        if self.rr.is_some() {
-            return vec![self.rr.as_ref().unwrap().clone()];
+            list.extend(leaf.borrow().get_all_rrs());
         }
+
         list
     }
 }
@@ -338,4 +339,66 @@ mod tests {
         rr = tree.get_rr("baidu1.com");
         assert_eq!(true, rr.is_none());
     }
+
+    #[test]
+    pub fn test_domaintree_ip6_arpa() {
+        // the 32 nibble labels of 2001:db8::1 and 2001:db8::2 share all but the first label
+        let names = [
+            "1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa",
+            "2.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa",
+        ];
+        let mut tree = DomainTree::new();
+        for name in names {
+            // the repeated push does not duplicate the nodes
+            tree.push(name);
+            tree.push(name);
+            let mut rr = RR::new();
+            rr.with_name(name);
+            tree.set_rr(name, Rc::new(RefCell::new(rr)));
+        }
+        assert_eq!(1, tree.leaves.len());
+        for name in names {
+            assert_eq!(name, tree.get_rr(name).unwrap().borrow().name());
+        }
+        assert_eq!(
+            true,
+            tree.get_rr("3.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa")
+                .is_none()
+        );
+        assert_eq!(2, tree.get_all_rrs().len());
+    }
+
+    #[test]
+    pub fn test_domaintree_get_all_rrs() {
+        let mut tree = DomainTree::new();
+        let domains = [
+            "baidu.com",
+            "google.com",
+            "www.baidu.com",
+            "a.baidu.com",
+            "baidu.cn",
+        ];
+        for domain in domains {
+            tree.push(domain);
+            let mut rr = RR::new();
+            rr.with_name(domain);
+            tree.set_rr(domain, Rc::new(RefCell::new(rr)));
+        }
+
+        let names: Vec<String> = tree
+            .get_all_rrs()
+            .iter()
+            .map(|rr| rr.borrow().name().to_string())
+            .collect();
+        assert_eq!(
+            vec![
+                "baidu.cn",
+                "baidu.com",
+                "a.baidu.com",
+                "www.baidu.com",
+                "google.com"
+            ],
+            names
+        );
+    }
 }
@@ -1,33 +1,54 @@
-use super::DomainTree;
+use super::SyncDomainTree;
 use crate::{
     dns::{
-        rdata::{a::A, RDataType},
-        RcRf, RR, TYPE_A,
+        rdata::{
+            a::A, aaaa::AAAA, cname::CName, hinfo::HInfo, mx::MX, ns::NS, ptr::PTR, soa::SOA,
+            txt::TXT, RDataType,
+        },
+        type_mnemonic, ArcRf, Class, Type, VecArcRf, RR, TYPE_A, TYPE_AAAA, TYPE_CNAME, TYPE_HINFO,
+        TYPE_MX, TYPE_NS, TYPE_PTR, TYPE_SOA, TYPE_TXT,
     },
-    util::{decode_name, encode_name},
+    util::decode_name,
 };
 use anyhow::{anyhow, Error};
 use std::{
-    cell::RefCell,
     fs::{self},
-    net::Ipv4Addr,
-    rc::Rc,
+    net::IpAddr,
     str::FromStr,
+    sync::{Arc, RwLock},
 };
 
+/// the types loaded from the master file, the RRs of the other types are not written into it, so
+/// the file written is always loaded back.
+pub const MASTER_FILE_TYPES: [Type; 9] = [
+    TYPE_A, TYPE_AAAA, TYPE_NS, TYPE_MX, TYPE_SOA, TYPE_CNAME, TYPE_PTR, TYPE_TXT, TYPE_HINFO,
+];
+
+/// check_master_file_type: error if the type of the rr is not one of the [`MASTER_FILE_TYPES`].
+pub fn check_master_file_type(rr: &RR) -> Result<(), Error> {
+    if MASTER_FILE_TYPES.contains(&rr.typ()) {
+        return Ok(());
+    }
+    Err(anyhow!(
+        "the master file does not support the type {}: {}",
+        type_mnemonic(rr.typ()),
+        rr
+    ))
+}
+
 /**
  * Default Master Files
  */
 pub struct DefaultMasterFiles {
     mf: String,
-    tree: DomainTree,
+    tree: SyncDomainTree,
 }
 
 impl DefaultMasterFiles {
     pub fn new(mf: &str) -> Self {
         Self {
             mf: mf.to_string(),
-            tree: DomainTree::new(),
+            tree: SyncDomainTree::new(),
         }
     }
 
@@ -42,10 +63,18 @@ impl DefaultMasterFiles {
 
         let parse_line = |line_data: &str| -> Result<RR, Error> {
             let sigment: Vec<&str> = line_data.split(' ').collect();
+            if sigment.len() < 4 {
+                return Err(anyhow!(
+                    "lack of the name, type, class or ttl: {}",
+                    line_data
+                ));
+            }
             let name = sigment.get(0).unwrap().to_string();
             let typ = sigment.get(1).unwrap().to_string().parse::<u16>()?;
             let class = sigment.get(2).unwrap().to_string().parse::<u16>()?;
             let ttl: u32 = sigment.get(3).unwrap().to_string().parse::<u32>()?;
+            // the <character-string>s may contain the spaces, so the rdata is the rest of the line
+            let text = line_data.splitn(5, ' ').nth(4).unwrap_or_default();
 
             let mut rr = RR::new();
             rr.with_name(decode_name(name.as_str()))
@@ -54,10 +83,80 @@ impl DefaultMasterFiles {
                 .with_ttl(ttl);
 
             match typ {
-                TYPE_A => {
-                    let ipv4 = Ipv4Addr::from_str(sigment.get(4).unwrap())?;
-                    let a = A::new(ipv4);
-                    rr.with_rdata(RDataType::A(a));
+                TYPE_A | TYPE_AAAA => {
+                    let address = sigment.get(4).ok_or(anyhow!("lack of address"))?;
+                    let ip = IpAddr::from_str(address)
+                        .map_err(|_| anyhow!("invalid ip address: {}", address))?;
+                    match (typ, ip) {
+                        (TYPE_A, IpAddr::V4(ipv4)) => rr.with_rdata(RDataType::A(A::new(ipv4))),
+                        (TYPE_AAAA, IpAddr::V6(ipv6)) => {
+                            rr.with_rdata(RDataType::AAAA(AAAA::new(ipv6)))
+                        }
+                        (TYPE_A, _) => {
+                            return Err(anyhow!("the A record needs an IPv4 address: {}", address))
+                        }
+                        _ => {
+                            return Err(anyhow!(
+                                "the AAAA record needs an IPv6 address: {}",
+                                address
+                            ))
+                        }
+                    };
+
+                    return Ok(rr);
+                }
+                TYPE_NS => {
+                    let ns = NS(
+                        decode_name(sigment.get(4).ok_or(anyhow!("lack of nsdname"))?).to_string(),
+                    );
+                    rr.with_rdata(RDataType::NS(ns));
+
+                    return Ok(rr);
+                }
+                TYPE_CNAME | TYPE_PTR => {
+                    let target = decode_name(sigment.get(4).ok_or(anyhow!("lack of domain name"))?);
+                    match typ {
+                        TYPE_CNAME => rr.with_rdata(RDataType::CName(CName(target.to_string()))),
+                        _ => rr.with_rdata(RDataType::PTR(PTR(target.to_string()))),
+                    };
+
+                    return Ok(rr);
+                }
+                TYPE_TXT => {
+                    rr.with_rdata(RDataType::TXT(TXT::from_str(text)?));
+
+                    return Ok(rr);
+                }
+                TYPE_HINFO => {
+                    rr.with_rdata(RDataType::HInfo(HInfo::from_str(text)?));
+
+                    return Ok(rr);
+                }
+                TYPE_MX => {
+                    let mut mx = MX::new();
+                    mx.preference = sigment
+                        .get(4)
+                        .ok_or(anyhow!("lack of preference"))?
+                        .parse::<u16>()?;
+                    mx.exchange =
+                        decode_name(sigment.get(5).ok_or(anyhow!("lack of exchange"))?).to_string();
+                    rr.with_rdata(RDataType::MX(mx));
+
+                    return Ok(rr);
+                }
+                TYPE_SOA => {
+                    if sigment.len() < 11 {
+                        return Err(anyhow!("lack of soa fields"));
+                    }
+                    let mut soa = SOA::new();
+                    soa.mname = decode_name(sigment[4]).to_string();
+                    soa.rname = decode_name(sigment[5]).to_string();
+                    soa.serial = sigment[6].parse::<u32>()?;
+                    soa.refresh = sigment[7].parse::<u32>()?;
+                    soa.retry = sigment[8].parse::<u32>()?;
+                    soa.expire = sigment[9].parse::<u32>()?;
+                    soa.minimum = sigment[10].parse::<u32>()?;
+                    rr.with_rdata(RDataType::SOA(soa));
 
                     return Ok(rr);
                 }
@@ -66,36 +165,135 @@ impl DefaultMasterFiles {
         };
 
         while let Some(line) = line_iter.next() {
-            let rr = parse_line(line)?;
-            let name = rr.name().to_string();
-            self.tree.set_rr(name.as_str(), Rc::new(RefCell::new(rr)));
+            // the names and TTLs of the lines are absolute, the $ORIGIN and $TTL directives are skipped.
+            if line.trim().is_empty() || line.starts_with('$') {
+                continue;
+            }
+            self.insert(parse_line(line)?);
         }
 
         Ok(())
     }
 
+    /// insert: add the rr into the tree, the other RRs of its name are kept.
+    pub fn insert(&mut self, rr: RR) {
+        let name = rr.name().to_string();
+        self.tree.push(name.as_str());
+        self.tree.add_rr(name.as_str(), Arc::new(RwLock::new(rr)));
+    }
+
     pub fn encode(&mut self) -> Result<(), Error> {
         let mut content = "".to_owned();
         for rrc in &self.tree.get_all_rrs() {
-            let rr = rrc.as_ref().borrow();
-            content.push_str(encode_name(rr.name()));
-            content.push_str(&format!(" {}", rr.typ()));
-            content.push_str(&format!(" {}", rr.class()));
-            content.push_str(&format!(" {}", rr.ttl()));
-            content.push_str(&format!(" {}", rr.rdata().as_str()));
+            check_master_file_type(&rrc.read().unwrap())?;
+            content.push_str(&rrc.read().unwrap().presentation());
+            content.push('\n');
         }
         fs::write(self.mf.as_str(), content)?;
 
         Ok(())
     }
 
-    pub fn update(&mut self, domain: &str, rr: RcRf<RR>) -> Result<(), Error> {
+    pub fn update(&mut self, domain: &str, rr: ArcRf<RR>) -> Result<(), Error> {
         self.tree.set_rr(domain, rr);
 
         Ok(())
     }
 
-    pub fn query(&self, domain: &str) -> Option<RcRf<RR>> {
-        self.tree.get_rr(domain)
+    /// remove_rrset: remove the RRs of the name, type and class.
+    pub fn remove_rrset(&mut self, name: &str, typ: Type, class: Class) {
+        let rest: VecArcRf<RR> = self
+            .tree
+            .get_rrs(name)
+            .into_iter()
+            .filter(|rr| {
+                let rr = rr.read().unwrap();
+                rr.typ() != typ || rr.class() != class
+            })
+            .collect();
+        self.tree.set_rrs(name, rest);
+    }
+
+    /// all_rrs: all the RRs in the tree.
+    pub fn all_rrs(&self) -> VecArcRf<RR> {
+        self.tree.get_all_rrs()
+    }
+
+    /// query: all the RRs of the domain.
+    pub fn query(&self, domain: &str) -> VecArcRf<RR> {
+        self.tree.get_rrs(domain)
+    }
+
+    /// contains: whether the domain exists, including the empty non-terminal.
+    pub fn contains(&self, domain: &str) -> bool {
+        self.tree.contains(domain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    fn load(filename: &str, content: &str) -> Result<DefaultMasterFiles, Error> {
+        let dir = std::env::temp_dir().join("rsdns_test_master_file");
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join(filename);
+        fs::write(&file, content).unwrap();
+
+        let mut mf = DefaultMasterFiles::new(file.to_str().unwrap());
+        mf.decode()?;
+        Ok(mf)
+    }
+
+    #[test]
+    fn test_master_file_decode_address() {
+        let mf = load(
+            "address",
+            "www.example.com 1 1 60 10.0.0.1\nwww.example.com 28 1 60 2001:db8::1\n",
+        )
+        .unwrap();
+        let rrs = mf.query("www.example.com");
+        assert_eq!(2, rrs.len());
+        assert_eq!(
+            &RDataType::A(A::new(Ipv4Addr::new(10, 0, 0, 1))),
+            rrs[0].read().unwrap().rdata()
+        );
+        assert_eq!(
+            &RDataType::AAAA(AAAA::new(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1))),
+            rrs[1].read().unwrap().rdata()
+        );
+
+        // mismatched address family
+        assert_eq!(
+            true,
+            load("v6_under_a", "www.example.com 1 1 60 2001:db8::1").is_err()
+        );
+        assert_eq!(
+            true,
+            load("v4_under_aaaa", "www.example.com 28 1 60 10.0.0.1").is_err()
+        );
+    }
+
+    #[test]
+    fn test_master_file_encode() {
+        let mut mf = load("encode", "www.example.com. 1 1 60 10.0.0.1\n").unwrap();
+        assert_eq!(1, mf.query("www.example.com").len());
+        // the types not loaded by the master file are written too
+        mf.insert(RR::txt("www.example.com", 60, &["hello"]));
+        mf.insert(RR::cname("ftp.example.com", 60, "www.example.com"));
+        mf.encode().unwrap();
+
+        let content = fs::read_to_string(&mf.mf).unwrap();
+        let mut lines: Vec<&str> = content.lines().collect();
+        lines.sort();
+        assert_eq!(
+            vec![
+                "ftp.example.com. 5 1 60 www.example.com.",
+                "www.example.com. 1 1 60 10.0.0.1",
+                "www.example.com. 16 1 60 \"hello\"",
+            ],
+            lines
+        );
     }
 }
@@ -1,8 +1,10 @@
 pub mod domain_tree;
 mod master_file;
+pub mod sync_domain_tree;
 pub mod zone;
 use self::zone::Zones;
 pub use domain_tree::DomainTree;
+pub use sync_domain_tree::SyncDomainTree;
 
 /**
  * The trait that list the zones for NameServer
@@ -25,3 +27,18 @@ impl ZonesOperation for DefaultZones {
         todo!()
     }
 }
+
+/** the zones of the records in memory for the tests */
+#[cfg(test)]
+pub(crate) struct MemZones(pub Vec<crate::dns::RR>);
+
+#[cfg(test)]
+impl ZonesOperation for MemZones {
+    fn calalog_zones(&mut self) -> Vec<Zones> {
+        let mut zones = Zones::new();
+        for rr in self.0.drain(..) {
+            zones.insert_rr(rr);
+        }
+        vec![zones]
+    }
+}
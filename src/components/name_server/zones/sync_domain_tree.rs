@@ -0,0 +1,313 @@
+/*!
+The Send + Sync twin of [`DomainTree`](super::DomainTree).
+
+The nodes and the resource records are shared by [`ArcRf`] (`Arc<RwLock<T>>`) instead of
+`Rc<RefCell<T>>`, so the tree can be shared across the threads of a multi-threaded server.
+*/
+
+use crate::dns::{ArcRf, VecArcRf, RR};
+use std::sync::{Arc, RwLock};
+
+#[derive(Debug)]
+pub struct SyncDomainTree {
+    owner: String,
+    leaves: VecArcRf<SyncDomainTree>,
+    rrs: VecArcRf<RR>,
+}
+
+impl SyncDomainTree {
+    pub fn new() -> Self {
+        Self {
+            owner: ".".to_string(),
+            leaves: vec![],
+            rrs: vec![],
+        }
+    }
+
+    fn leaf(owner: &str) -> Self {
+        Self {
+            owner: owner.to_string(),
+            leaves: vec![],
+            rrs: vec![],
+        }
+    }
+
+    /// the labels are compared case-insensitively, the node keeps the case of its first push.
+    fn find(&self, owner: &str) -> Result<usize, usize> {
+        let lower = |label: &str| {
+            label
+                .bytes()
+                .map(|b| b.to_ascii_lowercase())
+                .collect::<Vec<_>>()
+        };
+        let owner = lower(owner);
+        self.leaves
+            .binary_search_by(|probe| lower(&probe.read().unwrap().owner).cmp(&owner))
+    }
+
+    /// push: add the nodes of the domain, the root name (`.` or empty) is the tree itself. The
+    /// existing node is reused and the new leaf is inserted at its sorted position, so the deep names
+    /// sharing the most labels (such as the 32 nibbles of ip6.arpa) share the nodes.
+    pub fn push(&mut self, domain: &str) {
+        if is_root(domain) {
+            return;
+        }
+        let mut names = domain.rsplitn(2, ".");
+        let first = names.next().unwrap();
+        let pos = match self.find(first) {
+            Ok(pos) => pos,
+            Err(pos) => {
+                self.leaves
+                    .insert(pos, Arc::new(RwLock::new(Self::leaf(first))));
+                pos
+            }
+        };
+        if let Some(rest) = names.next() {
+            self.leaves[pos].write().unwrap().push(rest);
+        }
+    }
+
+    /// set_rr: replace all the RRs of the domain with the rr.
+    pub fn set_rr(&mut self, domain: &str, rr: ArcRf<RR>) {
+        self.with_node(domain, |node| node.rrs = vec![rr]);
+    }
+
+    /// set_rrs: replace all the RRs of the domain with the rrs.
+    pub fn set_rrs(&mut self, domain: &str, rrs: VecArcRf<RR>) {
+        self.with_node(domain, |node| node.rrs = rrs);
+    }
+
+    /// add_rr: append the rr to the RRs of the domain.
+    pub fn add_rr(&mut self, domain: &str, rr: ArcRf<RR>) {
+        self.with_node(domain, |node| node.rrs.push(rr));
+    }
+
+    /// apply the f on the node of the domain, nothing happens if the domain is not pushed.
+    fn with_node(&mut self, domain: &str, f: impl FnOnce(&mut Self)) {
+        if is_root(domain) {
+            return f(self);
+        }
+        let mut names = domain.rsplitn(2, ".");
+        let first = names.next().unwrap();
+        if let Ok(pos) = self.find(first) {
+            let mut leaf = self.leaves[pos].write().unwrap();
+            match names.next() {
+                Some(rest) => leaf.with_node(rest, f),
+                None => f(&mut leaf),
+            }
+        }
+    }
+
+    /// get_rr: the first RR of the domain.
+    pub fn get_rr(&self, domain: &str) -> Option<ArcRf<RR>> {
+        self.get_rrs(domain).into_iter().next()
+    }
+
+    /// get_rrs: all the RRs of the domain.
+    pub fn get_rrs(&self, domain: &str) -> VecArcRf<RR> {
+        if is_root(domain) {
+            return self.rrs.clone();
+        }
+        let mut names = domain.rsplitn(2, ".");
+        let first = names.next().unwrap();
+        let pos = match self.find(first) {
+            Ok(pos) => pos,
+            Err(_) => return vec![],
+        };
+        let leaf = self.leaves[pos].read().unwrap();
+        match names.next() {
+            Some(rest) => leaf.get_rrs(rest),
+            None => leaf.rrs.clone(),
+        }
+    }
+
+    /// contains: whether the domain exists in the tree, which has the RRs or the subdomains. The empty
+    /// non-terminal (such as `example.com` of only `www.example.com`) exists without any RR.
+    pub fn contains(&self, domain: &str) -> bool {
+        if is_root(domain) {
+            return !self.rrs.is_empty() || !self.leaves.is_empty();
+        }
+        let mut names = domain.rsplitn(2, ".");
+        let first = names.next().unwrap();
+        let pos = match self.find(first) {
+            Ok(pos) => pos,
+            Err(_) => return false,
+        };
+        let leaf = self.leaves[pos].read().unwrap();
+        match names.next() {
+            Some(rest) => leaf.contains(rest),
+            None => !leaf.rrs.is_empty() || !leaf.leaves.is_empty(),
+        }
+    }
+
+    /// get_all_rrs: the RRs of the node and all its subdomains in the canonical name order, the node
+    /// comes before its subdomains, and the subdomains are ordered by the sorted leaves.
+    ///
+    /// ref: https://www.rfc-editor.org/rfc/rfc4034#section-6.1
+    pub fn get_all_rrs(&self) -> VecArcRf<RR> {
+        let mut list = self.rrs.clone();
+        for leaf in &self.leaves {
+            list.extend(leaf.read().unwrap().get_all_rrs());
+        }
+
+        list
+    }
+}
+
+fn is_root(domain: &str) -> bool {
+    domain.is_empty() || domain == "."
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SyncDomainTree;
+    use crate::dns::RR;
+    use std::{
+        sync::{Arc, RwLock},
+        thread,
+    };
+
+    #[test]
+    pub fn test_sync_domaintree_get_rr() {
+        let mut tree = SyncDomainTree::new();
+        tree.push("baidu.com");
+
+        let mut rr = RR::new();
+        rr.with_name("baidu.com")
+            .with_class(11)
+            .with_type(12)
+            .with_ttl(13);
+        tree.set_rr("baidu.com", Arc::new(RwLock::new(rr)));
+
+        let rr = tree.get_rr("baidu.com");
+        assert_eq!(true, rr.is_some());
+        assert_eq!(13, rr.unwrap().read().unwrap().ttl());
+        assert_eq!(true, tree.get_rr("baidu.com1").is_none());
+        assert_eq!(true, tree.get_rr("baidu1.com").is_none());
+        // case-insensitive
+        assert_eq!(true, tree.get_rr("BaiDu.COM").is_some());
+        tree.push("WWW.Baidu.com");
+        tree.add_rr("www.baidu.com", Arc::new(RwLock::new(RR::new())));
+        assert_eq!(true, tree.contains("www.baidu.com"));
+        assert_eq!(1, tree.get_rrs("www.BAIDU.com").len());
+    }
+
+    #[test]
+    pub fn test_sync_domaintree_add_rr() {
+        let mut tree = SyncDomainTree::new();
+        tree.push("baidu.com");
+        for ttl in [1, 2] {
+            let mut rr = RR::new();
+            rr.with_name("baidu.com").with_ttl(ttl);
+            tree.add_rr("baidu.com", Arc::new(RwLock::new(rr)));
+        }
+        assert_eq!(2, tree.get_rrs("baidu.com").len());
+        assert_eq!(1, tree.get_rr("baidu.com").unwrap().read().unwrap().ttl());
+        assert_eq!(2, tree.get_all_rrs().len());
+
+        let mut rr = RR::new();
+        rr.with_name("baidu.com").with_ttl(3);
+        tree.set_rr("baidu.com", Arc::new(RwLock::new(rr)));
+        assert_eq!(1, tree.get_rrs("baidu.com").len());
+        assert_eq!(0, tree.get_rrs("com").len());
+
+        // the root name is the tree itself
+        tree.push(".");
+        tree.add_rr(
+            ".",
+            Arc::new(RwLock::new(RR::ns(".", 60, "a.root-servers.net"))),
+        );
+        assert_eq!(1, tree.get_rrs("").len());
+        assert_eq!(1, tree.get_rrs(".").len());
+    }
+
+    #[test]
+    pub fn test_sync_domaintree_ip6_arpa() {
+        // the 32 nibble labels of 2001:db8::1 and 2001:db8::2 share all but the first label
+        let names = [
+            "1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa",
+            "2.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa",
+        ];
+        let mut tree = SyncDomainTree::new();
+        for name in names {
+            // the repeated push does not duplicate the nodes
+            tree.push(name);
+            tree.push(name);
+            let mut rr = RR::new();
+            rr.with_name(name);
+            tree.set_rr(name, Arc::new(RwLock::new(rr)));
+        }
+        assert_eq!(1, tree.leaves.len());
+        for name in names {
+            assert_eq!(name, tree.get_rr(name).unwrap().read().unwrap().name());
+        }
+        assert_eq!(
+            true,
+            tree.get_rr("3.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa")
+                .is_none()
+        );
+        assert_eq!(2, tree.get_all_rrs().len());
+    }
+
+    #[test]
+    pub fn test_sync_domaintree_get_all_rrs() {
+        let mut tree = SyncDomainTree::new();
+        let domains = [
+            "baidu.com",
+            "google.com",
+            "www.baidu.com",
+            "a.baidu.com",
+            "baidu.cn",
+        ];
+        for domain in domains {
+            tree.push(domain);
+            let mut rr = RR::new();
+            rr.with_name(domain);
+            tree.set_rr(domain, Arc::new(RwLock::new(rr)));
+        }
+
+        let names: Vec<String> = tree
+            .get_all_rrs()
+            .iter()
+            .map(|rr| rr.read().unwrap().name().to_string())
+            .collect();
+        assert_eq!(
+            vec![
+                "baidu.cn",
+                "baidu.com",
+                "a.baidu.com",
+                "www.baidu.com",
+                "google.com"
+            ],
+            names
+        );
+    }
+
+    #[test]
+    pub fn test_sync_domaintree_share_between_threads() {
+        let mut tree = SyncDomainTree::new();
+        for (i, domain) in ["baidu.com", "google.com"].iter().enumerate() {
+            tree.push(domain);
+            let mut rr = RR::new();
+            rr.with_name(domain).with_ttl(i as u32);
+            tree.set_rr(domain, Arc::new(RwLock::new(rr)));
+        }
+
+        let tree = Arc::new(RwLock::new(tree));
+        let handles: Vec<_> = (0..2)
+            .map(|i| {
+                let tree = tree.clone();
+                thread::spawn(move || {
+                    let domain = ["baidu.com", "google.com"][i];
+                    for _ in 0..100 {
+                        let rr = tree.read().unwrap().get_rr(domain).unwrap();
+                        assert_eq!(i as u32, rr.read().unwrap().ttl());
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+}
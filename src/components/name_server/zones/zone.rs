@@ -1,9 +1,21 @@
-use super::master_file::DefaultMasterFiles;
+use super::master_file::{check_master_file_type, DefaultMasterFiles};
 use crate::dns::question::Questions;
-use crate::dns::{Question, RcRf, VecRcRf, RR};
-use crate::util;
+use crate::dns::{
+    detect_cname_chain_loop, is_meta_class, is_meta_type,
+    rdata::{
+        soa::{serial_gt, SerialScheme},
+        RDataType,
+    },
+    Class, Question, RcRf, Type, VecArcRf, CLASS_ANY, RCODE_NXDOMAIN, RCODE_SERVFAIL, RR, TYPE_A,
+    TYPE_AAAA, TYPE_ANY, TYPE_CNAME, TYPE_DS, TYPE_NS, TYPE_NSEC, TYPE_RRSIG, TYPE_SOA,
+};
+use crate::{util, DNS};
 use anyhow::{Error, Ok};
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::rc::Rc;
+use std::{fmt, fs};
 
 /**
 - The definition of zone boundaries.
@@ -15,42 +27,1044 @@ use std::collections::HashMap;
 - Statements of the refresh policies desired.
 */
 
+/// the key prefix of the in-memory domain trees built by [`Zones::insert_rr`], followed by the class.
+const MEMORY_ZONE_PREFIX: &str = "memory:";
+
+/// the limit of the CNAME chain followed by [`Zones::answer`].
+const MAX_CNAME_CHAIN: usize = 16;
+
+/// ZoneError: the inconsistency found by [`Zones::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZoneError {
+    /// the zone has no SOA record.
+    MissingSoa { zone: String },
+    /// the zone has more than one SOA record.
+    MultipleSoa { zone: String, count: usize },
+    /// no NS record at the apex.
+    MissingApexNs { apex: String },
+    /// the CNAME coexists with other types at the name.
+    CnameConflict { name: String },
+    /// the record is not under the apex.
+    OutOfZone { apex: String, name: String },
+    /// the record of the meta type or class, which is only used in the questions.
+    MetaRecord {
+        name: String,
+        typ: Type,
+        class: Class,
+    },
+}
+
+impl fmt::Display for ZoneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZoneError::MissingSoa { zone } => write!(f, "the zone {} has no SOA", zone),
+            ZoneError::MultipleSoa { zone, count } => {
+                write!(f, "the zone {} has {} SOA records", zone, count)
+            }
+            ZoneError::MissingApexNs { apex } => write!(f, "no NS record at the apex {}", apex),
+            ZoneError::CnameConflict { name } => {
+                write!(f, "the CNAME coexists with other types at {}", name)
+            }
+            ZoneError::OutOfZone { apex, name } => {
+                write!(f, "the record {} is out of the zone {}", name, apex)
+            }
+            ZoneError::MetaRecord { name, typ, class } => write!(
+                f,
+                "the record {} of the meta type {} or class {}",
+                name, typ, class
+            ),
+        }
+    }
+}
+
 pub struct Zones {
     domains: HashMap<String, DefaultMasterFiles>, // domain: MF
+    serial_scheme: SerialScheme,
 }
 
 impl Zones {
     pub fn new() -> Self {
         Self {
             domains: HashMap::new(),
+            serial_scheme: SerialScheme::Increment,
         }
     }
 
+    /// with_serial_scheme: how the SOA SERIAL is bumped on the edits, [`SerialScheme::Increment`] by default.
+    pub fn with_serial_scheme(&mut self, scheme: SerialScheme) -> &mut Self {
+        self.serial_scheme = scheme;
+        return self;
+    }
+
     pub fn from_dir(dir: &str) -> Result<Self, Error> {
         let mut zones = Self::new();
 
         let filenames = util::visit_dirs(dir)?;
         for filename in filenames {
-            let mut mf = DefaultMasterFiles::new(filename.as_str());
-            mf.decode()?;
-            zones.domains.insert(filename, mf);
+            zones.merge(Self::from_file(&filename)?);
         }
 
         Ok(zones)
     }
 
-    pub fn get_rr(&self, quess: &Questions) -> VecRcRf<RR> {
+    /// from_file: the zones of a single master file.
+    pub fn from_file(filename: &str) -> Result<Self, Error> {
+        let mut zones = Self::new();
+        let mut mf = DefaultMasterFiles::new(filename);
+        mf.decode()?;
+        zones.domains.insert(filename.to_string(), mf);
+
+        Ok(zones)
+    }
+
+    /// files: the master files that the zones loaded from.
+    pub fn files(&self) -> Vec<String> {
+        self.domains
+            .keys()
+            .filter(|key| !key.starts_with(MEMORY_ZONE_PREFIX))
+            .cloned()
+            .collect()
+    }
+
+    /// is_empty: whether there is no record in the zones.
+    pub fn is_empty(&self) -> bool {
+        self.domains.values().all(|mf| mf.all_rrs().is_empty())
+    }
+
+    /// merge: take the master files of the other, the one of the same file is replaced (reloaded).
+    /// The SOA SERIAL of the reloaded file is bumped if it is not greater than the old one, so the
+    /// secondaries notice the change.
+    pub fn merge(&mut self, other: Zones) {
+        for (key, mf) in other.domains {
+            if let Some(old) = self
+                .domains
+                .get(&key)
+                .and_then(|old| soa_serial(&old.all_rrs()))
+            {
+                for rr in mf.all_rrs() {
+                    if let RDataType::SOA(soa) = rr.write().unwrap().rdata_mut() {
+                        if !serial_gt(soa.serial, old) {
+                            soa.serial = old;
+                            soa.bump_serial(self.serial_scheme);
+                        }
+                    }
+                }
+            }
+            self.domains.insert(key, mf);
+        }
+    }
+
+    /// bump_serial: bump the SERIAL of all the SOA records by the serial scheme.
+    pub fn bump_serial(&mut self) {
+        for mf in self.domains.values() {
+            for rr in mf.all_rrs() {
+                if let RDataType::SOA(soa) = rr.write().unwrap().rdata_mut() {
+                    soa.bump_serial(self.serial_scheme);
+                }
+            }
+        }
+    }
+
+    /// update_rr: replace the RRs of the same name, type and class with the rr, then bump the SERIAL of
+    /// the zone which the rr belongs to.
+    pub fn update_rr(&mut self, rr: RR) {
+        for mf in self.domains.values_mut() {
+            mf.remove_rrset(rr.name(), rr.typ(), rr.class());
+        }
+        let (name, class) = (rr.name().to_string(), rr.class());
+        self.insert_rr(rr);
+        self.bump_zone_serial(&name, class);
+    }
+
+    /// bump_zone_serial: bump the SERIAL of the SOA of the zone which the name belongs to, that is the
+    /// closest enclosing one, same as [`Zones::soa`]. The other zones are untouched.
+    fn bump_zone_serial(&mut self, name: &str, class: Class) {
+        let mut name = name.trim_end_matches('.');
+        loop {
+            let soas: VecArcRf<RR> = self
+                .domains
+                .values()
+                .flat_map(|mf| mf.query(name))
+                .filter(|rr| {
+                    let rr = rr.read().unwrap();
+                    rr.typ() == TYPE_SOA && rr.class() == class
+                })
+                .collect();
+            if !soas.is_empty() {
+                for rr in soas {
+                    if let RDataType::SOA(soa) = rr.write().unwrap().rdata_mut() {
+                        soa.bump_serial(self.serial_scheme);
+                    }
+                }
+                return;
+            }
+            match name.split_once('.') {
+                Some((_, parent)) => name = parent,
+                None => return,
+            }
+        }
+    }
+
+    /// insert_rr: add the rr into the in-memory domain tree of its class, the tree is created at the
+    /// first insertion, so the zone can be built without the master files.
+    pub fn insert_rr(&mut self, rr: RR) {
+        let key = format!("{}{}", MEMORY_ZONE_PREFIX, rr.class());
+        self.domains
+            .entry(key.clone())
+            .or_insert_with(|| DefaultMasterFiles::new(&key))
+            .insert(rr);
+    }
+
+    /// reverse_zone: the zones of the PTR records of the addresses, the PTR of each AAAA (or A) record
+    /// is owned by its name under `ip6.arpa` (or `in-addr.arpa`) and points to the owner of the record.
+    /// The other records are skipped, the SOA and NS of the reverse zone are inserted by the caller.
+    pub fn reverse_zone(rrs: &[RR]) -> Self {
+        let mut zones = Self::new();
+        for rr in rrs {
+            let ip = match rr.rdata() {
+                RDataType::AAAA(aaaa) => IpAddr::V6(aaaa.0),
+                RDataType::A(a) => IpAddr::V4(a.0),
+                _ => continue,
+            };
+            zones.insert_rr(RR::ptr(&util::reverse_name(ip), rr.ttl(), rr.name()));
+        }
+
+        zones
+    }
+
+    /**
+    validate: check the consistency of each zone before serving:
+    - exactly one SOA, its owner is the apex.
+    - NS records present at the apex.
+    - no CNAME coexisting with other types at a name, the DNSSEC RRSIG and NSEC are allowed. (RFC 4035 2.5)
+    - all records are in the zone.
+    - no record of the meta type or class, such as ANY and AXFR.
+
+    All the problems are reported.
+     */
+    pub fn validate(&self) -> Result<(), Vec<ZoneError>> {
+        let mut errs = vec![];
+        let mut keys: Vec<&String> = self.domains.keys().collect();
+        keys.sort();
+        for key in keys {
+            let rrs: Vec<RR> = self.domains[key]
+                .all_rrs()
+                .iter()
+                .map(|rr| rr.read().unwrap().clone())
+                .collect();
+            validate_zone(key, &rrs, &mut errs);
+        }
+
+        errs.is_empty().then_some(()).ok_or(errs)
+    }
+
+    /**
+    to_master_file: write all the RRs of the zones into the master file of the path, which can be
+    loaded by [`Zones::from_file`]. The SOA is the first record, and the header is:
+    - `$ORIGIN`: the owner of the SOA, the root if there is no SOA.
+    - `$TTL`: the TTL of the SOA, the minimum TTL of the RRs if there is no SOA.
+
+    Each record is a line of [`RR::presentation`]. Error without writing if a record is not one of
+    the [`MASTER_FILE_TYPES`](super::master_file::MASTER_FILE_TYPES), which can not be loaded back.
+     */
+    pub fn to_master_file(&self, path: &str) -> Result<(), Error> {
+        let mut keys: Vec<&String> = self.domains.keys().collect();
+        keys.sort();
+        let mut rrs: Vec<RR> = keys
+            .into_iter()
+            .flat_map(|key| self.domains[key].all_rrs())
+            .map(|rr| rr.read().unwrap().clone())
+            .collect();
+        rrs.sort_by_key(|rr| rr.typ() != TYPE_SOA);
+
+        let (origin, ttl) = match rrs.first().filter(|rr| rr.typ() == TYPE_SOA) {
+            Some(soa) => (soa.name().trim_end_matches('.'), soa.ttl()),
+            None => ("", rrs.iter().map(|rr| rr.ttl()).min().unwrap_or(0)),
+        };
+        for rr in &rrs {
+            check_master_file_type(rr)?;
+        }
+        let mut content = format!("$ORIGIN {}.\n$TTL {}\n", origin, ttl);
+        for rr in &rrs {
+            content.push_str(&rr.presentation());
+            content.push('\n');
+        }
+        fs::write(path, content)?;
+
+        Ok(())
+    }
+
+    /// get_rr: all the RRs of the names of the questions.
+    pub fn get_rr(&self, quess: &Questions) -> VecArcRf<RR> {
         let mut list = vec![];
         for ques in &quess.0 {
             let domain = ques.qname().encode_to_str();
 
             for (_, mf) in &self.domains {
-                if let Some(rr) = mf.query(&domain) {
-                    list.push(rr);
-                }
+                list.extend(mf.query(&domain));
             }
         }
 
         list
     }
+
+    /**
+    answer: the authoritative response to the query, each question is looked up by the algorithm of
+    RFC 1034 section 4.3.2:
+    - the referral: the NS records of the delegation below the apex in the authority section.
+    - the records of the name, or synthesized from the wildcard of its closest encloser. (RFC 4592)
+    - the CNAME is followed in the zones until the records of the type are found, SERVFAIL on a loop.
+    - NODATA: the name exists without the records of the type, the SOA in the authority section.
+    - NXDOMAIN: the name not exists in the zone, the SOA in the authority section.
+    - the A and AAAA records of the NS/MX targets are attached to the additional section.
+
+    The names are looked up case-insensitively, the owners of the answers echo the case of the query.
+    The AA bit is set when the name is in the zone of a SOA, except the referral. The name out of
+    the zones is answered with nothing.
+     */
+    pub fn answer(&self, query: &DNS) -> DNS {
+        let mut resp = DNS::new();
+        let header = query.header();
+        resp.head()
+            .with_id(header.id())
+            .with_opcode(header.opcode())
+            .with_rd(header.rd());
+        for ques in &query.ques().0 {
+            resp.ques_mut().push(ques.clone());
+        }
+        resp.into_response();
+
+        for ques in &query.ques().0 {
+            self.answer_question(ques, &mut resp);
+        }
+
+        resp
+    }
+
+    /// soa: the SOA of the zone which the name belongs to, its TTL is the minimum of the TTL and the
+    /// MINIMUM field for the negative caching. (RFC 2308)
+    pub fn soa(&self, name: &str, class: Class) -> Option<RR> {
+        let mut name = name.trim_end_matches('.');
+        loop {
+            let soa = self
+                .rrs_of(name)
+                .into_iter()
+                .find(|rr| rr.typ() == TYPE_SOA && (rr.class() == class || class == CLASS_ANY));
+            if let Some(mut rr) = soa {
+                if let RDataType::SOA(soa) = rr.rdata() {
+                    let ttl = rr.ttl().min(soa.minimum);
+                    rr.with_ttl(ttl);
+                }
+                return Some(rr);
+            }
+            match name.split_once('.') {
+                Some((_, parent)) => name = parent,
+                None => return None,
+            }
+        }
+    }
+
+    fn answer_question(&self, ques: &Question, resp: &mut DNS) {
+        let (qtype, qclass) = (ques.qtype(), ques.qclass());
+        let in_class = |rr: &RR| rr.class() == qclass || qclass == CLASS_ANY;
+        let mut name = ques
+            .qname()
+            .encode_to_str()
+            .trim_end_matches('.')
+            .to_string();
+        let mut answers: Vec<RR> = vec![];
+        let mut authority: Vec<RR> = vec![];
+        loop {
+            // the CNAME loop, or the chain too long to follow
+            if detect_cname_chain_loop(&answers).is_some() || answers.len() > MAX_CNAME_CHAIN {
+                resp.head().with_rcode(RCODE_SERVFAIL);
+                return;
+            }
+            let soa = self.soa(&name, qclass);
+            if let Some(soa) = soa.as_ref() {
+                if let Some(ns) = self.delegation(&name, soa.name(), qtype, qclass) {
+                    authority.extend(ns);
+                    break;
+                }
+                if answers.is_empty() {
+                    resp.head().with_aa(true);
+                }
+            }
+
+            let mut rrs: Vec<RR> = self.rrs_of(&name).into_iter().filter(in_class).collect();
+            // echo the case of the query rather than the stored one, the 0x20 mixed-case queries rely on it
+            for rr in rrs.iter_mut() {
+                rr.with_name(&name);
+            }
+            let exists = !rrs.is_empty() || self.contains(&name);
+            if let (false, Some(soa)) = (exists, soa.as_ref()) {
+                rrs = self
+                    .wildcard_rrs(&name, soa.name())
+                    .into_iter()
+                    .filter(in_class)
+                    .collect();
+            }
+            let synthesized = !exists && !rrs.is_empty();
+
+            let matched: Vec<RR> = rrs
+                .iter()
+                .filter(|rr| rr.typ() == qtype || qtype == TYPE_ANY)
+                .cloned()
+                .collect();
+            if !matched.is_empty() {
+                answers.extend(matched);
+                break;
+            }
+            let cname = rrs.into_iter().find(|rr| rr.typ() == TYPE_CNAME);
+            if let Some(cname) = cname {
+                if let RDataType::CName(target) = cname.rdata() {
+                    name = target.0.trim_end_matches('.').to_string();
+                    answers.push(cname);
+                    continue;
+                }
+            }
+            // NODATA: the name exists or matches a wildcard, otherwise NXDOMAIN
+            if let Some(soa) = soa {
+                if !exists && !synthesized {
+                    resp.head().with_rcode(RCODE_NXDOMAIN);
+                }
+                authority.push(soa);
+            }
+            break;
+        }
+
+        let additional = self.glue(answers.iter().chain(authority.iter()));
+        for rr in answers {
+            resp.add_answer_rr(Rc::new(RefCell::new(rr)));
+        }
+        for rr in authority {
+            resp.add_authority_rr(Rc::new(RefCell::new(rr)));
+        }
+        for rr in additional {
+            resp.add_additional_rr(Rc::new(RefCell::new(rr)));
+        }
+    }
+
+    /// the RRs of the name in all the master files.
+    fn rrs_of(&self, name: &str) -> Vec<RR> {
+        self.domains
+            .values()
+            .flat_map(|mf| mf.query(name))
+            .map(|rr| rr.read().unwrap().clone())
+            .collect()
+    }
+
+    /// whether the name exists in any master file, including the empty non-terminal.
+    fn contains(&self, name: &str) -> bool {
+        self.domains.values().any(|mf| mf.contains(name))
+    }
+
+    /// the NS records of the topmost zone cut between the apex and the name, the DS at the cut
+    /// belongs to the parent zone.
+    fn delegation(&self, name: &str, apex: &str, qtype: Type, class: Class) -> Option<Vec<RR>> {
+        let apex = apex.trim_end_matches('.');
+        let mut cut = None;
+        let mut owner = name;
+        while !owner.eq_ignore_ascii_case(apex) {
+            if owner != name || qtype != TYPE_DS {
+                let ns: Vec<RR> = self
+                    .rrs_of(owner)
+                    .into_iter()
+                    .filter(|rr| rr.typ() == TYPE_NS && (rr.class() == class || class == CLASS_ANY))
+                    .collect();
+                if !ns.is_empty() {
+                    cut = Some(ns);
+                }
+            }
+            match owner.split_once('.') {
+                Some((_, parent)) => owner = parent,
+                None => break,
+            }
+        }
+
+        cut
+    }
+
+    /// the RRs synthesized from the wildcard of the closest encloser of the name, their owners are
+    /// replaced by the name. (RFC 4592 section 3.3.1)
+    fn wildcard_rrs(&self, name: &str, apex: &str) -> Vec<RR> {
+        let apex = apex.trim_end_matches('.');
+        let mut encloser = name;
+        while let Some((_, parent)) = encloser.split_once('.') {
+            encloser = parent;
+            if self.contains(encloser) || encloser.eq_ignore_ascii_case(apex) {
+                let mut rrs = self.rrs_of(&format!("*.{}", encloser));
+                for rr in rrs.iter_mut() {
+                    rr.with_name(name);
+                }
+                return rrs;
+            }
+        }
+
+        vec![]
+    }
+
+    /// the A and AAAA records of the NS/MX/SRV targets. (RFC 3596 section 3, RFC 2782)
+    fn glue<'a>(&self, rrs: impl Iterator<Item = &'a RR>) -> Vec<RR> {
+        let mut glue = vec![];
+        for rr in rrs {
+            let target = match rr.rdata() {
+                RDataType::NS(ns) => ns.0.as_str(),
+                RDataType::MX(mx) => mx.exchange.as_str(),
+                RDataType::SRV(srv) => srv.target.as_str(),
+                _ => continue,
+            };
+            glue.extend(
+                self.rrs_of(target.trim_end_matches('.'))
+                    .into_iter()
+                    .filter(|glue| glue.typ() == TYPE_A || glue.typ() == TYPE_AAAA),
+            );
+        }
+
+        glue
+    }
+}
+
+/// the SERIAL of the first SOA in the rrs.
+fn soa_serial(rrs: &VecArcRf<RR>) -> Option<u32> {
+    rrs.iter().find_map(|rr| match rr.read().unwrap().rdata() {
+        RDataType::SOA(soa) => Some(soa.serial),
+        _ => None,
+    })
+}
+
+fn validate_zone(zone: &str, rrs: &[RR], errs: &mut Vec<ZoneError>) {
+    let name_of = |rr: &RR| rr.name().trim_end_matches('.').to_ascii_lowercase();
+
+    let soas: Vec<&RR> = rrs.iter().filter(|rr| rr.typ() == TYPE_SOA).collect();
+    match soas.len() {
+        0 => errs.push(ZoneError::MissingSoa {
+            zone: zone.to_string(),
+        }),
+        1 => {}
+        count => errs.push(ZoneError::MultipleSoa {
+            zone: zone.to_string(),
+            count,
+        }),
+    }
+
+    if let Some(soa) = soas.first() {
+        let apex = name_of(soa);
+        if !rrs
+            .iter()
+            .any(|rr| rr.typ() == TYPE_NS && name_of(rr) == apex)
+        {
+            errs.push(ZoneError::MissingApexNs { apex: apex.clone() });
+        }
+        for rr in rrs {
+            let name = name_of(rr);
+            if name != apex && !name.ends_with(&format!(".{}", apex)) {
+                errs.push(ZoneError::OutOfZone {
+                    apex: apex.clone(),
+                    name,
+                });
+            }
+        }
+    }
+
+    for rr in rrs {
+        if is_meta_type(rr.typ()) || is_meta_class(rr.class()) {
+            errs.push(ZoneError::MetaRecord {
+                name: name_of(rr),
+                typ: rr.typ(),
+                class: rr.class(),
+            });
+        }
+    }
+
+    let mut types: BTreeMap<String, Vec<u16>> = BTreeMap::new();
+    for rr in rrs {
+        types.entry(name_of(rr)).or_default().push(rr.typ());
+    }
+    for (name, types) in types {
+        let has_cname = types.contains(&TYPE_CNAME);
+        let has_others = types
+            .iter()
+            .any(|typ| ![TYPE_CNAME, TYPE_RRSIG, TYPE_NSEC].contains(typ));
+        if has_cname && has_others {
+            errs.push(ZoneError::CnameConflict { name });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::{
+        rdata::{a::A, cname::CName, ns::NS, null::Null, ptr::PTR, soa::SOA, srv::SRV},
+        CLASS_IN, RCODE_NOERROR, TYPE_MX, TYPE_PTR, TYPE_SRV,
+    };
+    use std::{
+        net::{Ipv4Addr, Ipv6Addr},
+        path::Path,
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    fn new_rr(name: &str, typ: u16, rdata: RDataType) -> RR {
+        let mut rr = RR::new();
+        rr.with_name(name)
+            .with_type(typ)
+            .with_class(CLASS_IN)
+            .with_ttl(300)
+            .with_rdata(rdata);
+        rr
+    }
+
+    fn new_a(name: &str, ip: [u8; 4]) -> RR {
+        RR::a(name, 300, Ipv4Addr::from(ip))
+    }
+
+    #[test]
+    fn test_zones_insert_rr() {
+        let mut zones = Zones::new();
+        zones.insert_rr(new_rr("example.com", TYPE_SOA, RDataType::SOA(SOA::new())));
+        zones.insert_rr(new_rr(
+            "example.com",
+            TYPE_A,
+            RDataType::A(A::new(Ipv4Addr::new(10, 0, 0, 1))),
+        ));
+        zones.insert_rr(new_rr(
+            "www.example.com",
+            TYPE_A,
+            RDataType::A(A::new(Ipv4Addr::new(10, 0, 0, 2))),
+        ));
+
+        let query = |name: &str| {
+            let mut ques = Question::new();
+            for label in name.split('.') {
+                ques.with_name(label);
+            }
+            zones.get_rr(&Questions(vec![ques]))
+        };
+        let rrs = query("example.com");
+        assert_eq!(2, rrs.len());
+        assert_eq!(TYPE_SOA, rrs[0].read().unwrap().typ());
+        assert_eq!(TYPE_A, rrs[1].read().unwrap().typ());
+
+        let rrs = query("www.example.com");
+        assert_eq!(1, rrs.len());
+        assert_eq!(
+            &RDataType::A(A::new(Ipv4Addr::new(10, 0, 0, 2))),
+            rrs[0].read().unwrap().rdata()
+        );
+        assert_eq!(0, query("ftp.example.com").len());
+    }
+
+    #[test]
+    fn test_zones_validate() {
+        let mut zones = Zones::new();
+        zones.insert_rr(new_rr("example.com", TYPE_SOA, RDataType::SOA(SOA::new())));
+        zones.insert_rr(new_rr(
+            "example.com",
+            TYPE_NS,
+            RDataType::NS(NS("ns1.example.com".to_string())),
+        ));
+        zones.insert_rr(new_a("ns1.example.com", [10, 0, 0, 1]));
+        zones.insert_rr(new_rr(
+            "www.example.com",
+            TYPE_CNAME,
+            RDataType::CName(CName("ns1.example.com".to_string())),
+        ));
+        assert_eq!(true, zones.validate().is_ok());
+
+        // the CNAME coexists with the A
+        zones.insert_rr(new_a("www.example.com", [10, 0, 0, 2]));
+        // out of the zone
+        zones.insert_rr(new_a("www.example.org", [10, 0, 0, 3]));
+        // the meta type
+        zones.insert_rr(new_rr("any.example.com", TYPE_ANY, RDataType::None));
+        assert_eq!(
+            Err(vec![
+                ZoneError::OutOfZone {
+                    apex: "example.com".to_string(),
+                    name: "www.example.org".to_string(),
+                },
+                ZoneError::MetaRecord {
+                    name: "any.example.com".to_string(),
+                    typ: TYPE_ANY,
+                    class: CLASS_IN,
+                },
+                ZoneError::CnameConflict {
+                    name: "www.example.com".to_string(),
+                },
+            ]),
+            zones.validate()
+        );
+    }
+
+    #[test]
+    fn test_zones_validate_missing_soa() {
+        let mut zones = Zones::new();
+        zones.insert_rr(new_a("www.example.com", [10, 0, 0, 1]));
+        let errs = zones.validate().unwrap_err();
+        assert_eq!(1, errs.len());
+        assert_eq!(true, matches!(errs[0], ZoneError::MissingSoa { .. }));
+
+        // the apex without NS
+        zones.insert_rr(new_rr("example.com", TYPE_SOA, RDataType::SOA(SOA::new())));
+        assert_eq!(
+            Err(vec![ZoneError::MissingApexNs {
+                apex: "example.com".to_string()
+            }]),
+            zones.validate()
+        );
+    }
+
+    #[test]
+    fn test_zones_bump_serial() {
+        let query = |zones: &Zones, name: &str| {
+            let mut ques = Question::new();
+            for label in name.split('.') {
+                ques.with_name(label);
+            }
+            zones.get_rr(&Questions(vec![ques]))
+        };
+        let mut soa = SOA::new();
+        soa.serial = 10;
+        let mut zones = Zones::new();
+        zones.insert_rr(new_rr("example.com", TYPE_SOA, RDataType::SOA(soa)));
+        zones.insert_rr(new_a("www.example.com", [10, 0, 0, 1]));
+        assert_eq!(Some(10), soa_serial(&query(&zones, "example.com")));
+        // the other zone
+        let mut soa = SOA::new();
+        soa.serial = 20;
+        zones.insert_rr(new_rr("example.org", TYPE_SOA, RDataType::SOA(soa)));
+
+        zones.update_rr(new_a("www.example.com", [10, 0, 0, 2]));
+        assert_eq!(Some(11), soa_serial(&query(&zones, "example.com")));
+        assert_eq!(Some(20), soa_serial(&query(&zones, "example.org")));
+        let rrs = query(&zones, "www.example.com");
+        assert_eq!(1, rrs.len());
+        assert_eq!(
+            &RDataType::A(A::new(Ipv4Addr::new(10, 0, 0, 2))),
+            rrs[0].read().unwrap().rdata()
+        );
+
+        zones.bump_serial();
+        assert_eq!(Some(12), soa_serial(&query(&zones, "example.com")));
+        assert_eq!(Some(21), soa_serial(&query(&zones, "example.org")));
+
+        // the reloaded zone with a stale SERIAL
+        let mut soa = SOA::new();
+        soa.serial = 5;
+        let mut reloaded = Zones::new();
+        reloaded.insert_rr(new_rr("example.com", TYPE_SOA, RDataType::SOA(soa)));
+        zones.merge(reloaded);
+        assert_eq!(Some(13), soa_serial(&query(&zones, "example.com")));
+    }
+
+    #[test]
+    fn test_zones_to_master_file() {
+        let mut soa = SOA::new();
+        soa.mname = "ns.example.com".to_string();
+        soa.rname = "admin.example.com".to_string();
+        soa.serial = 2024010101;
+        soa.minimum = 60;
+        let mut zones = Zones::new();
+        zones.insert_rr(RR::ns("example.com", 3600, "ns.example.com"));
+        zones.insert_rr(RR::soa("example.com", 3600, soa));
+        zones.insert_rr(RR::mx("example.com", 3600, 10, "mail.example.com"));
+        zones.insert_rr(new_a("ns.example.com", [10, 0, 0, 1]));
+        zones.insert_rr(RR::aaaa(
+            "www.example.com",
+            300,
+            "2001:db8::1".parse().unwrap(),
+        ));
+        zones.insert_rr(RR::cname("ftp.example.com", 300, "www.example.com"));
+        zones.insert_rr(RR::txt("example.com", 300, &["v=spf1 -all", "say \"hi\""]));
+
+        // the unique directory of the test run
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!(
+            "rsdns_test_zones_to_master_file_{}_{}",
+            std::process::id(),
+            nanos
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("example.com");
+        let path = path.to_str().unwrap();
+        zones.to_master_file(path).unwrap();
+
+        let content = fs::read_to_string(path).unwrap();
+        let mut lines = content.lines();
+        assert_eq!(Some("$ORIGIN example.com."), lines.next());
+        assert_eq!(Some("$TTL 3600"), lines.next());
+        assert_eq!(
+            Some("example.com. 6 1 3600 ns.example.com. admin.example.com. 2024010101 0 0 0 60"),
+            lines.next()
+        );
+
+        let presentations = |zones: &Zones| {
+            let mut list: Vec<String> = zones
+                .domains
+                .values()
+                .flat_map(|mf| mf.all_rrs())
+                .map(|rr| rr.read().unwrap().presentation())
+                .collect();
+            list.sort();
+            list
+        };
+        let reloaded = Zones::from_file(path).unwrap();
+        assert_eq!(7, presentations(&reloaded).len());
+        assert_eq!(presentations(&zones), presentations(&reloaded));
+
+        // the type not loaded back is not written
+        let mut rr = RR::new();
+        rr.with_name("www.example.com")
+            .with_type(65280)
+            .with_class(CLASS_IN)
+            .with_rdata(RDataType::Unknown(Null::from(&[], &[1, 2], 0).unwrap()));
+        zones.insert_rr(rr);
+        let other = dir.join("unsupported");
+        let other = other.to_str().unwrap();
+        assert_eq!(true, zones.to_master_file(other).is_err());
+        assert_eq!(false, Path::new(other).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn example_zones() -> Zones {
+        let mut soa = SOA::new();
+        soa.mname = "ns.example.com".to_string();
+        soa.rname = "admin.example.com".to_string();
+        soa.minimum = 60;
+        let mut zones = Zones::new();
+        zones.insert_rr(RR::soa("example.com", 3600, soa));
+        zones.insert_rr(RR::ns("example.com", 3600, "ns.example.com"));
+        zones.insert_rr(new_a("ns.example.com", [10, 0, 0, 1]));
+        zones.insert_rr(new_a("www.example.com", [10, 0, 0, 2]));
+        zones.insert_rr(RR::cname("web.example.com", 300, "alias.example.com"));
+        zones.insert_rr(RR::cname("alias.example.com", 300, "www.example.com"));
+        zones.insert_rr(new_a("host.lab.example.com", [10, 0, 0, 3]));
+        zones.insert_rr(new_a("*.dyn.example.com", [10, 0, 0, 4]));
+        zones.insert_rr(RR::ns("sub.example.com", 3600, "ns.sub.example.com"));
+        zones.insert_rr(new_a("ns.sub.example.com", [10, 0, 1, 1]));
+        zones
+    }
+
+    fn query(name: &str, qtype: Type) -> DNS {
+        let mut dns = DNS::new();
+        dns.head().with_id(0x1234).with_rd(true);
+        dns.with_ques(name, qtype, CLASS_IN);
+        dns
+    }
+
+    fn names_types(rrs: &crate::dns::RRs) -> Vec<(String, Type)> {
+        rrs.0
+            .iter()
+            .map(|rr| (rr.borrow().name().to_string(), rr.borrow().typ()))
+            .collect()
+    }
+
+    #[test]
+    fn test_zones_answer() {
+        let mut zones = example_zones();
+
+        // the positive answer
+        let mut resp = zones.answer(&query("www.example.com", TYPE_A));
+        assert_eq!(0x1234, resp.head().id());
+        assert_eq!(true, resp.head().qr());
+        assert_eq!(true, resp.head().rd());
+        assert_eq!(true, resp.head().aa());
+        assert_eq!(RCODE_NOERROR, resp.head().rcode());
+        assert_eq!(1, resp.ques().len());
+        assert_eq!(vec![Ipv4Addr::new(10, 0, 0, 2)], resp.a_records());
+        assert_eq!(0, resp.authority().len());
+
+        // the CNAME chain is followed to the A record
+        let mut resp = zones.answer(&query("web.example.com", TYPE_A));
+        assert_eq!(RCODE_NOERROR, resp.head().rcode());
+        assert_eq!(
+            vec![
+                ("web.example.com".to_string(), TYPE_CNAME),
+                ("alias.example.com".to_string(), TYPE_CNAME),
+                ("www.example.com".to_string(), TYPE_A),
+            ],
+            names_types(resp.answers())
+        );
+        // the CNAME itself
+        let resp = zones.answer(&query("web.example.com", TYPE_CNAME));
+        assert_eq!(
+            vec![("web.example.com".to_string(), TYPE_CNAME)],
+            names_types(resp.answers())
+        );
+
+        // NODATA: the SOA with the negative TTL
+        let mut resp = zones.answer(&query("www.example.com", TYPE_MX));
+        assert_eq!(RCODE_NOERROR, resp.head().rcode());
+        assert_eq!(true, resp.head().aa());
+        assert_eq!(0, resp.answers().len());
+        assert_eq!(
+            vec![("example.com".to_string(), TYPE_SOA)],
+            names_types(resp.authority())
+        );
+        assert_eq!(60, resp.authority().0[0].borrow().ttl());
+        // the empty non-terminal exists
+        let mut resp = zones.answer(&query("lab.example.com", TYPE_A));
+        assert_eq!(RCODE_NOERROR, resp.head().rcode());
+        assert_eq!(1, resp.authority().len());
+
+        // NXDOMAIN
+        let mut resp = zones.answer(&query("nope.example.com", TYPE_A));
+        assert_eq!(RCODE_NXDOMAIN, resp.head().rcode());
+        assert_eq!(true, resp.head().aa());
+        assert_eq!(0, resp.answers().len());
+        assert_eq!(
+            vec![("example.com".to_string(), TYPE_SOA)],
+            names_types(resp.authority())
+        );
+        // the CNAME to the nonexistent name
+        zones.insert_rr(RR::cname("dangling.example.com", 300, "gone.example.com"));
+        let mut resp = zones.answer(&query("dangling.example.com", TYPE_A));
+        assert_eq!(RCODE_NXDOMAIN, resp.head().rcode());
+        assert_eq!(1, resp.answers().len());
+
+        // the wildcard of the closest encloser
+        let mut resp = zones.answer(&query("a.dyn.example.com", TYPE_A));
+        assert_eq!(RCODE_NOERROR, resp.head().rcode());
+        assert_eq!(
+            vec![("a.dyn.example.com".to_string(), TYPE_A)],
+            names_types(resp.answers())
+        );
+        let mut resp = zones.answer(&query("a.dyn.example.com", TYPE_MX));
+        assert_eq!(RCODE_NOERROR, resp.head().rcode());
+        assert_eq!(0, resp.answers().len());
+
+        // the referral with the glue
+        let mut resp = zones.answer(&query("www.sub.example.com", TYPE_A));
+        assert_eq!(RCODE_NOERROR, resp.head().rcode());
+        assert_eq!(false, resp.head().aa());
+        assert_eq!(0, resp.answers().len());
+        assert_eq!(
+            vec![("sub.example.com".to_string(), TYPE_NS)],
+            names_types(resp.authority())
+        );
+        assert_eq!(
+            vec![("ns.sub.example.com".to_string(), TYPE_A)],
+            names_types(resp.additional())
+        );
+
+        // the glue of the NS answer
+        let resp = zones.answer(&query("example.com", TYPE_NS));
+        assert_eq!(
+            vec![("ns.example.com".to_string(), TYPE_A)],
+            names_types(resp.additional())
+        );
+
+        // the A and AAAA of the SRV target
+        let mut srv = SRV::new();
+        srv.priority = 10;
+        srv.weight = 5;
+        srv.port = 5060;
+        srv.target = "sip.example.com".to_string();
+        zones.insert_rr(new_rr(
+            "_sip._udp.example.com",
+            TYPE_SRV,
+            RDataType::SRV(srv),
+        ));
+        zones.insert_rr(new_a("sip.example.com", [10, 0, 0, 5]));
+        zones.insert_rr(RR::aaaa("sip.example.com", 300, Ipv6Addr::LOCALHOST));
+        let resp = zones.answer(&query("_sip._udp.example.com", TYPE_SRV));
+        assert_eq!(1, resp.answers().len());
+        assert_eq!(
+            vec![
+                ("sip.example.com".to_string(), TYPE_A),
+                ("sip.example.com".to_string(), TYPE_AAAA)
+            ],
+            names_types(resp.additional())
+        );
+
+        // out of the zones
+        let mut resp = zones.answer(&query("www.example.org", TYPE_A));
+        assert_eq!(RCODE_NOERROR, resp.head().rcode());
+        assert_eq!(false, resp.head().aa());
+        assert_eq!(0, resp.answers().len() + resp.authority().len());
+    }
+
+    #[test]
+    fn test_zones_answer_case() {
+        let mut zones = example_zones();
+        zones.insert_rr(new_a("Mixed.Example.com", [10, 0, 0, 5]));
+
+        let resp = zones.answer(&query("ExAmPlE.com", TYPE_NS));
+        assert_eq!("ExAmPlE.com", resp.ques().0[0].qname().encode_to_str());
+        assert_eq!(
+            vec![("ExAmPlE.com".to_string(), TYPE_NS)],
+            names_types(resp.answers())
+        );
+
+        let resp = zones.answer(&query("wWw.ExAmPlE.CoM", TYPE_A));
+        assert_eq!(vec![Ipv4Addr::new(10, 0, 0, 2)], resp.a_records());
+        assert_eq!("wWw.ExAmPlE.CoM", resp.answers().0[0].borrow().name());
+
+        // the stored case is not an exact match either
+        let resp = zones.answer(&query("mixed.example.com", TYPE_A));
+        assert_eq!(vec![Ipv4Addr::new(10, 0, 0, 5)], resp.a_records());
+        assert_eq!("mixed.example.com", resp.answers().0[0].borrow().name());
+
+        // the CNAME chain echoes the case of the targets
+        let resp = zones.answer(&query("WEB.example.com", TYPE_A));
+        assert_eq!(
+            vec![
+                ("WEB.example.com".to_string(), TYPE_CNAME),
+                ("alias.example.com".to_string(), TYPE_CNAME),
+                ("www.example.com".to_string(), TYPE_A),
+            ],
+            names_types(resp.answers())
+        );
+    }
+
+    #[test]
+    fn test_zones_reverse_zone() {
+        let mut zones = Zones::reverse_zone(&[
+            RR::aaaa("www.example.com", 300, "2001:db8::1".parse().unwrap()),
+            RR::aaaa("mail.example.com", 300, "2001:db8::2".parse().unwrap()),
+            new_a("www.example.com", [10, 0, 0, 1]),
+            RR::ns("example.com", 3600, "ns.example.com"),
+        ]);
+        let mut soa = SOA::new();
+        soa.mname = "ns.example.com".to_string();
+        zones.insert_rr(RR::soa("8.b.d.0.1.0.0.2.ip6.arpa", 3600, soa));
+
+        let ptr = "1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa";
+        let mut resp = zones.answer(&query(ptr, TYPE_PTR));
+        assert_eq!(RCODE_NOERROR, resp.head().rcode());
+        assert_eq!(true, resp.head().aa());
+        assert_eq!(
+            vec![(ptr.to_string(), TYPE_PTR)],
+            names_types(resp.answers())
+        );
+        assert_eq!(
+            &RDataType::PTR(PTR("www.example.com".to_string())),
+            resp.answers().0[0].borrow().rdata()
+        );
+
+        let resp = zones.answer(&query("1.0.0.10.in-addr.arpa", TYPE_PTR));
+        assert_eq!(
+            &RDataType::PTR(PTR("www.example.com".to_string())),
+            resp.answers().0[0].borrow().rdata()
+        );
+
+        // the address without the AAAA record
+        let mut resp = zones.answer(&query(&ptr.replacen('1', "3", 1), TYPE_PTR));
+        assert_eq!(RCODE_NXDOMAIN, resp.head().rcode());
+    }
+
+    #[test]
+    fn test_zones_answer_cname_loop() {
+        let mut zones = Zones::new();
+        zones.insert_rr(RR::cname("a.example.com", 300, "b.example.com"));
+        zones.insert_rr(RR::cname("b.example.com", 300, "a.example.com"));
+
+        let mut resp = zones.answer(&query("a.example.com", TYPE_A));
+        assert_eq!(RCODE_SERVFAIL, resp.head().rcode());
+        assert_eq!(0, resp.answers().len());
+    }
 }
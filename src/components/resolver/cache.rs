@@ -0,0 +1,212 @@
+/*!
+ref: https://www.rfc-editor.org/rfc/rfc1034#section-4.3.4
+
+# Cache
+
+The resolver keeps the responses of the upstream in the cache by the question `(name, type, class)`,
+so the identical queries are answered locally until the TTL expires. The names are compared
+case-insensitively, `Example.com` and `example.com` hit the same entry.
+
+The response is kept for the minimum TTL of its records, the TTLs of the records are capped to the
+remaining lifetime when it is taken out.
+ */
+
+use crate::{
+    dns::{Class, Type},
+    DNS,
+};
+use anyhow::{anyhow, Error};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// CacheKey: the question of the cached response, the name is lowercased and the trailing dot is
+/// removed on construction, so the derived Hash and Eq are case-insensitive.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    name: String,
+    typ: Type,
+    class: Class,
+}
+
+impl CacheKey {
+    pub fn new(name: &str, typ: Type, class: Class) -> Self {
+        Self {
+            name: name.trim_end_matches('.').to_ascii_lowercase(),
+            typ,
+            class,
+        }
+    }
+
+    /// the key of the first question of the dns.
+    pub fn from(dns: &DNS) -> Result<Self, Error> {
+        let ques = dns
+            .ques()
+            .0
+            .first()
+            .ok_or_else(|| anyhow!("the dns has no question"))?;
+
+        Ok(Self::new(
+            &ques.qname().encode_to_str(),
+            ques.qtype(),
+            ques.qclass(),
+        ))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn typ(&self) -> Type {
+        self.typ
+    }
+
+    pub fn class(&self) -> Class {
+        self.class
+    }
+}
+
+/// the response in the wire format, and when it expires.
+struct Entry {
+    raw: Vec<u8>,
+    expire: Instant,
+}
+
+pub struct Cache {
+    entries: HashMap<CacheKey, Entry>,
+}
+
+impl Cache {
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// insert: cache the response by its question for the minimum TTL of its records, the response
+    /// without any record or with a record of TTL 0 is not cached.
+    pub fn insert(&mut self, resp: &mut DNS) -> Result<(), Error> {
+        let key = CacheKey::from(resp)?;
+        let ttl = match resp.min_ttl() {
+            Some(0) | None => return Ok(()),
+            Some(ttl) => ttl,
+        };
+        self.entries.insert(
+            key,
+            Entry {
+                raw: resp.encode(false)?,
+                expire: Instant::now() + Duration::from_secs(ttl as u64),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// get: the cached response to the query, with the ID of the query and the TTLs capped to the
+    /// remaining lifetime. The expired response is removed.
+    pub fn get(&mut self, query: &mut DNS) -> Option<DNS> {
+        let key = CacheKey::from(query).ok()?;
+        let now = Instant::now();
+        let entry = self.entries.get(&key)?;
+        if entry.expire <= now {
+            self.entries.remove(&key);
+            return None;
+        }
+
+        let mut resp = DNS::from(&entry.raw).ok()?;
+        resp.head().with_id(query.head().id());
+        // the whole response expires with its minimum TTL
+        resp.cap_ttls((entry.expire - now).as_secs_f64().ceil() as u32);
+
+        Some(resp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cache, CacheKey};
+    use crate::{
+        dns::{CLASS_IN, RR, TYPE_A, TYPE_MX},
+        DNS,
+    };
+    use std::{cell::RefCell, net::Ipv4Addr, rc::Rc};
+
+    fn query(name: &str, typ: u16) -> DNS {
+        let mut dns = DNS::new();
+        dns.with_ques(name, typ, CLASS_IN);
+        dns
+    }
+
+    #[test]
+    fn test_cache_key_case_insensitive() {
+        assert_eq!(
+            CacheKey::new("example.com", TYPE_A, CLASS_IN),
+            CacheKey::new("Example.COM.", TYPE_A, CLASS_IN)
+        );
+        assert_ne!(
+            CacheKey::new("example.com", TYPE_A, CLASS_IN),
+            CacheKey::new("example.com", TYPE_MX, CLASS_IN)
+        );
+
+        let mut cache = Cache::new();
+        let mut resp = query("Example.com", TYPE_A);
+        resp.into_response()
+            .add_answer_rr(Rc::new(RefCell::new(RR::a(
+                "Example.com",
+                300,
+                Ipv4Addr::new(10, 0, 0, 1),
+            ))));
+        cache.insert(&mut resp).unwrap();
+        assert_eq!(1, cache.len());
+
+        let mut lower = query("example.com", TYPE_A);
+        lower.head().with_id(53);
+        let mut hit = cache.get(&mut lower).unwrap();
+        assert_eq!(53, hit.head().id());
+        assert_eq!(vec![Ipv4Addr::new(10, 0, 0, 1)], hit.a_records());
+        assert_eq!(300, hit.answers().0[0].borrow().ttl());
+        assert_eq!(
+            true,
+            cache.get(&mut query("EXAMPLE.COM.", TYPE_A)).is_some()
+        );
+        assert_eq!(
+            true,
+            cache.get(&mut query("example.com", TYPE_MX)).is_none()
+        );
+
+        // the response without any record is not cached
+        let mut empty = query("www.example.com", TYPE_A);
+        cache.insert(empty.into_response()).unwrap();
+        assert_eq!(1, cache.len());
+    }
+
+    #[test]
+    fn test_cache_ttl_zero() {
+        let mut cache = Cache::new();
+        let mut resp = query("example.com", TYPE_A);
+        resp.into_response()
+            .add_answer_rr(Rc::new(RefCell::new(RR::a(
+                "example.com",
+                300,
+                Ipv4Addr::new(10, 0, 0, 1),
+            ))))
+            .add_answer_rr(Rc::new(RefCell::new(RR::a(
+                "example.com",
+                0,
+                Ipv4Addr::new(10, 0, 0, 2),
+            ))));
+        assert_eq!(Some(0), resp.min_ttl());
+        cache.insert(&mut resp).unwrap();
+        assert_eq!(true, cache.is_empty());
+        assert_eq!(true, cache.get(&mut query("example.com", TYPE_A)).is_none());
+    }
+}
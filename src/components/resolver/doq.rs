@@ -0,0 +1,186 @@
+/*!
+ref: https://www.rfc-editor.org/rfc/rfc9250
+
+# DNS over Dedicated QUIC Connections
+
+The client opens a QUIC connection to the port 853 of the server with the ALPN token "doq", then
+sends each query on its own client-initiated bidirectional stream:
+```shell
++--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+|                     LENGTH                    |
++--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/                   DNS MESSAGE                 /
++--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+```
+the message is prefixed with a 2 octet length field like the TCP, the client indicates the end of the
+query by the STREAM FIN, and the server answers on the same stream.
+
+The DNS Message ID MUST be set to 0 when sending the query, the stream identifies the transaction.
+ */
+
+use super::forward::AsyncForwardOperation;
+use crate::DNS;
+use anyhow::{anyhow, Error};
+use std::{
+    future::Future,
+    net::{SocketAddr, ToSocketAddrs},
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{sync::Mutex, time::timeout};
+
+/// the default port of DoQ.
+pub const DOQ_DEFAULT_PORT: u16 = 853;
+
+/// the ALPN token of DoQ.
+const DOQ_ALPN: &[u8] = b"doq";
+
+/// the default timeout waiting for the response of the forwarded request.
+const DEFAULT_DOQ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// DoqForward: forward the dns request to the upstream over DoQ, the QUIC connection is opened at
+/// the first request and reused by the following ones.
+pub struct DoqForward {
+    target: String,
+    server_name: String,
+    timeout: Duration,
+
+    conn: Mutex<Option<quinn::Connection>>,
+}
+
+impl DoqForward {
+    pub fn new() -> Self {
+        Self {
+            target: "".to_string(),
+            server_name: "".to_string(),
+            timeout: DEFAULT_DOQ_TIMEOUT,
+            conn: Mutex::new(None),
+        }
+    }
+
+    /// with_target: the address of the upstream, the [`DOQ_DEFAULT_PORT`] is used when the port is omitted.
+    pub fn with_target(&mut self, target: &str) -> &mut Self {
+        self.target = target.to_string();
+        return self;
+    }
+
+    /// with_server_name: the name sent in the SNI and verified against the certificate of the upstream.
+    pub fn with_server_name(&mut self, server_name: &str) -> &mut Self {
+        self.server_name = server_name.to_string();
+        return self;
+    }
+
+    pub fn with_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = timeout;
+        return self;
+    }
+
+    pub async fn forward_async(&self, dns: &mut DNS) -> Result<DNS, Error> {
+        let id = dns.head().id();
+        dns.head().with_id(0);
+        let raw = dns.encode(false);
+        dns.head().with_id(id);
+        let raw = raw?;
+
+        let conn = self.connection().await?;
+        let resp = timeout(self.timeout, async {
+            let (mut send, mut recv) = conn.open_bi().await?;
+            send.write_all(&(raw.len() as u16).to_be_bytes()).await?;
+            send.write_all(&raw).await?;
+            send.finish()?;
+
+            let mut len = [0u8; 2];
+            recv.read_exact(&mut len).await?;
+            let mut buff = vec![0u8; u16::from_be_bytes(len) as usize];
+            recv.read_exact(&mut buff).await?;
+            Ok::<_, Error>(buff)
+        })
+        .await??;
+
+        let mut new_dns = DNS::from(&resp)?;
+        new_dns.head().with_id(id);
+
+        Ok(new_dns)
+    }
+
+    /// return the cached connection, or open a new one when it is absent or closed.
+    async fn connection(&self) -> Result<quinn::Connection, Error> {
+        let mut conn = self.conn.lock().await;
+        if let Some(c) = conn.as_ref() {
+            if c.close_reason().is_none() {
+                return Ok(c.clone());
+            }
+        }
+
+        let addr = self.target_addr()?;
+        let mut roots = rustls::RootCertStore::empty();
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        let mut tls = rustls::ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        tls.alpn_protocols = vec![DOQ_ALPN.to_vec()];
+        let crypto = quinn::crypto::rustls::QuicClientConfig::try_from(tls)?;
+
+        let bind: SocketAddr = match addr {
+            SocketAddr::V4(_) => "0.0.0.0:0".parse()?,
+            SocketAddr::V6(_) => "[::]:0".parse()?,
+        };
+        let mut endpoint = quinn::Endpoint::client(bind)?;
+        endpoint.set_default_client_config(quinn::ClientConfig::new(Arc::new(crypto)));
+
+        let c = timeout(self.timeout, endpoint.connect(addr, &self.server_name)?).await??;
+        *conn = Some(c.clone());
+
+        Ok(c)
+    }
+
+    fn target_addr(&self) -> Result<SocketAddr, Error> {
+        let target = match self.target.parse::<std::net::IpAddr>() {
+            Ok(ip) => SocketAddr::new(ip, DOQ_DEFAULT_PORT).to_string(),
+            Err(_) if !self.target.contains(':') => {
+                format!("{}:{}", self.target, DOQ_DEFAULT_PORT)
+            }
+            Err(_) => self.target.clone(),
+        };
+        target
+            .to_socket_addrs()?
+            .next()
+            .ok_or(anyhow!("can not resolve the target: {}", self.target))
+    }
+}
+
+impl AsyncForwardOperation for DoqForward {
+    fn forward_async<'a>(
+        &'a self,
+        dns: &'a mut DNS,
+    ) -> Pin<Box<dyn Future<Output = Result<DNS, Error>> + Send + 'a>> {
+        Box::pin(DoqForward::forward_async(self, dns))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DoqForward;
+    use crate::{
+        dns::{CLASS_IN, TYPE_A},
+        DNS,
+    };
+
+    #[tokio::test]
+    #[ignore = "needs the network to reach a public DoQ resolver"]
+    async fn test_doq_forward() {
+        let mut doq = DoqForward::new();
+        doq.with_target("94.140.14.140")
+            .with_server_name("dns.adguard-dns.com");
+
+        let mut dns = DNS::new();
+        dns.head().with_id(0x1234).with_rd(true);
+        dns.with_ques("example.com", TYPE_A, CLASS_IN);
+
+        let mut resp = doq.forward_async(&mut dns).await.unwrap();
+        assert_eq!(0x1234, resp.head().id());
+        assert_eq!(true, resp.head().qr());
+        assert_eq!(false, resp.a_records().is_empty());
+    }
+}
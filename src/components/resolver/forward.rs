@@ -1,23 +1,95 @@
 use std::{
     fmt,
+    future::Future,
     net::UdpSocket,
-    sync::mpsc::{self, Receiver, Sender},
+    pin::Pin,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
     thread::{self, Thread},
+    time::{Duration, Instant},
 };
 
-use crate::DNS;
-use anyhow::Error;
+use super::{cache::Cache, pipeline::TcpPipeline};
+use crate::{
+    dns::{
+        rdata::{opt::EDNS_OPTION_COOKIE, RDataType},
+        DEFAULT_UDP_PAYLOAD,
+    },
+    DNS,
+};
+use anyhow::{anyhow, Error};
+use rand::Rng;
+use tokio::time::{error::Elapsed, timeout, timeout_at};
+
+/// the default timeout waiting for the response of the forwarded request.
+const DEFAULT_FORWARD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/**
+HardeningOptions: the anti-spoofing measures of the forward, they can be enabled together or independently.
+
+The defaults:
+- id_match: on, the response id must equal the request id.
+- randomize_case: off, randomize the case of the question names (dns 0x20). The echoed case of the
+  question is always verified, randomizing only adds the entropy.
+- cookies: off, send a random client cookie (RFC 7873) and the response must echo it.
+- random_source_port: off, the udp socket of [`DefaultForward::start`] binds a random port instead of
+  the configured one. The [`DefaultForward::forward_async`] always uses a random port.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HardeningOptions {
+    pub id_match: bool,
+    pub randomize_case: bool,
+    pub cookies: bool,
+    pub random_source_port: bool,
+}
+
+impl HardeningOptions {
+    pub fn new() -> Self {
+        Self {
+            id_match: true,
+            randomize_case: false,
+            cookies: false,
+            random_source_port: false,
+        }
+    }
+
+    /// all the measures are enabled.
+    pub fn all() -> Self {
+        Self {
+            id_match: true,
+            randomize_case: true,
+            cookies: true,
+            random_source_port: true,
+        }
+    }
+}
 
 pub trait ForwardOperation {
     fn forward(&self, dns: &mut DNS) -> Result<DNS, Error>;
 }
 
+/// the async version of [`ForwardOperation`].
+pub trait AsyncForwardOperation: Send + Sync {
+    fn forward_async<'a>(
+        &'a self,
+        dns: &'a mut DNS,
+    ) -> Pin<Box<dyn Future<Output = Result<DNS, Error>> + Send + 'a>>;
+}
+
 pub struct DefaultForward {
     target: String,
     protocol: String,
     port: String,
+    timeout: Duration,
+    hardening: HardeningOptions,
+    dnssec_requested: bool,
+    edns_probe: Vec<u16>,
+    cache: Option<Mutex<Cache>>,
 
     socket: Option<UdpSocket>,
+    pipeline: tokio::sync::Mutex<Option<Arc<TcpPipeline>>>,
 }
 
 impl DefaultForward {
@@ -26,7 +98,13 @@ impl DefaultForward {
             target: "".to_string(),
             protocol: "".to_string(),
             port: "0".to_string(),
+            timeout: DEFAULT_FORWARD_TIMEOUT,
+            hardening: HardeningOptions::new(),
+            dnssec_requested: false,
+            edns_probe: vec![],
+            cache: None,
             socket: None,
+            pipeline: tokio::sync::Mutex::new(None),
         }
     }
 
@@ -44,39 +122,314 @@ impl DefaultForward {
         return self;
     }
 
+    pub fn with_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = timeout;
+        return self;
+    }
+
+    pub fn with_hardening(&mut self, hardening: HardeningOptions) -> &mut Self {
+        self.hardening = hardening;
+        return self;
+    }
+
+    /// with_dnssec_requested: set the DO bit on all the forwarded requests regardless of the client,
+    /// for the validating resolver. See [`DNS::set_dnssec_requested`].
+    pub fn with_dnssec_requested(&mut self, dnssec_requested: bool) -> &mut Self {
+        self.dnssec_requested = dnssec_requested;
+        return self;
+    }
+
+    /**
+    with_edns_probe: the udp payload sizes advertised in turn when the forwarded udp request times out,
+    such as `[4096, 1232, 512]`, the request is given up after the last one times out. It works around
+    the path dropping the large fragmented responses. The probe is off by default.
+
+    ref: https://www.rfc-editor.org/rfc/rfc6891#section-6.2.5
+     */
+    pub fn with_edns_probe(&mut self, sizes: Vec<u16>) -> &mut Self {
+        self.edns_probe = sizes;
+        return self;
+    }
+
+    /// with_cache: answer the repeated requests by the cached responses until they expire, see
+    /// [`Cache`]. The cache is off by default.
+    pub fn with_cache(&mut self, cache: bool) -> &mut Self {
+        self.cache = cache.then(|| Mutex::new(Cache::new()));
+        return self;
+    }
+
     pub fn start(&mut self) {
         // https://stackoverflow.com/questions/7382906/cant-assign-requested-address-c-udp-sockets/7383682#7383682
-        let addr = fmt::format(format_args!("{}:{}", "0.0.0.0", self.port));
-        self.socket = Some(UdpSocket::bind(addr).expect("failed bind udp socket"));
+        let port = match self.hardening.random_source_port {
+            true => "0",
+            false => self.port.as_str(),
+        };
+        let addr = fmt::format(format_args!("{}:{}", "0.0.0.0", port));
+        let socket = UdpSocket::bind(addr).expect("failed bind udp socket");
+        socket
+            .set_read_timeout(Some(self.timeout))
+            .expect("failed set read timeout");
+        self.socket = Some(socket);
     }
 
     // pub fn receive_resp(&self) -> Result<DNS, Error> {}
-}
 
-impl ForwardOperation for DefaultForward {
-    fn forward(&self, dns: &mut DNS) -> Result<DNS, Error> {
-        match self.protocol.as_str() {
-            "udp" => {
-                println!("encode dns = {:?}", &dns.encode(true)?);
-                let _ = self
-                    .socket
-                    .as_ref()
-                    .unwrap()
-                    .send_to(&dns.encode(false)?, &self.target);
+    /// forward_async: the async version of [`ForwardOperation::forward`] base on tokio, so it will not
+    /// block the runtime. The udp and tcp(2 octets length prefixed) protocols are supported, the tcp
+    /// connection is kept and the concurrent requests are pipelined on it, see [`TcpPipeline`].
+    pub async fn forward_async(&self, dns: &mut DNS) -> Result<DNS, Error> {
+        if let Some(resp) = self.cached(dns) {
+            return Ok(resp);
+        }
+        let cookie = self.harden_request(dns);
 
-                let mut buff = [0u8; 512];
-                let (data_len, _) = self.socket.as_ref().unwrap().recv_from(&mut buff)?;
-                let resp = &buff[..data_len];
-                println!("resp = {:?}", resp);
+        let mut resp = match self.protocol.as_str() {
+            "udp" if self.edns_probe.is_empty() => self.query_udp(dns, cookie).await?,
+            "udp" => self.probe_udp(dns, cookie).await?,
+            "tcp" => {
+                let mut new_dns = self.pipeline().await?.query(dns).await?;
+                self.verify_response(dns, &mut new_dns, cookie)?;
+                new_dns
+            }
+            _ => {
+                return Err(Error::msg(
+                    "not found the match protocol to forward the dns request",
+                ))
+            }
+        };
+        self.cache_response(&mut resp);
 
-                let new_dns: DNS = DNS::from(resp)?;
+        Ok(resp)
+    }
+
+    /// the cached response to the request, None if the cache is off or missed.
+    fn cached(&self, dns: &mut DNS) -> Option<DNS> {
+        self.cache.as_ref()?.lock().unwrap().get(dns)
+    }
 
-                Ok(new_dns)
+    /// cache the response verified against the request if the cache is on.
+    fn cache_response(&self, resp: &mut DNS) {
+        if let Some(cache) = &self.cache {
+            let _ = cache.lock().unwrap().insert(resp);
+        }
+    }
+
+    /// the udp of [`ForwardOperation::forward`] by the socket of [`DefaultForward::start`].
+    fn forward_udp(&self, dns: &mut DNS) -> Result<DNS, Error> {
+        let cookie = self.harden_request(dns);
+        let _ = self
+            .socket
+            .as_ref()
+            .unwrap()
+            .send_to(&dns.encode(false)?, &self.target);
+
+        // the responses unparsable or failed the verification, such as the one to the wrong
+        // question, may be spoofed, they are dropped and the reading continues until the
+        // timeout.
+        let socket = self.socket.as_ref().unwrap();
+        let deadline = Instant::now() + self.timeout;
+        // the response may be as large as the payload advertised by the OPT of the request
+        let mut buff = vec![0u8; dns.request_udp_payload().max(DEFAULT_UDP_PAYLOAD) as usize];
+        loop {
+            let remain = deadline.saturating_duration_since(Instant::now());
+            if remain.is_zero() {
+                return Err(anyhow!("no response to the request verified in time"));
+            }
+            socket.set_read_timeout(Some(remain))?;
+            let (data_len, _) = socket.recv_from(&mut buff)?;
+            let mut new_dns = match DNS::from(&buff[..data_len]) {
+                Ok(new_dns) => new_dns,
+                Err(_) => continue,
+            };
+            if self.verify_response(dns, &mut new_dns, cookie).is_ok() {
+                return Ok(new_dns);
+            }
+        }
+    }
+
+    /// send the request over udp and wait for the response verified against it, the buffer holds the
+    /// advertised udp payload. The responses unparsable or failed the verification are dropped and the
+    /// reading continues until the timeout, same as [`ForwardOperation::forward`].
+    async fn query_udp(&self, dns: &mut DNS, cookie: Option<[u8; 8]>) -> Result<DNS, Error> {
+        let raw = dns.encode(false)?;
+        let sock = tokio::net::UdpSocket::bind("0.0.0.0:0").await?;
+        sock.send_to(&raw, &self.target).await?;
+
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        let mut buff = vec![0u8; dns.request_udp_payload().max(DEFAULT_UDP_PAYLOAD) as usize];
+        loop {
+            let (data_len, _) = timeout_at(deadline, sock.recv_from(&mut buff)).await??;
+            let mut new_dns = match DNS::from(&buff[..data_len]) {
+                Ok(new_dns) => new_dns,
+                Err(_) => continue,
+            };
+            if self.verify_response(dns, &mut new_dns, cookie).is_ok() {
+                return Ok(new_dns);
+            }
+        }
+    }
+
+    /// send the copies of the request over udp advertising the sizes of the edns probe in turn until
+    /// one is answered, only the timeout moves to the next size.
+    async fn probe_udp(&self, dns: &mut DNS, cookie: Option<[u8; 8]>) -> Result<DNS, Error> {
+        let mut last_err = anyhow!("no udp payload size to probe");
+        for &size in &self.edns_probe {
+            // the retries keep the id of the request, which the response is verified against
+            let mut attempt = dns.clone_for_retry(false);
+            if let Ok(mut prr) = attempt
+                .with_edns()
+                .edns()
+                .unwrap()
+                .borrow_mut()
+                .convert_pseudo()
+            {
+                prr.with_udp_payload(size);
             }
-            _ => Err(Error::msg(
-                "not found the match protocol to forward the dns request",
-            )),
+            match self.query_udp(&mut attempt, cookie).await {
+                Err(e) if e.is::<Elapsed>() => last_err = e,
+                result => return result,
+            }
+        }
+
+        Err(last_err)
+    }
+
+    /// the pipeline to the target, it is connected at the first tcp request and reconnected when closed.
+    async fn pipeline(&self) -> Result<Arc<TcpPipeline>, Error> {
+        let mut pipeline = self.pipeline.lock().await;
+        if let Some(p) = pipeline.as_ref().filter(|p| !p.is_closed()) {
+            return Ok(p.clone());
+        }
+
+        let mut p = timeout(self.timeout, TcpPipeline::connect(&self.target)).await??;
+        p.with_timeout(self.timeout);
+        let p = Arc::new(p);
+        *pipeline = Some(p.clone());
+
+        Ok(p)
+    }
+
+    /// prepare the request with the hardening options and the DO bit, return the client cookie if it is sent.
+    fn harden_request(&self, dns: &mut DNS) -> Option<[u8; 8]> {
+        if self.dnssec_requested {
+            dns.set_dnssec_requested();
+        }
+        if self.hardening.randomize_case {
+            dns.randomize_case();
+        }
+        if !self.hardening.cookies {
+            return None;
+        }
+
+        let cookie: [u8; 8] = rand::thread_rng().gen();
+        let opt = dns.with_edns().edns().unwrap();
+        if let Ok(mut prr) = opt.borrow_mut().convert_pseudo() {
+            prr.with_option(EDNS_OPTION_COOKIE, &cookie);
+        }
+
+        Some(cookie)
+    }
+
+    /// verify the response with the hardening options.
+    fn verify_response(
+        &self,
+        req: &mut DNS,
+        resp: &mut DNS,
+        cookie: Option<[u8; 8]>,
+    ) -> Result<(), Error> {
+        if self.hardening.id_match {
+            check_id(req.head().id(), resp)?;
+        }
+        check_question(req, resp)?;
+        check_case(req, resp)?;
+        if let Some(cookie) = cookie {
+            check_cookie(&cookie, resp)?;
         }
+
+        Ok(())
+    }
+}
+
+/// the id of the response must be the same as the request.
+fn check_id(id: u16, resp: &mut DNS) -> Result<(), Error> {
+    if resp.head().id() != id {
+        return Err(anyhow!(
+            "the response id {} not match the request id {}",
+            resp.head().id(),
+            id
+        ));
+    }
+
+    Ok(())
+}
+
+/// the response must echo the questions of the request, the names are compared case-insensitively.
+fn check_question(req: &DNS, resp: &DNS) -> Result<(), Error> {
+    let (req, resp) = (&req.ques().0, &resp.ques().0);
+    if req.len() != resp.len() || !req.iter().zip(resp.iter()).all(|(a, b)| a.matches(b)) {
+        return Err(anyhow!(
+            "the question of the response not match the request"
+        ));
+    }
+
+    Ok(())
+}
+
+/// the response must echo the question names in the same case. (dns 0x20)
+fn check_case(req: &DNS, resp: &DNS) -> Result<(), Error> {
+    if !req.case_matches(resp) {
+        return Err(anyhow!(
+            "the case of the response question not match the request"
+        ));
+    }
+
+    Ok(())
+}
+
+/// the response must echo the client cookie. (RFC 7873)
+fn check_cookie(cookie: &[u8; 8], resp: &DNS) -> Result<(), Error> {
+    let echoed = resp
+        .additional()
+        .0
+        .iter()
+        .any(|rr| match rr.borrow().rdata() {
+            RDataType::OPT(opt) => opt
+                .option(EDNS_OPTION_COOKIE)
+                .is_some_and(|data| data.starts_with(cookie)),
+            _ => false,
+        });
+    if !echoed {
+        return Err(anyhow!("the response not echo the client cookie"));
+    }
+
+    Ok(())
+}
+
+impl AsyncForwardOperation for DefaultForward {
+    fn forward_async<'a>(
+        &'a self,
+        dns: &'a mut DNS,
+    ) -> Pin<Box<dyn Future<Output = Result<DNS, Error>> + Send + 'a>> {
+        Box::pin(DefaultForward::forward_async(self, dns))
+    }
+}
+
+impl ForwardOperation for DefaultForward {
+    fn forward(&self, dns: &mut DNS) -> Result<DNS, Error> {
+        if let Some(resp) = self.cached(dns) {
+            return Ok(resp);
+        }
+        let mut resp = match self.protocol.as_str() {
+            "udp" => self.forward_udp(dns)?,
+            _ => {
+                return Err(Error::msg(
+                    "not found the match protocol to forward the dns request",
+                ))
+            }
+        };
+        self.cache_response(&mut resp);
+
+        Ok(resp)
     }
 }
 
@@ -324,7 +677,7 @@ mod tests {
     //         .with_class(class)
     //         .with_name(domain)
     //         .with_rdata(RDataType::TSig(tsig));
-    //     dns.with_additional(Rc::new(RefCell::new(tsig_rr)));
+    //     dns.add_additional_rr(Rc::new(RefCell::new(tsig_rr)));
 
     //     let mut fwd: DefaultForward = DefaultForward::new();
     //     let port = 31514;
@@ -452,6 +805,328 @@ mod tests {
         }
     }
 
+    /// serve one udp request, reply the google.com A response echoing the request id and question,
+    /// then the response is patched before sending.
+    async fn async_test_server(patch: fn(&[u8], &mut Vec<u8>)) -> String {
+        async_test_server_patches(vec![patch]).await
+    }
+
+    /// same as [`async_test_server`], but reply a response for each of the patches in turn.
+    async fn async_test_server_patches(patches: Vec<fn(&[u8], &mut Vec<u8>)>) -> String {
+        let sock = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = sock.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let mut buff = [0u8; 512];
+            let (size, peer) = sock.recv_from(&mut buff).await.unwrap();
+            for patch in patches {
+                let mut resp = fs::read("./test_dns_raw/google.com/1_1").unwrap();
+                // id
+                (resp[0], resp[1]) = (buff[0], buff[1]);
+                // question: google.com A IN
+                resp[12..28].copy_from_slice(&buff[12..28]);
+                patch(&buff[..size], &mut resp);
+                sock.send_to(&resp, peer).await.unwrap();
+            }
+        });
+
+        addr
+    }
+
+    #[test]
+    fn test_forward_wrong_question() {
+        // reply the responses of the patches in turn, echoing the request id and question
+        let server = |patches: Vec<fn(&mut Vec<u8>)>| {
+            let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let addr = sock.local_addr().unwrap().to_string();
+            thread::spawn(move || {
+                let mut buff = [0u8; 512];
+                let (_, peer) = sock.recv_from(&mut buff).unwrap();
+                for patch in patches {
+                    let mut resp = fs::read("./test_dns_raw/google.com/1_1").unwrap();
+                    (resp[0], resp[1]) = (buff[0], buff[1]);
+                    resp[12..28].copy_from_slice(&buff[12..28]);
+                    patch(&mut resp);
+                    sock.send_to(&resp, peer).unwrap();
+                }
+            });
+            addr
+        };
+        // the question of another name: gxogle.com
+        let wrong_name: fn(&mut Vec<u8>) = |resp| resp[14] = b'x';
+        // the unparsable response cut in the question
+        let truncated: fn(&mut Vec<u8>) = |resp| resp.truncate(20);
+
+        let mut dns = DNS::new();
+        dns.with_ques("google.com", TYPE_A, CLASS_IN);
+        let mut fwd = DefaultForward::new();
+        fwd.with_target(&server(vec![wrong_name, truncated, |_| {}]))
+            .with_protocol("udp")
+            .with_timeout(Duration::from_millis(500))
+            .start();
+        let resp = fwd.forward(&mut dns).unwrap();
+        assert_eq!(dns.header().id(), resp.header().id());
+        assert_eq!(
+            "google.com",
+            resp.ques().0[0]
+                .qname()
+                .encode_to_str()
+                .trim_end_matches('.')
+        );
+
+        // only the responses to the wrong question or unparsable
+        fwd.with_target(&server(vec![wrong_name, truncated]));
+        assert_eq!(true, fwd.forward(&mut dns).is_err());
+    }
+
+    #[test]
+    fn test_forward_large_udp_response() {
+        // reply the response larger than the default udp payload, within the payload of the request
+        let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = sock.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            let mut buff = [0u8; 512];
+            let (size, peer) = sock.recv_from(&mut buff).unwrap();
+            let mut resp = DNS::from(&buff[..size]).unwrap();
+            resp.into_response();
+            for i in 0..100 {
+                let ip = std::net::Ipv4Addr::new(10, 0, 0, i);
+                resp.add_answer_rr(Rc::new(RefCell::new(RR::a("google.com", 60, ip))));
+            }
+            let raw = resp.encode(false).unwrap();
+            assert_eq!(true, raw.len() > DEFAULT_UDP_PAYLOAD as usize);
+            sock.send_to(&raw, peer).unwrap();
+        });
+
+        let mut dns = DNS::new();
+        dns.with_ques("google.com", TYPE_A, CLASS_IN);
+        let opt = dns.with_edns().edns().unwrap();
+        opt.borrow_mut()
+            .convert_pseudo()
+            .unwrap()
+            .with_udp_payload(4096);
+        let mut fwd = DefaultForward::new();
+        fwd.with_target(&addr)
+            .with_protocol("udp")
+            .with_timeout(Duration::from_millis(500))
+            .start();
+        let resp = fwd.forward(&mut dns).unwrap();
+        assert_eq!(100, resp.a_records().len());
+    }
+
+    #[tokio::test]
+    async fn test_forward_async() {
+        let mut dns = DNS::new();
+        dns.with_ques("google.com", TYPE_A, CLASS_IN);
+
+        let mut fwd = DefaultForward::new();
+        fwd.with_target(&async_test_server(|_, _| {}).await)
+            .with_protocol("udp")
+            .with_timeout(Duration::from_millis(500));
+        let mut resp = fwd.forward_async(&mut dns).await.unwrap();
+        assert_eq!(dns.head().id(), resp.head().id());
+        assert_eq!(
+            vec![std::net::Ipv4Addr::new(93, 46, 8, 90)],
+            resp.a_records()
+        );
+
+        // wrong id
+        fwd.with_target(&async_test_server(|_, resp| resp[1] = resp[1].wrapping_add(1)).await);
+        assert_eq!(true, fwd.forward_async(&mut dns).await.is_err());
+
+        // the response alters the case of the question name
+        dns.randomize_case();
+        fwd.with_target(&async_test_server(|_, _| {}).await);
+        assert_eq!(true, fwd.forward_async(&mut dns).await.is_ok());
+        fwd.with_target(&async_test_server(|_, resp| resp[13] ^= 0x20).await);
+        assert_eq!(true, fwd.forward_async(&mut dns).await.is_err());
+
+        // the DO bit is set on the forwarded request
+        let mut dns = DNS::new();
+        dns.with_ques("google.com", TYPE_A, CLASS_IN);
+        fwd.with_dnssec_requested(true)
+            .with_target(&async_test_server(|_, _| {}).await);
+        assert_eq!(true, fwd.forward_async(&mut dns).await.is_ok());
+        assert_eq!(true, dns.dnssec_ok());
+        fwd.with_dnssec_requested(false);
+
+        // nobody answers
+        fwd.with_target(&async_test_server(|_, _| {}).await)
+            .with_timeout(Duration::from_millis(100));
+        fwd.forward_async(&mut dns).await.unwrap();
+        assert_eq!(true, fwd.forward_async(&mut dns).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_forward_cache() {
+        let query = |id: u16| {
+            let mut dns = DNS::new();
+            dns.head().with_id(id);
+            dns.with_ques("google.com", TYPE_A, CLASS_IN);
+            dns
+        };
+
+        // the test server answers only the first request
+        let mut fwd = DefaultForward::new();
+        fwd.with_target(&async_test_server(|_, _| {}).await)
+            .with_protocol("udp")
+            .with_timeout(Duration::from_millis(200))
+            .with_cache(true);
+        let resp = fwd.forward_async(&mut query(1)).await.unwrap();
+        assert_eq!(1, resp.header().id());
+        let resp = fwd.forward_async(&mut query(2)).await.unwrap();
+        assert_eq!(2, resp.header().id());
+        assert_eq!(
+            vec![std::net::Ipv4Addr::new(93, 46, 8, 90)],
+            resp.a_records()
+        );
+
+        // without the cache
+        fwd.with_target(&async_test_server(|_, _| {}).await)
+            .with_cache(false);
+        assert_eq!(true, fwd.forward_async(&mut query(1)).await.is_ok());
+        assert_eq!(true, fwd.forward_async(&mut query(2)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_forward_async_drop_responses() {
+        let mut dns = DNS::new();
+        dns.with_ques("google.com", TYPE_A, CLASS_IN);
+        // the unparsable response, the response of the wrong id and the one to the wrong question
+        let dropped: Vec<fn(&[u8], &mut Vec<u8>)> = vec![
+            |_, resp| resp.truncate(20),
+            |_, resp| resp[1] = resp[1].wrapping_add(1),
+            |_, resp| resp[14] = b'x',
+        ];
+
+        let mut fwd = DefaultForward::new();
+        fwd.with_target(
+            &async_test_server_patches([dropped.clone(), vec![|_, _| {}]].concat()).await,
+        )
+        .with_protocol("udp")
+        .with_timeout(Duration::from_millis(500));
+        let resp = fwd.forward_async(&mut dns).await.unwrap();
+        assert_eq!(dns.header().id(), resp.header().id());
+
+        fwd.with_target(&async_test_server_patches(dropped).await);
+        assert_eq!(true, fwd.forward_async(&mut dns).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_forward_edns_probe() {
+        // the stub only replies to the request advertising the 1232 octets udp payload
+        let sock = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = sock.local_addr().unwrap().to_string();
+        let server = tokio::spawn(async move {
+            let mut sizes = vec![];
+            let mut buff = [0u8; 512];
+            loop {
+                let (size, peer) = sock.recv_from(&mut buff).await.unwrap();
+                let payload = DNS::from(&buff[..size]).unwrap().request_udp_payload();
+                sizes.push(payload);
+                if payload != 1232 {
+                    continue;
+                }
+                let mut resp = fs::read("./test_dns_raw/google.com/1_1").unwrap();
+                (resp[0], resp[1]) = (buff[0], buff[1]);
+                resp[12..28].copy_from_slice(&buff[12..28]);
+                sock.send_to(&resp, peer).await.unwrap();
+                return sizes;
+            }
+        });
+
+        let mut dns = DNS::new();
+        dns.with_ques("google.com", TYPE_A, CLASS_IN);
+        let mut fwd = DefaultForward::new();
+        fwd.with_target(&addr)
+            .with_protocol("udp")
+            .with_timeout(Duration::from_millis(100))
+            .with_edns_probe(vec![4096, 1232, 512]);
+        let resp = fwd.forward_async(&mut dns).await.unwrap();
+        // the request itself is not altered by the probe
+        assert_eq!(true, dns.edns().is_none());
+        assert_eq!(
+            vec![std::net::Ipv4Addr::new(93, 46, 8, 90)],
+            resp.a_records()
+        );
+        assert_eq!(vec![4096, 1232], server.await.unwrap());
+
+        // nobody answers any of the sizes
+        let sock = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        fwd.with_target(&sock.local_addr().unwrap().to_string());
+        assert_eq!(true, fwd.forward_async(&mut dns).await.is_err());
+    }
+
+    /// append an OPT RR echoing the client cookie (the last 8 octets of the query) with a server cookie.
+    fn echo_cookie(query: &[u8], resp: &mut Vec<u8>) {
+        let client = &query[query.len() - 8..];
+        // the raw file holds two responses, keep the first one
+        resp.truncate(54);
+        resp.extend([0, 0, 41, 2, 0, 0, 0, 0, 0, 0, 20, 0, 10, 0, 16]);
+        resp.extend(client);
+        resp.extend([1, 2, 3, 4, 5, 6, 7, 8]);
+        let arcount = u16::from_be_bytes([resp[10], resp[11]]) + 1;
+        resp[10..12].copy_from_slice(&arcount.to_be_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_forward_hardening() {
+        let new_dns = || {
+            let mut dns = DNS::new();
+            dns.with_ques("google.com", TYPE_A, CLASS_IN);
+            dns
+        };
+        let mut fwd = DefaultForward::new();
+        fwd.with_protocol("udp")
+            .with_hardening(HardeningOptions::all())
+            .with_timeout(Duration::from_millis(500));
+
+        fwd.with_target(&async_test_server(echo_cookie).await);
+        let mut dns = new_dns();
+        let resp = fwd.forward_async(&mut dns).await.unwrap();
+        assert_eq!(
+            vec![std::net::Ipv4Addr::new(93, 46, 8, 90)],
+            resp.a_records()
+        );
+
+        // wrong id
+        fwd.with_target(
+            &async_test_server(|query, resp| {
+                echo_cookie(query, resp);
+                resp[1] = resp[1].wrapping_add(1);
+            })
+            .await,
+        );
+        assert_eq!(true, fwd.forward_async(&mut new_dns()).await.is_err());
+
+        // wrong case
+        fwd.with_target(
+            &async_test_server(|query, resp| {
+                echo_cookie(query, resp);
+                resp[13] = if resp[13] == b'g' { b'G' } else { b'g' };
+            })
+            .await,
+        );
+        assert_eq!(true, fwd.forward_async(&mut new_dns()).await.is_err());
+
+        // the cookie is not echoed
+        fwd.with_target(&async_test_server(|_, _| {}).await);
+        assert_eq!(true, fwd.forward_async(&mut new_dns()).await.is_err());
+
+        // the wrong id is accepted when the id match is off
+        fwd.with_hardening(HardeningOptions {
+            id_match: false,
+            ..HardeningOptions::all()
+        })
+        .with_target(
+            &async_test_server(|query, resp| {
+                echo_cookie(query, resp);
+                resp[1] = resp[1].wrapping_add(1);
+            })
+            .await,
+        );
+        assert_eq!(true, fwd.forward_async(&mut new_dns()).await.is_ok());
+    }
+
     #[test]
     fn test_domain_single() {
         let domain = "google.com";
@@ -462,11 +1137,7 @@ mod tests {
         dns.with_ques(domain, typ, class);
         dns.head().with_rd(true);
         let mut rr = RR::new();
-        let opt = OPT {
-            code: 0,
-            length: 0,
-            data: Vec::new(),
-        };
+        let opt = OPT::new();
         let mut prr = rr
             .with_type(TYPE_OPT)
             .with_rdata(RDataType::OPT(opt))
@@ -476,7 +1147,7 @@ mod tests {
             .with_udp_payload(512)
             .with_version(0);
 
-        dns.with_authority(Rc::new(RefCell::new(rr)));
+        dns.add_authority_rr(Rc::new(RefCell::new(rr)));
 
         let mut fwd: DefaultForward = DefaultForward::new();
         let port = 31114;
@@ -45,8 +45,14 @@ Request Topology:
 2. The next Resolver receive a dns request, perform the same logic like the step 1.
 */
 
-mod forward;
+pub mod cache;
+#[cfg(feature = "doq")]
+pub mod doq;
+pub mod forward;
+pub mod pipeline;
+pub mod recursive;
 mod resolver;
+pub mod single_flight;
 
 use crate::{
     dns::{Question, RR},
@@ -0,0 +1,237 @@
+/*!
+ref: https://www.rfc-editor.org/rfc/rfc7766#section-6.2.1.1
+
+# Query Pipelining
+
+The client may send multiple queries over one TCP connection without waiting for the responses, and
+the server may answer them out of order. Each message is prefixed with a 2 octet length field:
+```shell
++--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+|                     LENGTH                    |
++--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/                   DNS MESSAGE                 /
++--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+```
+the responses are matched to the outstanding queries by the message ID, so the IDs of the queries
+in flight on a connection must be unique.
+ */
+
+use crate::{
+    dns::tcp::{encode_tcp, read_tcp},
+    DNS,
+};
+use anyhow::{anyhow, Error};
+use rand::Rng;
+use std::{
+    collections::HashMap,
+    io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    io::AsyncWriteExt,
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpStream,
+    },
+    sync::{self, oneshot},
+    task::JoinHandle,
+    time::timeout,
+};
+
+/// the default timeout waiting for the response of a query.
+const DEFAULT_PIPELINE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Inflight: the outstanding queries of a connection, the waiting query of each ID.
+pub type Inflight = HashMap<u16, oneshot::Sender<DNS>>;
+
+/// TcpPipeline: a TCP connection carrying the pipelined queries, a reader task dispatches each
+/// response to the waiting query by the ID.
+pub struct TcpPipeline {
+    writer: sync::Mutex<OwnedWriteHalf>,
+    inflight: Arc<Mutex<Inflight>>,
+    timeout: Duration,
+
+    reader: JoinHandle<()>,
+}
+
+impl TcpPipeline {
+    pub async fn connect(target: &str) -> Result<Self, Error> {
+        let stream = timeout(DEFAULT_PIPELINE_TIMEOUT, TcpStream::connect(target)).await??;
+        let (rh, wh) = stream.into_split();
+        let inflight = Arc::new(Mutex::new(Inflight::new()));
+
+        Ok(Self {
+            writer: sync::Mutex::new(wh),
+            inflight: inflight.clone(),
+            timeout: DEFAULT_PIPELINE_TIMEOUT,
+            reader: tokio::spawn(read_responses(rh, inflight)),
+        })
+    }
+
+    pub fn with_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = timeout;
+        return self;
+    }
+
+    /// is_closed: whether the connection is closed, the queries on it fail then.
+    pub fn is_closed(&self) -> bool {
+        self.reader.is_finished()
+    }
+
+    /// query: send the dns and wait for its response. The dns is sent with an ID unique among the
+    /// queries in flight, the response carries the original ID of the dns. The caller verifies the
+    /// question of the response.
+    pub async fn query(&self, dns: &mut DNS) -> Result<DNS, Error> {
+        if self.is_closed() {
+            return Err(anyhow!("the pipeline connection is closed"));
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        let wire_id = {
+            let mut inflight = self.inflight.lock().unwrap();
+            let mut rng = rand::thread_rng();
+            let mut wire_id: u16 = rng.gen();
+            while inflight.contains_key(&wire_id) {
+                wire_id = rng.gen();
+            }
+            inflight.insert(wire_id, sender);
+            wire_id
+        };
+        let guard = InflightGuard {
+            inflight: &self.inflight,
+            id: wire_id,
+        };
+
+        let id = dns.head().id();
+        dns.head().with_id(wire_id);
+        let framed = encode_tcp(dns);
+        dns.head().with_id(id);
+        self.writer.lock().await.write_all(&framed?).await?;
+
+        let mut resp = timeout(self.timeout, receiver)
+            .await?
+            .map_err(|_| anyhow!("the pipeline connection is closed"))?;
+        drop(guard);
+        resp.head().with_id(id);
+
+        Ok(resp)
+    }
+}
+
+impl Drop for TcpPipeline {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// InflightGuard remove the query from the inflight when it finished, failed or cancelled.
+struct InflightGuard<'a> {
+    inflight: &'a Mutex<Inflight>,
+    id: u16,
+}
+
+impl Drop for InflightGuard<'_> {
+    fn drop(&mut self) {
+        self.inflight.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// read the responses until the connection is closed, each one is sent to the query of its ID, the
+/// unmatched or unparsable ones are dropped.
+async fn read_responses(mut reader: OwnedReadHalf, inflight: Arc<Mutex<Inflight>>) {
+    loop {
+        let mut resp = match read_tcp(&mut reader).await {
+            Ok(resp) => resp,
+            // the unparsable message is read entirely, the next one follows
+            Err(e) if e.downcast_ref::<io::Error>().is_none() => continue,
+            Err(_) => break,
+        };
+        let sender = inflight.lock().unwrap().remove(&resp.head().id());
+        if let Some(sender) = sender {
+            let _ = sender.send(resp);
+        }
+    }
+
+    // the waiting queries fail
+    inflight.lock().unwrap().clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TcpPipeline;
+    use crate::{
+        components::resolver::forward::DefaultForward,
+        dns::{
+            tcp::{encode_tcp, read_tcp},
+            CLASS_IN, RR, TYPE_A,
+        },
+        DNS,
+    };
+    use std::{cell::RefCell, net::Ipv4Addr, rc::Rc};
+    use tokio::{io::AsyncWriteExt, net::TcpListener};
+
+    /// read the queries, then answer them in the reverse order.
+    async fn reversed_server(queries: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut raws = vec![];
+            for _ in 0..queries {
+                raws.push(read_tcp(&mut stream).await.unwrap().raw().to_vec());
+            }
+            for raw in raws.iter().rev() {
+                let resp = {
+                    let mut dns = DNS::from(raw).unwrap();
+                    let name = dns.ques().0[0].qname().encode_to_str();
+                    let answer = RR::a(&name, 60, Ipv4Addr::new(10, 0, 0, 1));
+                    dns.into_response()
+                        .add_answer_rr(Rc::new(RefCell::new(answer)));
+                    encode_tcp(&mut dns).unwrap()
+                };
+                stream.write_all(&resp).await.unwrap();
+            }
+        });
+
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_tcp_pipeline() {
+        let pipeline = TcpPipeline::connect(&reversed_server(3).await)
+            .await
+            .unwrap();
+
+        let query = |name: &str, id: u16| {
+            let mut dns = DNS::new();
+            dns.head().with_id(id);
+            dns.with_ques(name, TYPE_A, CLASS_IN);
+            dns
+        };
+        // the same id is allowed, the pipeline sends unique ones
+        let (mut q0, mut q1, mut q2) = (query("a.com", 7), query("b.com", 7), query("c.com", 9));
+        let (r0, r1, r2) = tokio::join!(
+            pipeline.query(&mut q0),
+            pipeline.query(&mut q1),
+            pipeline.query(&mut q2)
+        );
+
+        for (name, id, resp) in [("a.com", 7, r0), ("b.com", 7, r1), ("c.com", 9, r2)] {
+            let mut resp = resp.unwrap();
+            assert_eq!(id, resp.head().id());
+            assert_eq!(name, resp.ques().0[0].qname().encode_to_str());
+            assert_eq!(name, resp.answers().0[0].borrow().name());
+        }
+        assert_eq!(true, pipeline.inflight.lock().unwrap().is_empty());
+
+        // the tcp forward pipelines the concurrent requests on one connection
+        let mut fwd = DefaultForward::new();
+        fwd.with_target(&reversed_server(2).await)
+            .with_protocol("tcp");
+        let (mut q0, mut q1) = (query("a.com", 1), query("b.com", 2));
+        let (r0, r1) = tokio::join!(fwd.forward_async(&mut q0), fwd.forward_async(&mut q1));
+        assert_eq!("a.com", r0.unwrap().answers().0[0].borrow().name());
+        assert_eq!("b.com", r1.unwrap().answers().0[0].borrow().name());
+    }
+}
@@ -0,0 +1,270 @@
+/*!
+ref: https://www.rfc-editor.org/rfc/rfc8109
+
+# Priming Queries
+
+The recursive resolver starts with a list of the root server addresses (the root hints), which may be
+outdated. The resolver sends the priming query `. NS IN` to one of the hints, the response lists the
+NS records of the root zone in the answer section, and the A and AAAA records of the root servers in
+the additional section:
+```shell
+;; ANSWER SECTION:
+.                       518400  IN  NS      a.root-servers.net.
+;; ADDITIONAL SECTION:
+a.root-servers.net.     518400  IN  A       198.41.0.4
+a.root-servers.net.     518400  IN  AAAA    2001:503:ba3e::2:30
+```
+the root servers in the response replace the current ones, the priming query should be repeated
+before the TTL of the NS RRset expires.
+ */
+
+use super::forward::{DefaultForward, ForwardOperation};
+use crate::{
+    dns::{rdata::RDataType, CLASS_IN, RCODE_NOERROR, TYPE_NS},
+    DNS,
+};
+use anyhow::{anyhow, Error};
+use rand::Rng;
+use std::{net::IpAddr, time::Duration};
+
+/// the default timeout waiting for the response of the priming query.
+const DEFAULT_PRIME_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// RootServer: the name and the addresses of a root server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootServer {
+    pub name: String,
+    pub addrs: Vec<IpAddr>,
+}
+
+pub struct RecursiveResolver {
+    hints: Vec<String>,
+    roots: Vec<RootServer>,
+    timeout: Duration,
+
+    forward: Option<Box<dyn ForwardOperation>>,
+}
+
+impl RecursiveResolver {
+    pub fn new() -> Self {
+        Self {
+            hints: vec![],
+            roots: vec![],
+            timeout: DEFAULT_PRIME_TIMEOUT,
+            forward: None,
+        }
+    }
+
+    /// with_hint: add the address(`ip:port`) of a root hint, the priming query is sent to the hints in order.
+    pub fn with_hint(&mut self, hint: &str) -> &mut Self {
+        self.hints.push(hint.to_string());
+        return self;
+    }
+
+    pub fn with_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = timeout;
+        return self;
+    }
+
+    /// with_forward: send the queries by the forward instead of the udp to the hints.
+    pub fn with_forward(&mut self, forward: Box<dyn ForwardOperation>) -> &mut Self {
+        self.forward = Some(forward);
+        return self;
+    }
+
+    /// roots: the current root servers.
+    pub fn roots(&self) -> &[RootServer] {
+        &self.roots
+    }
+
+    /// prime: send the priming query `. NS IN` and refresh the root servers by the response. The root
+    /// servers are kept when all the hints fail or the response carries no root server with an address.
+    pub fn prime(&mut self) -> Result<(), Error> {
+        let resp = self.send(&mut priming_query())?;
+        self.refresh(resp)
+    }
+
+    /// resolve: send the query of the client upstream with the DO bit set whatever the client asked,
+    /// so the DNSSEC records are returned for the validation. The query of the client is untouched.
+    pub fn resolve(&self, query: &mut DNS) -> Result<DNS, Error> {
+        let mut upstream = DNS::from(&query.encode(false)?)?;
+        upstream.set_dnssec_requested();
+        self.send(&mut upstream)
+    }
+
+    /// send the query by the forward, or by the udp to the hints in order.
+    fn send(&self, query: &mut DNS) -> Result<DNS, Error> {
+        if let Some(forward) = self.forward.as_ref() {
+            return forward.forward(query);
+        }
+        let mut last_err = None;
+        for hint in &self.hints {
+            let mut forward = DefaultForward::new();
+            forward
+                .with_target(hint)
+                .with_protocol("udp")
+                .with_timeout(self.timeout);
+            forward.start();
+            match forward.forward(query) {
+                Ok(resp) => return Ok(resp),
+                Err(e) => last_err = Some(e),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| anyhow!("no root hint to send the query")))
+    }
+
+    /// refresh the root servers by the NS records of the root and their glue A/AAAA records.
+    fn refresh(&mut self, mut resp: DNS) -> Result<(), Error> {
+        if resp.head().rcode() != RCODE_NOERROR {
+            return Err(anyhow!(
+                "the priming response failed with rcode {}",
+                resp.head().rcode()
+            ));
+        }
+
+        let mut roots: Vec<RootServer> = vec![];
+        for rr in &resp.answers().0 {
+            let rr = rr.borrow();
+            match rr.rdata() {
+                RDataType::NS(ns) if is_root(rr.name()) => roots.push(RootServer {
+                    name: ns.0.trim_end_matches('.').to_lowercase(),
+                    addrs: vec![],
+                }),
+                _ => {}
+            }
+        }
+        for rr in &resp.additional().0 {
+            let rr = rr.borrow();
+            let addr = match rr.rdata() {
+                RDataType::A(a) => IpAddr::V4(a.0),
+                RDataType::AAAA(aaaa) => IpAddr::V6(aaaa.0),
+                _ => continue,
+            };
+            let name = rr.name().trim_end_matches('.').to_lowercase();
+            if let Some(root) = roots.iter_mut().find(|root| root.name == name) {
+                if !root.addrs.contains(&addr) {
+                    root.addrs.push(addr);
+                }
+            }
+        }
+
+        roots.retain(|root| !root.addrs.is_empty());
+        if roots.is_empty() {
+            return Err(anyhow!(
+                "the priming response carries no root server with an address"
+            ));
+        }
+        self.roots = roots;
+
+        Ok(())
+    }
+}
+
+/// the priming query `. NS IN` with a random id, the EDNS is added for the size of the response.
+fn priming_query() -> DNS {
+    let mut dns = DNS::new();
+    dns.head().with_id(rand::thread_rng().gen());
+    dns.with_ques(".", TYPE_NS, CLASS_IN);
+    dns.with_edns();
+    dns
+}
+
+fn is_root(name: &str) -> bool {
+    name.is_empty() || name == "."
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RecursiveResolver, RootServer};
+    use crate::{
+        components::{
+            name_server::{zones::MemZones, NameServer},
+            resolver::forward::ForwardOperation,
+        },
+        dns::{CLASS_IN, RR, TYPE_A},
+        DNS,
+    };
+    use anyhow::Error;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    use tokio::runtime::Runtime;
+
+    /// the server acting as the root, the messages go through the wire format.
+    struct RootForward {
+        server: NameServer,
+        rt: Runtime,
+    }
+
+    impl ForwardOperation for RootForward {
+        fn forward(&self, dns: &mut DNS) -> Result<DNS, Error> {
+            let query = DNS::from(&dns.encode(false)?)?;
+            let mut resp = self.rt.block_on(self.server.query(query));
+            DNS::from(&resp.encode(false)?)
+        }
+    }
+
+    /// answer the query by itself, so the test sees what is sent upstream.
+    struct EchoForward;
+
+    impl ForwardOperation for EchoForward {
+        fn forward(&self, dns: &mut DNS) -> Result<DNS, Error> {
+            DNS::from(&dns.encode(false)?)
+        }
+    }
+
+    #[test]
+    fn test_recursive_resolver_resolve() {
+        let mut resolver = RecursiveResolver::new();
+        resolver.with_forward(Box::new(EchoForward));
+
+        let mut query = DNS::new();
+        query.with_ques("www.example.com", TYPE_A, CLASS_IN);
+        assert_eq!(false, query.dnssec_ok());
+
+        let upstream = resolver.resolve(&mut query).unwrap();
+        assert_eq!(true, upstream.dnssec_ok());
+        assert_eq!(false, query.dnssec_ok());
+    }
+
+    #[test]
+    fn test_recursive_resolver_prime() {
+        let a = Ipv4Addr::new(198, 41, 0, 4);
+        let aaaa: Ipv6Addr = "2001:503:ba3e::2:30".parse().unwrap();
+        let b = Ipv4Addr::new(170, 247, 170, 2);
+        let server = NameServer::from(Box::new(MemZones(vec![
+            RR::ns(".", 518400, "a.root-servers.net"),
+            RR::ns(".", 518400, "b.root-servers.net"),
+            RR::a("a.root-servers.net", 518400, a),
+            RR::aaaa("a.root-servers.net", 518400, aaaa),
+            RR::a("b.root-servers.net", 518400, b),
+        ])));
+
+        let mut resolver = RecursiveResolver::new();
+        resolver.with_forward(Box::new(RootForward {
+            server,
+            rt: Runtime::new().unwrap(),
+        }));
+        assert_eq!(true, resolver.roots().is_empty());
+
+        resolver.prime().unwrap();
+        let mut roots = resolver.roots().to_vec();
+        roots.sort_by(|x, y| x.name.cmp(&y.name));
+        assert_eq!(
+            vec![
+                RootServer {
+                    name: "a.root-servers.net".to_string(),
+                    addrs: vec![IpAddr::V4(a), IpAddr::V6(aaaa)],
+                },
+                RootServer {
+                    name: "b.root-servers.net".to_string(),
+                    addrs: vec![IpAddr::V4(b)],
+                },
+            ],
+            roots
+        );
+
+        // no root hint
+        let mut resolver = RecursiveResolver::new();
+        assert_eq!(true, resolver.prime().is_err());
+    }
+}
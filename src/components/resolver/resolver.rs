@@ -1,9 +1,9 @@
 use std::{cell::RefCell, fs::OpenOptions, rc::Rc};
 
-use anyhow::Error;
+use anyhow::{anyhow, Error};
 use nom::Err;
 
-use crate::dns::RcRf;
+use crate::dns::detect_cname_loop;
 
 use super::{
     forward::{DefaultForward, ForwardOperation},
@@ -80,7 +80,7 @@ impl ResolveOperation for Resolver {
             // TODO: 判断是否满足resolve条件
             if rr.is_some() {
                 // let _rr = rr.unwrap().into_inner();
-                // dns.with_answer(rr.unwrap().into_inner());
+                // dns.add_answer_rr(rr.unwrap().into_inner());
                 return Ok(());
             }
         }
@@ -89,6 +89,10 @@ impl ResolveOperation for Resolver {
             peer.resolve(dns, recursive, from_id)?;
         }
 
+        if let Some(name) = detect_cname_loop(&dns.answers().0) {
+            return Err(anyhow!("cname loop detected at {}", name));
+        }
+
         Ok(())
     }
 
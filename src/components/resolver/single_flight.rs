@@ -0,0 +1,198 @@
+/*!
+SingleFlight: coalesce the concurrent identical queries into one upstream query.
+
+Under load, many clients may ask for the same name simultaneously. While one upstream query of
+`(name, type, class)` is in flight, the other identical queries wait for the same result instead of
+sending their own. The shared result is the wire format of the response, every waiter decodes it and
+gets the response with its own id.
+ */
+
+use super::forward::AsyncForwardOperation;
+use crate::{
+    dns::{Class, Type},
+    DNS,
+};
+use anyhow::{anyhow, Error};
+use std::{collections::HashMap, sync::Mutex};
+use tokio::sync::broadcast;
+
+/// the shared result of a flight, the error is shared by its message.
+type Shared = Result<Vec<u8>, String>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FlightKey {
+    name: String,
+    typ: Type,
+    class: Class,
+}
+
+impl FlightKey {
+    /// the key of the first question of the dns, the name is case-insensitive.
+    pub fn from(dns: &DNS) -> Result<Self, Error> {
+        let ques = dns
+            .ques()
+            .0
+            .first()
+            .ok_or(anyhow!("the dns has no question"))?;
+
+        Ok(Self {
+            name: ques.qname().encode_to_str().to_ascii_lowercase(),
+            typ: ques.qtype(),
+            class: ques.qclass(),
+        })
+    }
+}
+
+pub struct SingleFlight<F: AsyncForwardOperation> {
+    forward: F,
+    inflight: Mutex<HashMap<FlightKey, broadcast::Sender<Shared>>>,
+}
+
+impl<F: AsyncForwardOperation> SingleFlight<F> {
+    pub fn new(forward: F) -> Self {
+        Self {
+            forward,
+            inflight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// query: forward the dns through the wrapped forward, or wait for the identical query in flight.
+    pub async fn query(&self, dns: &mut DNS) -> Result<DNS, Error> {
+        let key = FlightKey::from(dns)?;
+        let id = dns.head().id();
+
+        let waiter = {
+            let mut inflight = self.inflight.lock().unwrap();
+            match inflight.get(&key) {
+                Some(sender) => Some(sender.subscribe()),
+                None => {
+                    inflight.insert(key.clone(), broadcast::channel(1).0);
+                    None
+                }
+            }
+        };
+
+        let raw = match waiter {
+            Some(mut receiver) => receiver
+                .recv()
+                .await
+                .map_err(|_| anyhow!("the leader of the flight is gone"))?
+                .map_err(Error::msg)?,
+            None => {
+                let mut guard = FlightGuard {
+                    inflight: &self.inflight,
+                    key: Some(key),
+                };
+                let shared = match self.forward.forward_async(dns).await {
+                    Ok(mut resp) => resp.encode(false).map_err(|e| e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                };
+                guard.land(shared.clone());
+                shared.map_err(Error::msg)?
+            }
+        };
+
+        let mut resp = DNS::from(&raw)?;
+        resp.head().with_id(id);
+
+        Ok(resp)
+    }
+}
+
+/// FlightGuard remove the flight when the leader finished or cancelled, so the waiters never hang.
+struct FlightGuard<'a> {
+    inflight: &'a Mutex<HashMap<FlightKey, broadcast::Sender<Shared>>>,
+    key: Option<FlightKey>,
+}
+
+impl FlightGuard<'_> {
+    /// remove the flight and broadcast the result to the waiters.
+    fn land(&mut self, shared: Shared) {
+        if let Some(key) = self.key.take() {
+            if let Some(sender) = self.inflight.lock().unwrap().remove(&key) {
+                let _ = sender.send(shared);
+            }
+        }
+    }
+}
+
+impl Drop for FlightGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.inflight.lock().unwrap().remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::{CLASS_IN, TYPE_A};
+    use std::{
+        fs,
+        future::Future,
+        net::Ipv4Addr,
+        pin::Pin,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    struct MockForward {
+        hits: AtomicUsize,
+    }
+
+    impl AsyncForwardOperation for MockForward {
+        fn forward_async<'a>(
+            &'a self,
+            _dns: &'a mut DNS,
+        ) -> Pin<Box<dyn Future<Output = Result<DNS, Error>> + Send + 'a>> {
+            Box::pin(async move {
+                self.hits.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                DNS::from(&fs::read("./test_dns_raw/google.com/1_1")?)
+            })
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_single_flight() {
+        let sf = Arc::new(SingleFlight::new(MockForward {
+            hits: AtomicUsize::new(0),
+        }));
+
+        let mut handles = vec![];
+        for i in 0..10 {
+            let sf = sf.clone();
+            handles.push(tokio::spawn(async move {
+                let mut dns = DNS::new();
+                dns.head().with_id(i);
+                dns.with_ques(
+                    if i % 2 == 0 {
+                        "google.com"
+                    } else {
+                        "Google.com"
+                    },
+                    TYPE_A,
+                    CLASS_IN,
+                );
+                sf.query(&mut dns).await
+            }));
+        }
+        for (i, handle) in handles.into_iter().enumerate() {
+            let mut resp = handle.await.unwrap().unwrap();
+            assert_eq!(i as u16, resp.head().id());
+            assert_eq!(vec![Ipv4Addr::new(93, 46, 8, 90)], resp.a_records());
+        }
+        assert_eq!(1, sf.forward.hits.load(Ordering::SeqCst));
+        assert_eq!(true, sf.inflight.lock().unwrap().is_empty());
+
+        // the flight landed, the next query hit the forward again
+        let mut dns = DNS::new();
+        dns.with_ques("google.com", TYPE_A, CLASS_IN);
+        sf.query(&mut dns).await.unwrap();
+        assert_eq!(2, sf.forward.hits.load(Ordering::SeqCst));
+    }
+}
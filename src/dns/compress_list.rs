@@ -2,6 +2,10 @@ use nom::FindSubstring;
 
 /**
 CompressList: Save the domain_name(String) and offset(usize) as a tuple into Vector
+
+The offsets are relative to the start of the current message, a pointer can only refer to the
+message it is in. So the list must be [`CompressList::clear`]ed (or created anew) before encoding
+the next message, such as each message of a zone transfer.
  */
 #[derive(Debug)]
 pub struct CompressList(Vec<(String, usize)>);
@@ -11,6 +15,11 @@ impl CompressList {
         Self { 0: vec![] }
     }
 
+    /// clear: forget all the names, for encoding the next message.
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
     fn sort(&mut self) {
         self.0.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
     }
@@ -67,6 +76,71 @@ impl CompressList {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dns::{Labels, RRs, RR};
+    use crate::DNS;
+    use std::{cell::RefCell, net::Ipv4Addr, rc::Rc};
+
+    fn message(names: &[&str]) -> DNS {
+        let mut dns = DNS::new();
+        for name in names {
+            let rr = RR::a(name, 300, Ipv4Addr::new(10, 0, 0, 1));
+            dns.add_answer_rr(Rc::new(RefCell::new(rr)));
+        }
+        dns
+    }
+
+    /// encode the dns by the compression list shared between the messages.
+    fn encode_with(dns: &mut DNS, cl: &mut CompressList) -> Vec<u8> {
+        let mut raw = dns.encode(true).unwrap()[..12].to_vec();
+        dns.ques().encode(&mut raw, cl).unwrap();
+        RRs(dns.answers().0.clone())
+            .encode(&mut raw, cl, true)
+            .unwrap();
+        raw
+    }
+
+    #[test]
+    fn test_compress_list_clear() {
+        let mut first = message(&["www.example.com", "www.example.com"]);
+        let mut second = message(&["a.example.org", "www.example.com", "www.example.com"]);
+
+        let mut cl = CompressList::new();
+        let raw = encode_with(&mut first, &mut cl);
+        assert_eq!(first.encode(true).unwrap(), raw);
+        // the pointers to the first message are wrong in the second one
+        let stale = encode_with(&mut second, &mut cl);
+        assert_ne!(second.encode(true).unwrap(), stale);
+
+        cl.clear();
+        assert_eq!(true, cl.get_0().is_empty());
+        let raw = encode_with(&mut second, &mut cl);
+        assert_eq!(second.encode(true).unwrap(), raw);
+        // the second owner points to the first one in the message
+        let first_owner = 12
+            + Labels::from_domain_name("a.example.org")
+                .to_wire()
+                .unwrap()
+                .len()
+            + 10
+            + 4;
+        let second_owner = first_owner + 17 + 10 + 4;
+        assert_eq!(
+            [0xC0 | (first_owner >> 8) as u8, first_owner as u8],
+            raw[second_owner..second_owner + 2]
+        );
+        let parsed = DNS::from(&raw).unwrap();
+        let names: Vec<String> = parsed
+            .answers()
+            .0
+            .iter()
+            .map(|rr| rr.borrow().name().to_string())
+            .collect();
+        assert_eq!(
+            vec!["a.example.org", "www.example.com", "www.example.com"],
+            names
+        );
+    }
+
     #[test]
     fn compress_list_push() {
         let mut cl = CompressList::new();
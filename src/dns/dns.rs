@@ -1,13 +1,21 @@
 use super::header::Header;
 use super::question::Questions;
-use super::rr::RRs;
-use super::{Class, Question, RcRf, Type, RR};
+use super::rdata::{domain_name_len, opt::OPT, RDataType};
+use super::rr::{is_same_rrset, RRs};
+use super::{
+    Class, Question, RcRf, Type, DEFAULT_UDP_PAYLOAD, MIN_UDP_PAYLOAD, RR, TYPE_OPT, TYPE_TSIG,
+};
 use crate::dns::compress_list::CompressList;
-use anyhow::Error;
+use anyhow::{anyhow, Error};
+use rand::Rng;
 use std::cell::RefCell;
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::rc::Rc;
 
+/// the error of the package shorter than the header or its records.
+const ERR_INCOMPLETE_PACKAGE: &str = "the dns package is incomplete";
+
 /**
 # DNS Structure:
 ```shell
@@ -25,11 +33,48 @@ use std::rc::Rc;
 ```
 */
 
+/// Section: the parts of the dns message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Header,
+    Question,
+    Answer,
+    Authority,
+    Additional,
+}
+
+/// ParseWarning: a part of the message failed to parse by [`DNS::from_lenient`].
+#[derive(Debug)]
+pub struct ParseWarning {
+    pub section: Section,
+    /// the index of the record in the section.
+    pub index: usize,
+    pub error: Error,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}[{}]: {}", self.section, self.index, self.error)
+    }
+}
+
+/// how the parse recovers from a record failed to parse.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Recovery {
+    /// return the error.
+    Abort,
+    /// stop at the record, keep the parsed ones.
+    Truncate,
+    /// skip the record by its RDLENGTH and continue, stop if it can not be skipped.
+    Skip,
+}
+
 #[derive(Debug)]
 pub struct DNS {
     _raw: Vec<u8>,
     _is_compressed: bool,
     _parsed_len: usize,
+    _truncated_parse: bool,
 
     head: Header,
     ques: Questions,
@@ -47,6 +92,7 @@ impl DNS {
             _raw: vec![],
             _is_compressed: false,
             _parsed_len: 0,
+            _truncated_parse: false,
 
             head: Header::new(),
             ques: Questions::new(),
@@ -68,12 +114,23 @@ impl DNS {
         self._parsed_len
     }
 
+    /// truncated_parse: whether the message was parsed by [`DNS::from_partial`] and stopped at a
+    /// record that failed to parse.
+    pub fn truncated_parse(&self) -> bool {
+        self._truncated_parse
+    }
+
+    /// from_fake: parse the header and the questions only.
     pub fn from_fake(raw: &[u8]) -> Result<Self, Error> {
+        if raw.len() < 12 {
+            return Err(Error::msg(ERR_INCOMPLETE_PACKAGE));
+        }
         let mut offset = 0;
         let mut dns = Self {
             _raw: raw.to_vec(),
             _is_compressed: false,
             _parsed_len: 0,
+            _truncated_parse: false,
             head: Header::from(raw, &mut offset)?,
             ques: Questions::new(),
             answers: RRs::new(),
@@ -81,16 +138,64 @@ impl DNS {
             additional: RRs::new(),
         };
         // parse question
-        for _i in 0..dns.head.qdcount() {
-            let ques = Question::from(&raw, &mut offset)?;
-            dns.ques.push(ques);
-        }
+        dns.ques = Questions::parse(raw, &mut offset, dns.head.qdcount())?;
 
         Ok(dns)
     }
 
     pub fn from(raw: &[u8]) -> Result<Self, Error> {
-        let dns_packet_err = Err(Error::msg("the dns package not incomplete"));
+        Ok(Self::parse(raw, false, Recovery::Abort)?.0)
+    }
+
+    /// from_strict: same as [`DNS::from`], but reject the packet when any rdata length of the rr
+    /// not match the length that its type decoded.
+    pub fn from_strict(raw: &[u8]) -> Result<Self, Error> {
+        Ok(Self::parse(raw, true, Recovery::Abort)?.0)
+    }
+
+    /**
+    from_partial: same as [`DNS::from`], but the ancount/nscount/arcount may overrun the records
+    that actually in the packet (e.g. truncated in the middle of a record).
+
+    The parse stops at the first record that failed to parse, the records parsed before it are kept,
+    the counts of the header are rewritten to the number of the parsed records, and the
+    [`DNS::truncated_parse`] flag is set.
+     */
+    pub fn from_partial(raw: &[u8]) -> Result<Self, Error> {
+        Ok(Self::parse(raw, false, Recovery::Truncate)?.0)
+    }
+
+    /**
+    from_lenient: parse as much as possible for inspecting the possibly corrupt captures, never fail.
+
+    The record failed to parse is skipped by its RDLENGTH and reported as a [`ParseWarning`], the parse
+    stops (and [`DNS::truncated_parse`] is set) only when the record can not be skipped. The counts of
+    the header are rewritten to the number of the parsed records.
+
+    When the parse stops before the additional section, the OPT RR at the end of the message is still
+    recovered, so the EDNS parameters and the extended DNS error explaining the failure are readable.
+     */
+    pub fn from_lenient(raw: &[u8]) -> (Self, Vec<ParseWarning>) {
+        match Self::parse(raw, false, Recovery::Skip) {
+            Ok(parsed) => parsed,
+            Err(error) => (
+                Self::new(),
+                vec![ParseWarning {
+                    section: Section::Header,
+                    index: 0,
+                    error,
+                }],
+            ),
+        }
+    }
+
+    fn parse(
+        raw: &[u8],
+        strict: bool,
+        recovery: Recovery,
+    ) -> Result<(Self, Vec<ParseWarning>), Error> {
+        let parse_rr = if strict { RR::from_strict } else { RR::from };
+        let dns_packet_err = Err(Error::msg(ERR_INCOMPLETE_PACKAGE));
         if raw.len() < 12 {
             return dns_packet_err;
         }
@@ -100,6 +205,7 @@ impl DNS {
             _raw: raw.to_vec(),
             _is_compressed: false,
             _parsed_len: 0,
+            _truncated_parse: false,
 
             head: Header::from(raw, &mut offset)?,
             ques: Questions::new(),
@@ -119,40 +225,140 @@ impl DNS {
         );
 
         // parse question
-        for _i in 0..dns.head.qdcount() {
-            let ques = Question::from(&raw, &mut offset)?;
-            dns.ques.push(ques);
-        }
+        let mut warnings = vec![];
+        dns.ques = match Questions::parse(raw, &mut offset, dns.head.qdcount()) {
+            Ok(ques) => ques,
+            Err(error) if recovery == Recovery::Skip => {
+                warnings.push(ParseWarning {
+                    section: Section::Question,
+                    index: 0,
+                    error,
+                });
+                dns._truncated_parse = true;
+                dns.head
+                    .with_qdcount(0)
+                    .with_ancount(0)
+                    .with_nscount(0)
+                    .with_arcount(0);
+                return Ok((dns, warnings));
+            }
+            Err(e) => return Err(e),
+        };
 
         if offset > raw.len() {
-            return Ok(dns);
+            return Ok((dns, warnings));
         }
-        // parse anwer
-        for _i in 0..dns.head.ancount() {
-            let rr = RR::from(&raw, &mut offset, &mut dns._is_compressed)?;
-            dns.answers.0.push(Rc::new(RefCell::new(rr)));
+        // parse anwer, authority and additional
+        let counts = [dns.head.ancount(), dns.head.nscount(), dns.head.arcount()];
+        let mut parsed = [0_u16; 3];
+        let sections = [Section::Answer, Section::Authority, Section::Additional];
+        'sections: for (i, count) in counts.into_iter().enumerate() {
+            for index in 0..count as usize {
+                let start = offset;
+                let rr = match parse_rr(&raw, &mut offset, &mut dns._is_compressed) {
+                    Ok(rr) => rr,
+                    Err(e) if recovery == Recovery::Abort => return Err(e),
+                    Err(error) => {
+                        offset = start;
+                        let skipped =
+                            recovery == Recovery::Skip && RR::skip(raw, &mut offset).is_ok();
+                        if recovery == Recovery::Skip {
+                            warnings.push(ParseWarning {
+                                section: sections[i],
+                                index,
+                                error,
+                            });
+                        }
+                        if skipped {
+                            continue;
+                        }
+                        dns._truncated_parse = true;
+                        break 'sections;
+                    }
+                };
+                let rrs = match i {
+                    0 => &mut dns.answers,
+                    1 => &mut dns.authority,
+                    _ => &mut dns.additional,
+                };
+                rrs.0.push(Rc::new(RefCell::new(rr)));
+                parsed[i] += 1;
+            }
+        }
+        if dns._truncated_parse && recovery == Recovery::Skip && dns.edns().is_none() {
+            if let Some(opt) = Self::recover_opt(raw, offset) {
+                dns.additional.0.push(Rc::new(RefCell::new(opt)));
+                parsed[2] += 1;
+            }
+        }
+        if parsed != counts {
+            dns.head
+                .with_ancount(parsed[0])
+                .with_nscount(parsed[1])
+                .with_arcount(parsed[2]);
         }
 
-        // parse authority
-        for _i in 0..dns.head.nscount() {
-            let rr = RR::from(&raw, &mut offset, &mut dns._is_compressed)?;
-            dns.authority.0.push(Rc::new(RefCell::new(rr)));
+        dns._parsed_len = offset;
+        return Ok((dns, warnings));
+    }
+
+    /// recover_opt: the OPT RR ending the message after the offset, it is the last record of the
+    /// additional section as usual.
+    fn recover_opt(raw: &[u8], offset: usize) -> Option<RR> {
+        // root name, TYPE, CLASS, TTL and RDLENGTH
+        let fixed = 1 + 10;
+        let start = raw.len().checked_sub(fixed)?;
+        for pos in (offset..=start).rev() {
+            if raw[pos] != 0 || raw[pos + 1..pos + 3] != TYPE_OPT.to_be_bytes() {
+                continue;
+            }
+            let rdlength = u16::from_be_bytes([raw[pos + 9], raw[pos + 10]]) as usize;
+            if pos + fixed + rdlength != raw.len() {
+                continue;
+            }
+            let mut pos = pos;
+            let mut is_compressed = false;
+            if let Ok(rr) = RR::from(raw, &mut pos, &mut is_compressed) {
+                return Some(rr);
+            }
         }
 
-        // parse additional
-        for _i in 0..dns.head.arcount() {
-            let rr = RR::from(&raw, &mut offset, &mut dns._is_compressed)?;
-            dns.additional.0.push(Rc::new(RefCell::new(rr)));
+        None
+    }
+
+    /// verify_roundtrip: re-encode the message with the detected compression flag, and compare it
+    /// with the parsed bytes of the raw, the offset of the first differing byte is reported on mismatch.
+    pub fn verify_roundtrip(&mut self) -> Result<(), Error> {
+        let encoded = self.encode(self._is_compressed)?;
+        let raw = &self._raw[..self._parsed_len.min(self._raw.len())];
+        if raw == encoded.as_slice() {
+            return Ok(());
         }
 
-        dns._parsed_len = offset;
-        return Ok(dns);
+        match raw.iter().zip(encoded.iter()).position(|(a, b)| a != b) {
+            Some(offset) => Err(anyhow!(
+                "the encoded message differs from the raw at offset {}: raw={:#04x}, encoded={:#04x}",
+                offset,
+                raw[offset],
+                encoded[offset]
+            )),
+            None => Err(anyhow!(
+                "the encoded message differs from the raw in length: raw={}, encoded={}",
+                raw.len(),
+                encoded.len()
+            )),
+        }
     }
 
     pub fn head(&mut self) -> &mut Header {
         return &mut self.head;
     }
 
+    /// header: the read-only header, see [`DNS::head`] to modify it.
+    pub fn header(&self) -> &Header {
+        return &self.head;
+    }
+
     pub fn ques(&self) -> &Questions {
         return &self.ques;
     }
@@ -161,31 +367,407 @@ impl DNS {
         return &mut self.ques;
     }
 
+    pub fn answers(&self) -> &RRs {
+        return &self.answers;
+    }
+
+    pub fn authority(&self) -> &RRs {
+        return &self.authority;
+    }
+
+    pub fn additional(&self) -> &RRs {
+        return &self.additional;
+    }
+
+    /// edns: the OPT pseudo RR in the additional section.
+    pub fn edns(&self) -> Option<RcRf<RR>> {
+        self.additional
+            .0
+            .iter()
+            .find(|rr| rr.borrow().typ() == TYPE_OPT)
+            .cloned()
+    }
+
+    /// dnssec_ok: whether the DO bit of the EDNS is set, the requestor asks for the DNSSEC records.
+    pub fn dnssec_ok(&self) -> bool {
+        self.edns().is_some_and(|rr| {
+            rr.borrow_mut()
+                .convert_pseudo()
+                .is_ok_and(|meta| meta.dnssec_ok())
+        })
+    }
+
+    /**
+    request_udp_payload: the UDP payload size the requestor can reassemble, which is the class of the
+    OPT RR. The 512 is returned when the request has no OPT RR, or advertises a smaller size.
+
+    ref: https://www.rfc-editor.org/rfc/rfc6891#section-6.2.3
+     */
+    pub fn request_udp_payload(&self) -> u16 {
+        let payload = self.edns().and_then(|rr| {
+            rr.borrow_mut()
+                .convert_pseudo()
+                .ok()
+                .map(|meta| meta.udp_payload())
+        });
+
+        payload.unwrap_or(MIN_UDP_PAYLOAD).max(MIN_UDP_PAYLOAD)
+    }
+
+    /// requests_dnssec: whether the requestor asks for the DNSSEC records by the DO bit, same as
+    /// [`DNS::dnssec_ok`] on a parsed query.
+    pub fn requests_dnssec(&self) -> bool {
+        self.dnssec_ok()
+    }
+
+    /**
+    set_dnssec_requested: set the DO bit of the EDNS, the OPT is added if absent.
+
+    A validating resolver must always set the DO bit on the upstream queries regardless of the bit
+    of the client, so the DNSSEC records are returned.
+
+    ref: https://www.rfc-editor.org/rfc/rfc4035#section-3.2.1
+     */
+    pub fn set_dnssec_requested(&mut self) -> &mut Self {
+        self.with_dnssec_ok(true)
+    }
+
+    /// with_dnssec_ok: set the DO bit of the EDNS, the OPT is added if absent when setting.
+    /// Clearing the bit keeps the OPT, see [`DNS::requests_dnssec`] to read it back. The DNS is
+    /// built by its `with_*` methods, so this is the builder entry of the DO query.
+    pub fn with_dnssec_ok(&mut self, dnssec_ok: bool) -> &mut Self {
+        if dnssec_ok {
+            self.with_edns();
+        }
+        if let Some(opt) = self.edns() {
+            if let Ok(mut prr) = opt.borrow_mut().convert_pseudo() {
+                prr.with_dnssec_ok(dnssec_ok);
+            }
+        }
+        return self;
+    }
+
+    /// with_edns: add the OPT RR advertising the [`DEFAULT_UDP_PAYLOAD`] into the additional section,
+    /// the existing one is kept.
+    pub fn with_edns(&mut self) -> &mut Self {
+        if self.edns().is_none() {
+            let mut rr = RR::new();
+            rr.with_type(TYPE_OPT)
+                .with_rdata(RDataType::OPT(OPT::new()))
+                .convert_pseudo()
+                .unwrap()
+                .with_udp_payload(DEFAULT_UDP_PAYLOAD);
+            self.add_additional_rr(Rc::new(RefCell::new(rr)));
+        }
+        return self;
+    }
+
+    /**
+    resolved_addresses: the final addresses of the question name for the stub clients, the CNAME chain
+    in the answer section is followed from the question name to the A and AAAA records of its end, the
+    unrelated records are ignored. The names are compared case-insensitively.
+
+    An empty vec is returned when the chain is broken or looped.
+     */
+    pub fn resolved_addresses(&self) -> Vec<IpAddr> {
+        let mut name = match self.ques.0.first() {
+            Some(ques) => ques.qname().encode_to_str(),
+            None => return vec![],
+        };
+        let is_owner = |rr: &RR, name: &str| {
+            rr.name()
+                .trim_end_matches('.')
+                .eq_ignore_ascii_case(name.trim_end_matches('.'))
+        };
+
+        // each step follows one CNAME, so a looped chain ends after all the records are followed
+        for _ in 0..=self.answers.0.len() {
+            let addrs: Vec<IpAddr> = self
+                .answers
+                .0
+                .iter()
+                .filter_map(|rr| {
+                    let rr = rr.borrow();
+                    match rr.rdata() {
+                        RDataType::A(a) if is_owner(&rr, &name) => Some(IpAddr::V4(a.0)),
+                        RDataType::AAAA(aaaa) if is_owner(&rr, &name) => Some(IpAddr::V6(aaaa.0)),
+                        _ => None,
+                    }
+                })
+                .collect();
+            if !addrs.is_empty() {
+                return addrs;
+            }
+
+            let target = self.answers.0.iter().find_map(|rr| {
+                let rr = rr.borrow();
+                match rr.rdata() {
+                    RDataType::CName(cname) if is_owner(&rr, &name) => Some(cname.0.clone()),
+                    _ => None,
+                }
+            });
+            match target {
+                Some(target) => name = target,
+                None => break,
+            }
+        }
+
+        vec![]
+    }
+
+    /// a_records: the IPv4 addresses of all the A records in the answer section.
+    pub fn a_records(&self) -> Vec<Ipv4Addr> {
+        self.answers
+            .0
+            .iter()
+            .filter_map(|rr| match rr.borrow().rdata() {
+                RDataType::A(a) => Some(a.0),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// aaaa_records: the IPv6 addresses of all the AAAA records in the answer section.
+    pub fn aaaa_records(&self) -> Vec<Ipv6Addr> {
+        self.answers
+            .0
+            .iter()
+            .filter_map(|rr| match rr.borrow().rdata() {
+                RDataType::AAAA(aaaa) => Some(aaaa.0),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// canonicalize: normalize the dns before signing, lowercase the question and all the owner names,
+    /// sort each section in canonical RRset order and unify the TTLs within each RRset.
+    pub fn canonicalize(&mut self) {
+        for ques in self.ques.0.iter_mut() {
+            for label in ques.qname_mut().0.iter_mut() {
+                *label = label.to_ascii_lowercase();
+            }
+        }
+        self.answers.canonicalize();
+        self.authority.canonicalize();
+        self.additional.canonicalize();
+    }
+
+    /**
+    clone_for_retry: a fresh copy of the dns to send again, the records are copied rather than shared
+    so the copy can be altered and encoded on its own.
+
+    The same ID is kept unless new_id, so a late response to the former attempt still matches the
+    retry. A new random ID drops the responses to the former one.
+     */
+    pub fn clone_for_retry(&self, new_id: bool) -> DNS {
+        let clone_rrs = |rrs: &RRs| {
+            let mut cloned = RRs::new();
+            for rr in &rrs.0 {
+                cloned.extend(Rc::new(RefCell::new(rr.borrow().clone())));
+            }
+            cloned
+        };
+        let mut dns = Self {
+            _raw: vec![],
+            _is_compressed: self._is_compressed,
+            _parsed_len: 0,
+            _truncated_parse: false,
+
+            head: self.head.clone(),
+            ques: self.ques.clone(),
+            answers: clone_rrs(&self.answers),
+            authority: clone_rrs(&self.authority),
+            additional: clone_rrs(&self.additional),
+        };
+        if new_id {
+            // never the same as the former one
+            let offset = rand::thread_rng().gen_range(1..=u16::MAX);
+            dns.head.with_id(self.head.id().wrapping_add(offset));
+        }
+
+        dns
+    }
+
+    /// randomize_case: randomly upper/lowercase the letters of the question names before sending,
+    /// the response must echo the same case.
+    ///
+    /// ref: https://datatracker.ietf.org/doc/html/draft-vixie-dnsext-dns0x20-00
+    pub fn randomize_case(&mut self) {
+        let mut rng = rand::thread_rng();
+        for ques in self.ques.0.iter_mut() {
+            for label in ques.qname_mut().0.iter_mut() {
+                *label = label
+                    .chars()
+                    .map(|c| {
+                        if rng.gen::<bool>() {
+                            c.to_ascii_uppercase()
+                        } else {
+                            c.to_ascii_lowercase()
+                        }
+                    })
+                    .collect();
+            }
+        }
+    }
+
+    /// case_matches: compare the question names with the response case-sensitively.
+    pub fn case_matches(&self, other: &DNS) -> bool {
+        self.ques.len() == other.ques.len()
+            && self
+                .ques
+                .0
+                .iter()
+                .zip(other.ques.0.iter())
+                .all(|(a, b)| a.qname().0 == b.qname().0)
+    }
+
+    /// cap_ttls: cap the TTLs of the answer, authority and additional RRs to the max, so the cache
+    /// will not keep them too long.
+    pub fn cap_ttls(&mut self, max: u32) {
+        self.answers.cap_ttls(max);
+        self.authority.cap_ttls(max);
+        self.additional.cap_ttls(max);
+    }
+
+    /// min_ttl: the minimum TTL of the answer, authority and additional RRs, which is the lifetime of
+    /// the whole response in the cache. The OPT pseudo RR is excluded, its TTL carries the extended
+    /// RCODE and flags. The TTLs are effective, see [`RR::effective_ttl`]. Return None if there is no RR.
+    ///
+    /// The 0 means the response must not be cached, as a RR of TTL 0 is only used for the current
+    /// transaction. (RFC 1035 section 3.2.1)
+    pub fn min_ttl(&self) -> Option<u32> {
+        self.answers
+            .0
+            .iter()
+            .chain(&self.authority.0)
+            .chain(&self.additional.0)
+            .map(|rr| rr.borrow())
+            .filter(|rr| rr.typ() != TYPE_OPT)
+            .map(|rr| rr.effective_ttl())
+            .min()
+    }
+
+    /**
+    into_response: rewrite the query into the skeleton of its response.
+
+    The ID, OPCODE, RD and the question section are copied through unchanged, QR is set, AA, TC and
+    RCODE are cleared, and the answer, authority and additional sections are emptied.
+    RA is cleared too, the server that offers recursion should set it again by `head().with_ra(true)`.
+     */
+    pub fn into_response(&mut self) -> &mut Self {
+        self._raw.clear();
+        self._is_compressed = false;
+        self._parsed_len = 0;
+        self._truncated_parse = false;
+
+        self.head
+            .with_qr(true)
+            .with_aa(false)
+            .with_tc(false)
+            .with_ra(false)
+            .with_z(false)
+            .with_rcode(0);
+        self.answers = RRs::new();
+        self.authority = RRs::new();
+        self.additional = RRs::new();
+        self.head
+            .with_qdcount(self.ques.len() as u16)
+            .with_ancount(0)
+            .with_nscount(0)
+            .with_arcount(0);
+
+        return self;
+    }
+
+    /// with_ques: add the question of the domain, the root is `"."` or `""`, such as the `. NS`
+    /// priming query. The trailing dot of the domain is optional.
     pub fn with_ques(&mut self, domain: &str, qtype: Type, qclass: Class) {
         let mut ques = Question::new();
-        let mut names = domain.split(".");
-        let mut iter = names.next();
-        while iter.is_some() {
-            ques.with_name(iter.unwrap());
-            iter = names.next();
+        for name in domain.split(".").filter(|name| !name.is_empty()) {
+            ques.with_name(name);
         }
         ques.with_qclass(qclass).with_qtype(qtype);
 
         self.ques.push(ques);
     }
 
+    #[deprecated(note = "use add_answer_rr instead")]
     pub fn with_answer(&mut self, rr: RcRf<RR>) {
-        self.answers.extend(rr);
+        self.add_answer_rr(rr);
     }
 
+    #[deprecated(note = "use add_authority_rr instead")]
     pub fn with_authority(&mut self, ns: RcRf<RR>) {
-        self.authority.extend(ns);
+        self.add_authority_rr(ns);
     }
 
+    #[deprecated(note = "use add_additional_rr instead")]
     pub fn with_additional(&mut self, ar: RcRf<RR>) {
-        self.additional.extend(ar);
+        self.add_additional_rr(ar);
+    }
+
+    /// add_answer_rr: append the RR into the answer section, and bump the ANCOUNT of the header.
+    pub fn add_answer_rr(&mut self, rr: RcRf<RR>) -> &mut Self {
+        self.answers.extend(rr);
+        self.head.with_ancount(self.answers.len() as u16);
+        return self;
+    }
+
+    /// add_authority_rr: append the RR into the authority section, and bump the NSCOUNT of the header.
+    ///
+    /// the authority section only carries NS and SOA (and their DNSSEC companions) in practice.
+    pub fn add_authority_rr(&mut self, rr: RcRf<RR>) -> &mut Self {
+        self.authority.extend(rr);
+        self.head.with_nscount(self.authority.len() as u16);
+        return self;
+    }
+
+    /// add_additional_rr: append the RR into the additional section, and bump the ARCOUNT of the header.
+    ///
+    /// the pseudo RRs (OPT, TSIG) and the glue records belong here, not the answers.
+    pub fn add_additional_rr(&mut self, rr: RcRf<RR>) -> &mut Self {
+        self.additional.extend(rr);
+        self.head.with_arcount(self.additional.len() as u16);
+        return self;
+    }
+
+    /**
+    merge_answers: append the answer, authority and additional RRs of the other into the same sections,
+    such as the A and AAAA answers from the different upstreams. The header and the questions of self
+    are kept, the counts are recomputed.
+
+    The RR identical to one of the section (the same RRset and rdata, the TTL is ignored) is skipped.
+    The TSIG of the other signs the other message, and a message has one OPT at most, so they are not
+    taken either.
+     */
+    pub fn merge_answers(&mut self, other: &DNS) -> &mut Self {
+        let merge = |rrs: &mut RRs, others: &RRs| {
+            for rr in &others.0 {
+                let rr = rr.borrow();
+                if rr.typ() == TYPE_TSIG || rr.typ() == TYPE_OPT {
+                    continue;
+                }
+                let exists = rrs.0.iter().any(|exist| {
+                    let exist = exist.borrow();
+                    is_same_rrset(&exist, &rr) && exist.rdata() == rr.rdata()
+                });
+                if !exists {
+                    rrs.extend(Rc::new(RefCell::new(rr.clone())));
+                }
+            }
+        };
+        merge(&mut self.answers, &other.answers);
+        merge(&mut self.authority, &other.authority);
+        merge(&mut self.additional, &other.additional);
+
+        self.head.with_ancount(self.answers.len() as u16);
+        self.head.with_nscount(self.authority.len() as u16);
+        self.head.with_arcount(self.additional.len() as u16);
+        return self;
     }
 
+    /// encode: encode the dns into the wire format, the domain names in the owners and the rdata are
+    /// compressed by the pointers if is_compressed, otherwise there is no pointer in the output.
     pub fn encode(&mut self, is_compressed: bool) -> Result<Vec<u8>, Error> {
         let mut result = Vec::<u8>::new();
 
@@ -196,10 +778,10 @@ impl DNS {
         self.head.with_arcount(self.additional.len() as u16);
 
         // encode head
-        result.extend_from_slice(&self.head.get_0());
+        result.extend_from_slice(self.head.as_bytes());
         let mut cl = CompressList::new();
         // encode questions
-        self.ques.encode(&mut result, &mut cl);
+        self.ques.encode(&mut result, &mut cl)?;
         // encode answers
         self.answers.encode(&mut result, &mut cl, is_compressed)?;
         // encode authority
@@ -210,13 +792,120 @@ impl DNS {
 
         return Ok(result);
     }
+
+    /// encode_uncompressed: encode without any compression pointer, for the peers mishandle the compression.
+    pub fn encode_uncompressed(&mut self) -> Result<Vec<u8>, Error> {
+        self.encode(false)
+    }
+
+    /**
+    estimated_len: estimate the length of the encoded dns without encoding it, to choose the UDP or TCP
+    or decide the truncation cheaply.
+
+    It is exact for the uncompressed one. Under the compression, the owner name with a suffix that
+    has been written before is counted as its leading labels and a pointer, the names in the rdata
+    are counted uncompressed.
+     */
+    pub fn estimated_len(&self, compress: bool) -> usize {
+        // the names can be pointed to
+        let mut written: Vec<String> = vec![];
+        let mut name_len = |name: &str| -> usize {
+            if !compress {
+                return domain_name_len(name);
+            }
+            let labels: Vec<&str> = name.split(".").filter(|l| l.len() != 0).collect();
+            let mut len = 0;
+            for i in 0..labels.len() {
+                let suffix = labels[i..].join(".");
+                if written.contains(&suffix) {
+                    // the pointer
+                    return len + 2;
+                }
+                written.push(suffix);
+                len += 1 + labels[i].len();
+            }
+            // the root
+            len + 1
+        };
+
+        let mut len = 12;
+        for ques in &self.ques.0 {
+            // the question is never compressed, but can be pointed to
+            name_len(&ques.qname().encode_to_str());
+            len += domain_name_len(&ques.qname().encode_to_str()) + 4;
+        }
+        for rr in self
+            .answers
+            .0
+            .iter()
+            .chain(&self.authority.0)
+            .chain(&self.additional.0)
+        {
+            let rr = rr.borrow();
+            // TYPE, CLASS, TTL and RDLENGTH
+            len += name_len(rr.name()) + 10 + rr.rdata().estimated_len();
+        }
+
+        len
+    }
+}
+
+/// the sections of the dns in the dig style, the question by [`Question::to_presentation`] and the
+/// records by the Display of the [`RR`] one per line. The empty record sections are omitted.
+impl fmt::Display for DNS {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, ";; QUESTION SECTION:")?;
+        for ques in &self.ques.0 {
+            writeln!(f, "{}", ques.to_presentation())?;
+        }
+        for (title, rrs) in [
+            ("ANSWER", &self.answers),
+            ("AUTHORITY", &self.authority),
+            ("ADDITIONAL", &self.additional),
+        ] {
+            if rrs.0.is_empty() {
+                continue;
+            }
+            writeln!(f, "\n;; {} SECTION:", title)?;
+            for rr in &rrs.0 {
+                writeln!(f, "{}", rr.borrow())?;
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::Section;
+    use crate::dns::{
+        rdata::{a::A, aaaa::AAAA, opt::OPT, RDataType},
+        CLASS_IN, DEFAULT_UDP_PAYLOAD, RR, TYPE_A, TYPE_AAAA, TYPE_CNAME, TYPE_MX, TYPE_NS,
+        TYPE_OPT,
+    };
     use crate::DNS;
     use core::panic;
     use std::fs;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    use std::{cell::RefCell, rc::Rc};
+
+    fn rc(rr: RR) -> Rc<RefCell<RR>> {
+        Rc::new(RefCell::new(rr))
+    }
+
+    /// add the records with the names in the rdata: an A and a CNAME answer, a NS authority and
+    /// a MX additional record.
+    fn add_sections(dns: &mut DNS) {
+        dns.add_answer_rr(rc(RR::a(
+            "www.example.com",
+            300,
+            Ipv4Addr::new(10, 0, 0, 1),
+        )))
+        .add_answer_rr(rc(RR::cname("ftp.example.com", 300, "www.example.com")))
+        .add_authority_rr(rc(RR::ns("example.com", 300, "ns1.example.com")))
+        .add_additional_rr(rc(RR::mx("example.com", 300, 10, "mail.example.com")));
+    }
 
     fn test_dns_from_a_file(filepath: &str) -> Option<DNS> {
         let raw_dns = fs::read(filepath).unwrap();
@@ -224,10 +913,7 @@ mod tests {
         println!("filepath={:?}", raw_dns);
         match DNS::from(&raw_dns) {
             Ok(mut parsed_dns) => {
-                assert_eq!(
-                    raw_dns.to_vec()[..parsed_dns.parsed_len()],
-                    parsed_dns.encode(parsed_dns.is_compressed()).unwrap()
-                );
+                parsed_dns.verify_roundtrip().unwrap();
                 Some(parsed_dns)
             }
             Err(e) => {
@@ -282,4 +968,715 @@ mod tests {
             test_dns_from_a_file(filename);
         });
     }
+
+    #[test]
+    fn test_dns_resolved_addresses() {
+        let add = |dns: &mut DNS, rr: RR| {
+            dns.add_answer_rr(Rc::new(RefCell::new(rr)));
+        };
+        let mut dns = DNS::new();
+        dns.with_ques("www.example.com", TYPE_A, CLASS_IN);
+        dns.into_response();
+        add(
+            &mut dns,
+            RR::cname("www.example.com", 300, "Web.example.net"),
+        );
+        add(
+            &mut dns,
+            RR::a("other.example.org", 300, Ipv4Addr::new(10, 0, 0, 9)),
+        );
+        add(
+            &mut dns,
+            RR::a("web.example.net", 300, Ipv4Addr::new(10, 0, 0, 1)),
+        );
+        assert_eq!(
+            vec![IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))],
+            dns.resolved_addresses()
+        );
+
+        // the CNAME to a name without any address
+        let mut dns = DNS::new();
+        dns.with_ques("www.example.com", TYPE_A, CLASS_IN);
+        add(
+            &mut dns,
+            RR::cname("www.example.com", 300, "web.example.net"),
+        );
+        add(
+            &mut dns,
+            RR::a("other.example.org", 300, Ipv4Addr::new(10, 0, 0, 9)),
+        );
+        assert_eq!(true, dns.resolved_addresses().is_empty());
+
+        // the looped chain
+        add(
+            &mut dns,
+            RR::cname("web.example.net", 300, "www.example.com"),
+        );
+        assert_eq!(true, dns.resolved_addresses().is_empty());
+    }
+
+    #[test]
+    fn test_dns_a_records() {
+        let raw_dns = fs::read("./test_dns_raw/google.com/1_1").unwrap();
+        let dns = DNS::from(&raw_dns).unwrap();
+        assert_eq!(vec![Ipv4Addr::new(93, 46, 8, 90)], dns.a_records());
+        assert_eq!(true, dns.aaaa_records().is_empty());
+    }
+
+    #[test]
+    fn test_dns_aaaa_records() {
+        let mut dns = DNS::new();
+        dns.with_ques("www.example.com", TYPE_AAAA, CLASS_IN);
+        dns.into_response();
+        let ip = "2001:db8::1".parse::<Ipv6Addr>().unwrap();
+        for rr in [
+            RR::aaaa("www.example.com", 300, ip),
+            RR::a("www.example.com", 300, Ipv4Addr::new(10, 0, 0, 1)),
+            RR::aaaa("www.example.com", 300, Ipv6Addr::LOCALHOST),
+        ] {
+            dns.add_answer_rr(Rc::new(RefCell::new(rr)));
+        }
+        let parsed = DNS::from(&dns.encode(false).unwrap()).unwrap();
+        assert_eq!(vec![ip, Ipv6Addr::LOCALHOST], parsed.aaaa_records());
+        assert_eq!(vec![Ipv4Addr::new(10, 0, 0, 1)], parsed.a_records());
+    }
+
+    #[test]
+    fn test_dns_display() {
+        let mut dns = DNS::new();
+        dns.with_ques("example.com", TYPE_A, CLASS_IN);
+        assert_eq!(
+            ";; QUESTION SECTION:\n;example.com.\t\t\tIN\tA\n",
+            dns.to_string()
+        );
+
+        dns.into_response()
+            .add_answer_rr(Rc::new(RefCell::new(RR::a(
+                "example.com",
+                300,
+                Ipv4Addr::new(10, 0, 0, 1),
+            ))));
+        assert_eq!(
+            ";; QUESTION SECTION:\n;example.com.\t\t\tIN\tA\n\n;; ANSWER SECTION:\nexample.com.\t\t300\tIN\tA\t10.0.0.1\n",
+            dns.to_string()
+        );
+    }
+
+    #[test]
+    fn test_dns_clone_for_retry() {
+        let mut dns = DNS::new();
+        dns.with_ques("www.example.com", TYPE_A, CLASS_IN);
+        dns.with_edns().head().with_id(0x1234);
+        let raw = dns.encode(false).unwrap();
+
+        let mut same = dns.clone_for_retry(false);
+        assert_eq!(0x1234, same.header().id());
+        assert_eq!(raw, same.encode(false).unwrap());
+
+        let mut renewed = dns.clone_for_retry(true);
+        assert_ne!(0x1234, renewed.header().id());
+        assert_eq!(raw[2..], renewed.encode(false).unwrap()[2..]);
+
+        // the records of the copy are not shared
+        same.edns()
+            .unwrap()
+            .borrow_mut()
+            .convert_pseudo()
+            .unwrap()
+            .with_udp_payload(512);
+        assert_eq!(DEFAULT_UDP_PAYLOAD, dns.request_udp_payload());
+    }
+
+    #[test]
+    fn test_dns_randomize_case() {
+        let mut dns = DNS::new();
+        dns.with_ques(
+            "a-very-long-label-for-case-randomization.example.com",
+            TYPE_A,
+            CLASS_IN,
+        );
+        let origin = dns.ques().0[0].qname().encode_to_str();
+        dns.randomize_case();
+        let randomized = dns.ques().0[0].qname().encode_to_str();
+        assert_eq!(true, origin.eq_ignore_ascii_case(&randomized));
+
+        let mut resp = DNS::from(&dns.encode(false).unwrap()).unwrap();
+        assert_eq!(true, dns.case_matches(&resp));
+
+        resp.ques_mut().0[0].qname_mut().0[0] = randomized
+            .split('.')
+            .next()
+            .unwrap()
+            .chars()
+            .map(|c| match c.is_ascii_uppercase() {
+                true => c.to_ascii_lowercase(),
+                false => c.to_ascii_uppercase(),
+            })
+            .collect();
+        assert_eq!(false, dns.case_matches(&resp));
+    }
+
+    #[test]
+    fn test_dns_cap_ttls() {
+        let mut dns = DNS::new();
+        dns.add_answer_rr(rc(RR::a("example.com", 86400, Ipv4Addr::new(10, 0, 0, 1))));
+        dns.add_authority_rr(rc(RR::a("example.com", 60, Ipv4Addr::new(10, 0, 0, 1))));
+        dns.add_additional_rr(rc(RR::a("example.com", 0, Ipv4Addr::new(10, 0, 0, 1))));
+        dns.cap_ttls(3600);
+
+        assert_eq!(3600, dns.answers().0[0].borrow().ttl());
+        assert_eq!(60, dns.authority().0[0].borrow().ttl());
+        assert_eq!(0, dns.additional().0[0].borrow().ttl());
+    }
+
+    #[test]
+    fn test_dns_add_rr_counts() {
+        let new_a = || rc(RR::a("example.com", 300, Ipv4Addr::new(10, 0, 0, 1)));
+
+        let mut dns = DNS::new();
+        dns.add_answer_rr(new_a()).add_answer_rr(new_a());
+        assert_eq!(2, dns.head().ancount());
+        assert_eq!(0, dns.head().nscount());
+
+        dns.add_authority_rr(new_a());
+        assert_eq!(1, dns.head().nscount());
+
+        dns.add_additional_rr(new_a());
+        dns.add_additional_rr(new_a());
+        dns.add_additional_rr(new_a());
+        assert_eq!(3, dns.head().arcount());
+        assert_eq!(2, dns.head().ancount());
+
+        let raw = dns.encode(false).unwrap();
+        let parsed = DNS::from(&raw).unwrap();
+        assert_eq!(2, parsed.answers().len());
+        assert_eq!(1, parsed.authority().len());
+        assert_eq!(3, parsed.additional().len());
+    }
+
+    #[test]
+    fn test_dns_from_partial() {
+        let mut dns = DNS::new();
+        dns.with_ques("example.com", TYPE_A, CLASS_IN);
+        for i in 1..=3 {
+            dns.add_answer_rr(rc(RR::a("example.com", 300, Ipv4Addr::new(10, 0, 0, i))));
+        }
+        let raw = dns.encode(false).unwrap();
+        // truncated inside the rdata of the third answer
+        let raw = &raw[..raw.len() - 2];
+
+        assert_eq!(true, DNS::from(raw).is_err());
+
+        let mut parsed = DNS::from_partial(raw).unwrap();
+        assert_eq!(true, parsed.truncated_parse());
+        assert_eq!(2, parsed.answers().len());
+        assert_eq!(2, parsed.head().ancount());
+        assert_eq!(
+            vec![Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2)],
+            parsed.a_records()
+        );
+
+        let parsed = DNS::from_partial(&dns.encode(false).unwrap()).unwrap();
+        assert_eq!(false, parsed.truncated_parse());
+        assert_eq!(3, parsed.answers().len());
+    }
+
+    #[test]
+    fn test_dns_into_response() {
+        let mut dns = DNS::new();
+        dns.head().with_id(0x1234).with_rd(true);
+        dns.with_ques("www.example.com", TYPE_A, CLASS_IN);
+        let mut rr = RR::new();
+        rr.with_name("www.example.com")
+            .with_type(TYPE_A)
+            .with_class(CLASS_IN)
+            .with_rdata(RDataType::A(A::new(Ipv4Addr::new(10, 0, 0, 1))));
+        dns.add_additional_rr(Rc::new(RefCell::new(rr)));
+
+        let mut query = DNS::from(&dns.encode(false).unwrap()).unwrap();
+        query.into_response();
+
+        assert_eq!(true, query.head().qr());
+        assert_eq!(0x1234, query.head().id());
+        assert_eq!(true, query.head().rd());
+        assert_eq!(false, query.head().ra());
+        assert_eq!(0, query.additional().len());
+        assert_eq!(0, query.head().arcount());
+        assert_eq!(1, query.ques().len());
+        assert_eq!("www.example.com", query.ques().0[0].qname().encode_to_str());
+
+        let resp = DNS::from(&query.encode(false).unwrap()).unwrap();
+        assert_eq!(dns.ques().0[0].qtype(), resp.ques().0[0].qtype());
+        assert_eq!(dns.ques().0[0].qclass(), resp.ques().0[0].qclass());
+    }
+
+    #[test]
+    fn test_dns_verify_roundtrip() {
+        let raw = fs::read("./test_dns_raw/google.com/1_1").unwrap();
+        let mut dns = DNS::from(&raw[..54]).unwrap();
+        assert_eq!(true, dns.verify_roundtrip().is_ok());
+
+        // the ttl of the answer: 60 -> 61
+        dns.answers().0[0].borrow_mut().with_ttl(61);
+        let err = dns.verify_roundtrip().unwrap_err().to_string();
+        assert_eq!(true, err.contains("at offset 47"));
+    }
+
+    #[test]
+    fn test_dns_canonicalize() {
+        let mut dns = DNS::new();
+        dns.with_ques("WWW.Example.com", TYPE_A, CLASS_IN);
+        dns.add_answer_rr(rc(RR::a(
+            "www.EXAMPLE.com",
+            300,
+            Ipv4Addr::new(10, 0, 0, 2),
+        )));
+        dns.add_answer_rr(rc(RR::a("Example.com", 60, Ipv4Addr::new(10, 0, 0, 9))));
+        dns.add_answer_rr(rc(RR::a(
+            "WWW.example.COM",
+            100,
+            Ipv4Addr::new(10, 0, 0, 1),
+        )));
+        dns.canonicalize();
+
+        assert_eq!("www.example.com", dns.ques().0[0].qname().encode_to_str());
+        let got: Vec<(String, u32, Ipv4Addr)> = dns
+            .answers()
+            .0
+            .iter()
+            .map(|rr| {
+                let rr = rr.borrow();
+                let ip = match rr.rdata() {
+                    RDataType::A(a) => a.0,
+                    _ => unreachable!(),
+                };
+                (rr.name().to_string(), rr.ttl(), ip)
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("example.com".to_string(), 60, Ipv4Addr::new(10, 0, 0, 9)),
+                (
+                    "www.example.com".to_string(),
+                    100,
+                    Ipv4Addr::new(10, 0, 0, 1)
+                ),
+                (
+                    "www.example.com".to_string(),
+                    100,
+                    Ipv4Addr::new(10, 0, 0, 2)
+                ),
+            ],
+            got
+        );
+    }
+
+    #[test]
+    fn test_dns_from_strict() {
+        let mut raw = vec![0_u8, 1, 0x81, 0x80, 0, 0, 0, 1, 0, 0, 0, 0];
+        // root name, type A, class IN, ttl 60, rdlength 5
+        raw.extend([0_u8, 0, 1, 0, 1, 0, 0, 0, 60, 0, 5]);
+        raw.extend([10_u8, 0, 0, 1, 0]);
+
+        assert_eq!(true, DNS::from(&raw).is_ok());
+        assert_eq!(true, DNS::from_strict(&raw).is_err());
+
+        // rdlength 4 is exactly an A record
+        let len = raw.len();
+        raw[len - 6] = 4;
+        raw.pop();
+        assert_eq!(true, DNS::from_strict(&raw).is_ok());
+    }
+
+    #[test]
+    fn test_dns_encode_answer_points_to_question() {
+        let mut dns = DNS::new();
+        dns.with_ques("www.example.com", TYPE_A, CLASS_IN);
+        dns.into_response()
+            .add_answer_rr(Rc::new(RefCell::new(RR::a(
+                "www.example.com",
+                300,
+                Ipv4Addr::new(10, 0, 0, 1),
+            ))));
+        let raw = dns.encode(true).unwrap();
+        // header, then the question www.example.com A IN
+        let answer = 12 + 17 + 4;
+        assert_eq!(&[0xc0, 12], &raw[answer..answer + 2]);
+        assert_eq!(answer + 2 + 10 + 4, raw.len());
+        assert_eq!(
+            vec![Ipv4Addr::new(10, 0, 0, 1)],
+            DNS::from(&raw).unwrap().a_records()
+        );
+    }
+
+    #[test]
+    fn test_dns_encode_uncompressed() {
+        let mut dns = DNS::new();
+        dns.with_ques("www.example.com", TYPE_A, CLASS_IN);
+        add_sections(&mut dns);
+
+        // walk the names of the questions and the records, count the pointers in the name positions
+        let count_pointers = |raw: &[u8]| {
+            let mut pointers = 0;
+            let mut offset = 12;
+            let mut walk_name = |offset: &mut usize| loop {
+                let len = raw[*offset];
+                if len & 0xC0 == 0xC0 {
+                    pointers += 1;
+                    *offset += 2;
+                    return;
+                }
+                *offset += 1 + len as usize;
+                if len == 0 {
+                    return;
+                }
+            };
+            walk_name(&mut offset);
+            offset += 4;
+            while offset < raw.len() {
+                walk_name(&mut offset);
+                let typ = u16::from_be_bytes([raw[offset], raw[offset + 1]]);
+                let rdlength = u16::from_be_bytes([raw[offset + 8], raw[offset + 9]]) as usize;
+                offset += 10;
+                let end = offset + rdlength;
+                match typ {
+                    TYPE_CNAME | TYPE_NS => walk_name(&mut offset),
+                    TYPE_MX => walk_name(&mut (offset + 2)),
+                    _ => {}
+                }
+                offset = end;
+            }
+            pointers
+        };
+
+        let compressed = dns.encode(true).unwrap();
+        assert_eq!(true, count_pointers(&compressed) > 0);
+
+        let raw = dns.encode_uncompressed().unwrap();
+        assert_eq!(0, count_pointers(&raw));
+        assert_eq!(true, raw.len() > compressed.len());
+
+        let mut parsed = DNS::from(&raw).unwrap();
+        assert_eq!(raw, parsed.encode_uncompressed().unwrap());
+    }
+
+    #[test]
+    fn test_dns_estimated_len() {
+        let mut dns = DNS::new();
+        assert_eq!(12, dns.estimated_len(false));
+
+        dns.with_ques("www.example.com", TYPE_A, CLASS_IN);
+        let len = dns.encode(false).unwrap().len();
+        assert_eq!(len, dns.estimated_len(false));
+        assert_eq!(len, dns.estimated_len(true));
+
+        add_sections(&mut dns);
+
+        let uncompressed = dns.encode(false).unwrap().len();
+        assert_eq!(uncompressed, dns.estimated_len(false));
+
+        // the rdata names are not counted compressed, so it is between the real ones
+        let compressed = dns.encode(true).unwrap().len();
+        let estimated = dns.estimated_len(true);
+        assert_eq!(true, compressed <= estimated && estimated < uncompressed);
+
+        // the parsed message
+        let raw = fs::read("./test_dns_raw/google.com/1_1").unwrap();
+        let mut dns = DNS::from(&raw).unwrap();
+        let uncompressed = dns.encode(false).unwrap().len();
+        assert_eq!(uncompressed, dns.estimated_len(false));
+    }
+
+    #[test]
+    fn test_dns_from_lenient() {
+        let mut dns = DNS::new();
+        dns.with_ques("www.example.com", TYPE_A, CLASS_IN);
+        for i in 1..=3 {
+            dns.add_answer_rr(Rc::new(RefCell::new(RR::a(
+                "www.example.com",
+                300,
+                Ipv4Addr::new(10, 0, 0, i),
+            ))));
+        }
+        let mut raw = dns.encode_uncompressed().unwrap();
+
+        // the second answer claims to be a TXT, its character-string overruns the rdata
+        // header, question(name + 4), answer(name + 10 + 4)
+        let second = 12 + (17 + 4) + (17 + 10 + 4);
+        let type_offset = second + 17;
+        assert_eq!(&[0, 1], &raw[type_offset..type_offset + 2]);
+        raw[type_offset + 1] = 16;
+        assert_eq!(true, DNS::from(&raw).is_err());
+
+        let (mut parsed, warnings) = DNS::from_lenient(&raw);
+        assert_eq!(1, warnings.len());
+        assert_eq!(Section::Answer, warnings[0].section);
+        assert_eq!(1, warnings[0].index);
+        assert_eq!(false, parsed.truncated_parse());
+        assert_eq!(2, parsed.head().ancount());
+        assert_eq!(
+            vec![Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 3)],
+            parsed.a_records()
+        );
+
+        // can not skip the record that overruns the raw
+        raw.truncate(raw.len() - 2);
+        let (parsed, warnings) = DNS::from_lenient(&raw);
+        assert_eq!(2, warnings.len());
+        assert_eq!(true, parsed.truncated_parse());
+        assert_eq!(vec![Ipv4Addr::new(10, 0, 0, 1)], parsed.a_records());
+
+        let (_, warnings) = DNS::from_lenient(&raw[..4]);
+        assert_eq!(Section::Header, warnings[0].section);
+    }
+
+    #[test]
+    fn test_dns_from_lenient_recover_opt() {
+        let mut dns = DNS::new();
+        dns.with_ques("www.example.com", TYPE_A, CLASS_IN);
+        dns.add_answer_rr(Rc::new(RefCell::new(RR::a(
+            "www.example.com",
+            300,
+            Ipv4Addr::new(10, 0, 0, 1),
+        ))));
+        dns.with_edns();
+        dns.edns()
+            .unwrap()
+            .borrow_mut()
+            .convert_pseudo()
+            .unwrap()
+            .with_extended_error(6, "DNSSEC Bogus");
+        let mut raw = dns.encode_uncompressed().unwrap();
+
+        // the RDLENGTH of the answer overruns the raw, so it can not be skipped
+        // header, question(name + 4), answer(name + TYPE, CLASS, TTL)
+        let rdlength = 12 + (17 + 4) + (17 + 8);
+        assert_eq!(&[0, 4], &raw[rdlength..rdlength + 2]);
+        raw[rdlength] = 0xff;
+        assert_eq!(true, DNS::from(&raw).is_err());
+
+        let (mut parsed, warnings) = DNS::from_lenient(&raw);
+        assert_eq!(1, warnings.len());
+        assert_eq!(Section::Answer, warnings[0].section);
+        assert_eq!(true, parsed.truncated_parse());
+        assert_eq!(0, parsed.head().ancount());
+        assert_eq!(1, parsed.head().arcount());
+        let opt = parsed.edns().unwrap();
+        let mut opt = opt.borrow_mut();
+        let meta = opt.convert_pseudo().unwrap();
+        assert_eq!(DEFAULT_UDP_PAYLOAD, meta.udp_payload());
+        assert_eq!(Some((6, "DNSSEC Bogus".to_string())), meta.extended_error());
+
+        // no OPT to recover
+        raw.truncate(raw.len() - 1);
+        let (parsed, _) = DNS::from_lenient(&raw);
+        assert_eq!(true, parsed.edns().is_none());
+    }
+
+    #[test]
+    fn test_dns_with_dnssec_ok() {
+        let mut dns = DNS::new();
+        dns.with_ques("example.com", TYPE_A, CLASS_IN);
+        dns.with_dnssec_ok(false);
+        assert_eq!(true, dns.edns().is_none());
+
+        dns.with_dnssec_ok(true);
+        assert_eq!(1, dns.additional().len());
+        let mut parsed = DNS::from(&dns.encode(false).unwrap()).unwrap();
+        assert_eq!(true, parsed.requests_dnssec());
+        assert_eq!(DEFAULT_UDP_PAYLOAD, parsed.request_udp_payload());
+
+        // the OPT is kept, only the bit is cleared
+        parsed.with_dnssec_ok(false);
+        let parsed = DNS::from(&parsed.encode(false).unwrap()).unwrap();
+        assert_eq!(false, parsed.requests_dnssec());
+        assert_eq!(1, parsed.additional().len());
+    }
+
+    #[test]
+    fn test_dns_merge_answers() {
+        let response = |rrs: Vec<RR>| {
+            let mut dns = DNS::new();
+            dns.with_ques("www.example.com", TYPE_A, CLASS_IN);
+            dns.into_response();
+            for rr in rrs {
+                dns.add_answer_rr(Rc::new(RefCell::new(rr)));
+            }
+            dns.add_authority_rr(Rc::new(RefCell::new(RR::ns(
+                "example.com",
+                3600,
+                "ns.example.com",
+            ))));
+            dns
+        };
+        let mut v4 = response(vec![RR::a(
+            "www.example.com",
+            300,
+            Ipv4Addr::new(10, 0, 0, 1),
+        )]);
+        v4.head().with_id(7);
+        let mut v6 = response(vec![RR::aaaa(
+            "www.example.com",
+            300,
+            "2001:db8::1".parse().unwrap(),
+        )]);
+        // the identical record with the other case and TTL
+        v6.add_answer_rr(Rc::new(RefCell::new(RR::a(
+            "WWW.example.com",
+            60,
+            Ipv4Addr::new(10, 0, 0, 1),
+        ))));
+        v6.with_dnssec_ok(true);
+
+        v4.merge_answers(&v6);
+        assert_eq!(7, v4.head().id());
+        assert_eq!(1, v4.ques().len());
+        assert_eq!(
+            vec![TYPE_A, TYPE_AAAA],
+            v4.answers()
+                .0
+                .iter()
+                .map(|rr| rr.borrow().typ())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(1, v4.authority().len());
+        assert_eq!(0, v4.additional().len());
+
+        let mut merged = DNS::from(&v4.encode(false).unwrap()).unwrap();
+        assert_eq!(2, merged.head().ancount());
+        assert_eq!(vec![Ipv4Addr::new(10, 0, 0, 1)], merged.a_records());
+        assert_eq!(
+            &RDataType::AAAA(AAAA::new("2001:db8::1".parse().unwrap())),
+            merged.answers().0[1].borrow().rdata()
+        );
+    }
+
+    #[test]
+    fn test_dns_request_edns() {
+        let mut dns = DNS::new();
+        dns.with_ques("example.com", TYPE_A, CLASS_IN);
+        let query = DNS::from(&dns.encode(false).unwrap()).unwrap();
+        assert_eq!(512, query.request_udp_payload());
+        assert_eq!(false, query.requests_dnssec());
+
+        let mut opt = RR::new();
+        opt.with_type(TYPE_OPT)
+            .with_rdata(RDataType::OPT(OPT::new()))
+            .convert_pseudo()
+            .unwrap()
+            .with_udp_payload(4096)
+            .with_dnssec_ok(true);
+        dns.add_additional_rr(Rc::new(RefCell::new(opt)));
+        let query = DNS::from(&dns.encode(false).unwrap()).unwrap();
+        assert_eq!(4096, query.request_udp_payload());
+        assert_eq!(true, query.requests_dnssec());
+
+        // the size smaller than 512 is treated as 512
+        dns.edns()
+            .unwrap()
+            .borrow_mut()
+            .convert_pseudo()
+            .unwrap()
+            .with_udp_payload(100);
+        let query = DNS::from(&dns.encode(false).unwrap()).unwrap();
+        assert_eq!(512, query.request_udp_payload());
+    }
+
+    #[test]
+    fn test_dns_set_dnssec_requested() {
+        // no OPT
+        let mut dns = DNS::new();
+        dns.with_ques("example.com", TYPE_A, CLASS_IN);
+        assert_eq!(false, dns.dnssec_ok());
+        dns.set_dnssec_requested();
+        assert_eq!(true, dns.dnssec_ok());
+        assert_eq!(1, dns.head().arcount());
+        let opt = dns.edns().unwrap();
+        assert_eq!(
+            1232,
+            opt.borrow_mut().convert_pseudo().unwrap().udp_payload()
+        );
+
+        // the existing OPT is reused, its other fields are kept
+        let mut opt = RR::new();
+        opt.with_type(TYPE_OPT)
+            .with_rdata(RDataType::OPT(OPT::new()))
+            .convert_pseudo()
+            .unwrap()
+            .with_udp_payload(4096);
+        let mut dns = DNS::new();
+        dns.add_additional_rr(Rc::new(RefCell::new(opt)));
+        dns.set_dnssec_requested();
+        assert_eq!(true, dns.dnssec_ok());
+        assert_eq!(1, dns.head().arcount());
+        let opt = dns.edns().unwrap();
+        assert_eq!(
+            4096,
+            opt.borrow_mut().convert_pseudo().unwrap().udp_payload()
+        );
+
+        // survives the encoding
+        let raw = dns.encode(false).unwrap();
+        assert_eq!(true, DNS::from(&raw).unwrap().dnssec_ok());
+    }
+
+    #[test]
+    fn test_dns_with_edns() {
+        let mut dns = DNS::new();
+        dns.with_ques("example.com", TYPE_A, CLASS_IN);
+        dns.with_edns().with_edns();
+        assert_eq!(1, dns.head().arcount());
+
+        let raw = dns.encode(false).unwrap();
+        let opt = DNS::from(&raw).unwrap().edns().unwrap();
+        let mut opt = opt.borrow_mut();
+        let prr = opt.convert_pseudo().unwrap();
+        assert_eq!(1232, prr.udp_payload());
+        assert_eq!(false, prr.dnssec_ok());
+    }
+
+    #[test]
+    fn test_dns_with_ques_root() {
+        for root in [".", ""] {
+            let mut dns = DNS::new();
+            dns.with_ques(root, TYPE_NS, CLASS_IN);
+            assert_eq!(0, dns.ques().0[0].qname().0.len());
+
+            let raw = dns.encode(false).unwrap();
+            // header, the root QNAME, QTYPE and QCLASS
+            assert_eq!(12 + 1 + 4, raw.len());
+            assert_eq!(0, raw[12]);
+            assert_eq!(&[0, 2, 0, 1], &raw[13..]);
+        }
+
+        // the trailing dot
+        let mut dns = DNS::new();
+        dns.with_ques("example.com.", TYPE_A, CLASS_IN);
+        assert_eq!(vec!["example", "com"], dns.ques().0[0].qname().0);
+    }
+
+    #[test]
+    fn test_dns_min_ttl() {
+        let mut dns = DNS::new();
+        assert_eq!(None, dns.min_ttl());
+
+        dns.with_edns();
+        assert_eq!(None, dns.min_ttl());
+
+        let rr = |rr: RR| Rc::new(RefCell::new(rr));
+        dns.add_answer_rr(rr(RR::a(
+            "www.example.com",
+            300,
+            Ipv4Addr::new(10, 0, 0, 1),
+        )));
+        dns.add_authority_rr(rr(RR::ns("example.com", 3600, "ns.example.com")));
+        dns.add_additional_rr(rr(RR::a("ns.example.com", 60, Ipv4Addr::new(10, 0, 0, 2))));
+        assert_eq!(Some(60), dns.min_ttl());
+    }
+
+    #[test]
+    fn test_dns_from_fake_short() {
+        let err = DNS::from_fake(&[0, 1, 2, 3, 4]).unwrap_err();
+        assert_eq!("the dns package is incomplete", err.to_string());
+    }
 }
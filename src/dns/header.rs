@@ -24,7 +24,7 @@ The header contains the following fields:
 +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
 ```
  */
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Header([u8; 12]);
 
 impl Header {
@@ -435,6 +435,16 @@ impl Header {
     pub fn get_0(&self) -> [u8; 12] {
         return self.0;
     }
+
+    /// as_bytes: the wire format of the header borrowed, without the copy of [`Header::get_0`].
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// as_bytes_mut: the wire format of the header to alter in place, such as the ID.
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
 }
 
 #[cfg(test)]
@@ -454,6 +464,17 @@ mod tests {
         assert_eq!(12, head.id());
     }
 
+    #[test]
+    pub fn test_header_as_bytes() {
+        let mut head = Header([0; 12]);
+        head.with_id(0x1234);
+        assert_eq!(&[0x12, 0x34], &head.as_bytes()[..2]);
+        assert_eq!(&head.get_0()[..], head.as_bytes());
+
+        head.as_bytes_mut()[1] = 0x56;
+        assert_eq!(0x1256, head.id());
+    }
+
     #[test]
     pub fn test_header_qr() {
         let mut head = Header([0, 0, 0xFF, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
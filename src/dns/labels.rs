@@ -1,12 +1,25 @@
 use crate::util;
-use anyhow::Error;
+use anyhow::{anyhow, Error};
 use nom::AsChar;
 
+/// the max length of a label.
+pub const MAX_LABEL_LEN: usize = 63;
+
+/// the max length of a name in the wire format, including the length octets.
+pub const MAX_NAME_LEN: usize = 255;
+
+/// the max number of the compression pointers followed when parsing a name, a longer chain is rejected
+/// even if there is no loop.
+pub const MAX_POINTER_FOLLOWS: usize = 16;
+
+/// the length of the fixed header of the message, a compression pointer never points into it.
+pub const HEADER_LEN: usize = 12;
+
 /// The labels must follow the rules for ARPANET host names.  They must
 /// start with a letter, end with a letter or digit, and have as interior
 /// characters only letters, digits, and hyphen.  There are also some
 /// restrictions on the length.  Labels must be 63 characters or less.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Labels(pub Vec<String>);
 
 impl Labels {
@@ -21,13 +34,15 @@ impl Labels {
         Ok(labels)
     }
 
-    pub fn clone(&self) -> Self {
-        let mut new_labels = Labels::new();
-        for s in &self.0 {
-            let new_s = s.as_str();
-            new_labels.0.push(new_s.to_string());
-        }
-        new_labels
+    /// from_domain_name: split the dotted domain name into the labels, the empty labels (the trailing
+    /// root) are omitted.
+    pub fn from_domain_name(name: &str) -> Self {
+        Labels(
+            name.split(".")
+                .filter(|label| !label.is_empty())
+                .map(|label| label.to_string())
+                .collect(),
+        )
     }
 
     pub fn extend(&mut self, labels: Labels) {
@@ -37,17 +52,87 @@ impl Labels {
     }
 
     pub fn parse(raw: &[u8], offset: &mut usize) -> Result<Self, Error> {
+        Self::parse_with_budget(raw, offset, &mut { MAX_POINTER_FOLLOWS })
+    }
+
+    /// parse_with_budget: same as [`Labels::parse`], but each followed compression pointer consumes the
+    /// budget shared with the caller, see [`follow_pointer`]. The name is checked by [`Labels::check_len`].
+    pub fn parse_with_budget(
+        raw: &[u8],
+        offset: &mut usize,
+        budget: &mut usize,
+    ) -> Result<Self, Error> {
+        let labels = match Self::parse_uncompressed(raw, offset) {
+            Some(labels) => labels?,
+            None => Self::parse_compressed(raw, offset, budget)?,
+        };
+        labels.check_len()?;
+
+        Ok(labels)
+    }
+
+    /// the fast path of parse: when no compression pointer is present, the labels are sliced directly from
+    /// the raw, the UTF-8 is validated once for the whole name.
+    ///
+    /// return None if the name is compressed.
+    fn parse_uncompressed(raw: &[u8], offset: &mut usize) -> Option<Result<Self, Error>> {
+        // find the end of the name, and count the labels
+        let mut end = *offset;
+        let mut count = 0;
+        loop {
+            let length = match raw.get(end) {
+                Some(length) => *length as usize,
+                None => return Some(Err(Error::msg("the labels are incomplete"))),
+            };
+            if length == 0 {
+                break;
+            }
+            if let Err(e) = check_label_type(length as u8) {
+                return Some(Err(e));
+            }
+            if length & 0b1100_0000 != 0 {
+                return None;
+            }
+            end += 1 + length;
+            count += 1;
+        }
+
+        // the length octets are ASCII, so a valid name is also valid for every label
+        let name = match std::str::from_utf8(&raw[*offset..end]) {
+            Ok(name) => name,
+            Err(e) => return Some(Err(e.into())),
+        };
+        let mut labels = Labels(Vec::with_capacity(count));
+        let mut start = 0;
+        while start < name.len() {
+            let length = raw[*offset + start] as usize;
+            labels
+                .0
+                .push(name[start + 1..start + 1 + length].to_string());
+            start += 1 + length;
+        }
+        *offset = end + 1;
+
+        Some(Ok(labels))
+    }
+
+    fn parse_compressed(raw: &[u8], offset: &mut usize, budget: &mut usize) -> Result<Self, Error> {
         let mut label = Labels { 0: vec![] };
         let mut iter = raw[*offset..].as_ref().iter();
         let mut start: usize = *offset;
+        let name_start = *offset;
 
-        let label_err: Error = Error::msg("the labels not incomplete");
+        let label_err: Error = Error::msg("the labels are incomplete");
 
         loop {
             let (mut comressed_offset, is_compressed) = util::is_compressed_wrap(&raw[start..]);
             if is_compressed {
-                let lb = Self::parse(raw, &mut comressed_offset)?;
+                check_pointer(comressed_offset, name_start)?;
+                follow_pointer(budget)?;
+                let lb = Self::parse_with_budget(raw, &mut comressed_offset, budget)?;
                 label.extend(lb);
+                // the name ends with the 2 octets pointer
+                *offset += 2;
                 break;
             }
 
@@ -57,6 +142,7 @@ impl Labels {
             if u.as_char().eq(&'\x00') {
                 break;
             }
+            check_label_type(*u)?;
 
             let mut length = *u as usize;
             *offset += length;
@@ -66,7 +152,7 @@ impl Labels {
             }
             label
                 .0
-                .push(String::from_utf8(raw[start..start + length].to_vec()).unwrap());
+                .push(String::from_utf8(raw[start..start + length].to_vec())?);
             // TODO: 使用skip优化
             while length > 0 {
                 start += 1;
@@ -81,6 +167,127 @@ impl Labels {
     pub fn encode_to_str(&self) -> String {
         return self.0.join(".");
     }
+
+    /**
+    to_wire: the wire format of the labels, each label is a length octet followed by the octets,
+    terminated by the zero length octet of the root. The empty labels are omitted.
+
+    Error if a label is longer than 63 octets or the name is longer than 255 octets.
+
+    ref: https://www.rfc-editor.org/rfc/rfc1035#section-3.1
+     */
+    pub fn to_wire(&self) -> Result<Vec<u8>, Error> {
+        self.check_len()?;
+        let mut wire = Vec::with_capacity(self.0.iter().map(|l| 1 + l.len()).sum::<usize>() + 1);
+        for label in self.0.iter().filter(|label| !label.is_empty()) {
+            wire.push(label.len() as u8);
+            wire.extend_from_slice(label.as_bytes());
+        }
+        wire.push(b'\x00');
+
+        Ok(wire)
+    }
+
+    /// check_len: error if a label is longer than 63 octets or the name is longer than 255 octets in the
+    /// wire format, such as a name parsed from the labels joined by the compression pointers.
+    pub fn check_len(&self) -> Result<(), Error> {
+        let mut len = 1;
+        for label in self.0.iter().filter(|label| !label.is_empty()) {
+            if label.len() > MAX_LABEL_LEN {
+                return Err(anyhow!(
+                    "the label is longer than {} octets: {}",
+                    MAX_LABEL_LEN,
+                    label
+                ));
+            }
+            len += 1 + label.len();
+        }
+        if len > MAX_NAME_LEN {
+            return Err(anyhow!(
+                "the name is longer than {} octets: {}",
+                MAX_NAME_LEN,
+                self.encode_to_str()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// follow_pointer: consume one of the budget before following a compression pointer, error if the
+/// budget is exhausted.
+pub fn follow_pointer(budget: &mut usize) -> Result<(), Error> {
+    if *budget == 0 {
+        return Err(anyhow!(
+            "the name follows more than {} compression pointers",
+            MAX_POINTER_FOLLOWS
+        ));
+    }
+    *budget -= 1;
+
+    Ok(())
+}
+
+/**
+check_label_type: the top 2 bits of the length octet are the label type, `0b00` is the normal label and
+`0b11` is the compression pointer. The extended label types `0b01` and `0b10` are not supported, rather
+than reading them as the long labels.
+
+ref: https://www.rfc-editor.org/rfc/rfc6891#section-5 (obsoletes RFC 2673)
+ */
+pub fn check_label_type(length: u8) -> Result<(), Error> {
+    match length & 0b1100_0000 {
+        0b0100_0000 | 0b1000_0000 => Err(anyhow!(
+            "unsupported extended label type 0b{:02b}: 0x{:02x}",
+            length >> 6,
+            length
+        )),
+        _ => Ok(()),
+    }
+}
+
+/**
+check_pointer: a compression pointer points to a prior occurrence of the name, so it must point
+backward: the target is before the start of the name being parsed, and after the header.
+
+This rule prevents the cycles: the targets strictly decrease along a chain of the pointers, so no
+pointer is followed twice. The pointer not pointing backward, such as the one to itself, is rejected
+as a compression pointer loop at once, rather than followed until [`MAX_POINTER_FOLLOWS`] is
+exhausted.
+
+ref: https://www.rfc-editor.org/rfc/rfc1035#section-4.1.4
+ */
+pub fn check_pointer(target: usize, name_start: usize) -> Result<(), Error> {
+    if target < HEADER_LEN {
+        return Err(anyhow!(
+            "the compression pointer points into the header: {}",
+            target
+        ));
+    }
+    if target >= name_start {
+        return Err(anyhow!(
+            "compression pointer loop: the pointer to {} does not point backward from {}",
+            target,
+            name_start
+        ));
+    }
+
+    Ok(())
+}
+
+/// pointer_chain: the name "a" after the header followed by a chain of the pointers, each points to
+/// the previous one, the fixture of the pointer budget tests.
+#[cfg(test)]
+pub(crate) fn pointer_chain(pointers: usize) -> Vec<u8> {
+    let mut raw = vec![0; HEADER_LEN];
+    raw.extend([1, b'a', 0]);
+    let mut prev = HEADER_LEN as u16;
+    for _ in 0..pointers {
+        let offset = raw.len() as u16;
+        raw.extend_from_slice(&(0xC000 | prev).to_be_bytes());
+        prev = offset;
+    }
+    raw
 }
 
 #[cfg(test)]
@@ -113,4 +320,208 @@ mod tests {
         );
         assert_eq!(false, label.is_ok());
     }
+
+    #[test]
+    fn test_labels_parse_invalid_utf8() {
+        // the question name `01 ff c0 0c` after the header found by the fuzzer
+        let mut raw = vec![0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0];
+        raw.extend([0x01, 0xff, 0xc0, 0x0c, 0x00, 0x01, 0x00, 0x01]);
+        assert_eq!(true, Labels::parse(&raw, &mut 12).is_err());
+        assert_eq!(true, crate::DNS::from(&raw).is_err());
+    }
+
+    #[test]
+    fn test_labels_parse_pointer_suffix() {
+        // the header, example com, then key + pointer to example com, then a following octet
+        let mut raw = vec![0; HEADER_LEN];
+        raw.extend([
+            0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm', 0x00, 0x03,
+            b'k', b'e', b'y', 0xc0, 0x0c, 0xff,
+        ]);
+        let mut offset = HEADER_LEN + 13;
+        let labels = Labels::parse(&raw, &mut offset).unwrap();
+        assert_eq!("key.example.com", labels.encode_to_str());
+        assert_eq!(HEADER_LEN + 19, offset);
+    }
+
+    #[test]
+    fn test_labels_parse_fast_path() {
+        // www mail google com
+        let raw = vec![
+            0x03, b'w', b'w', b'w', 0x04, b'm', b'a', b'i', b'l', 0x06, b'g', b'o', b'o', b'g',
+            b'l', b'e', 0x03, b'c', b'o', b'm', 0x00, 0xff,
+        ];
+        let mut offset = 0_usize;
+        let fast = Labels::parse(&raw, &mut offset).unwrap();
+        let mut slow_offset = 0_usize;
+        let slow =
+            Labels::parse_compressed(&raw, &mut slow_offset, &mut { MAX_POINTER_FOLLOWS }).unwrap();
+        assert_eq!(vec!["www", "mail", "google", "com"], fast.0);
+        assert_eq!(slow.0, fast.0);
+        assert_eq!(slow_offset, offset);
+
+        // invalid utf-8 is an error instead of panic
+        let mut offset = 0_usize;
+        assert_eq!(
+            true,
+            Labels::parse(&[0x01, 0xff, 0x00], &mut offset).is_err()
+        );
+
+        let times = 100_000;
+        let bench = |parse: fn(&[u8], &mut usize) -> Result<Labels, Error>| {
+            let start = std::time::Instant::now();
+            for _ in 0..times {
+                let mut offset = 0_usize;
+                parse(&raw, &mut offset).unwrap();
+            }
+            start.elapsed().as_nanos() / times
+        };
+        println!(
+            "parse four labels: before {}ns/op, after {}ns/op",
+            bench(|raw, offset| Labels::parse_compressed(raw, offset, &mut {
+                MAX_POINTER_FOLLOWS
+            })),
+            bench(Labels::parse)
+        );
+    }
+
+    #[test]
+    fn test_labels_to_wire() {
+        let wire = Labels::from_domain_name("google.com").to_wire().unwrap();
+        assert_eq!(
+            vec![0x06, b'g', b'o', b'o', b'g', b'l', b'e', 0x03, b'c', b'o', b'm', 0x00],
+            wire
+        );
+        assert_eq!(
+            wire,
+            Labels::from_domain_name("google.com.").to_wire().unwrap()
+        );
+        assert_eq!(vec![0x00], Labels::from_domain_name("").to_wire().unwrap());
+
+        assert_eq!(
+            true,
+            Labels::from_domain_name(&"a".repeat(64)).to_wire().is_err()
+        );
+        // 4 * (1 + 63) + 1 > 255
+        let long = vec!["a".repeat(63); 4].join(".");
+        assert_eq!(true, Labels::from_domain_name(&long).to_wire().is_err());
+    }
+
+    /// the wire format of the labels without the terminating zero octet.
+    fn wire_labels(labels: &[&str]) -> Vec<u8> {
+        let mut raw = vec![];
+        for label in labels {
+            raw.push(label.len() as u8);
+            raw.extend(label.as_bytes());
+        }
+        raw
+    }
+
+    #[test]
+    fn test_labels_parse_name_len() {
+        // the length octet 64 is not a normal label
+        let mut raw = wire_labels(&[&"a".repeat(64)]);
+        raw.push(0);
+        assert_eq!(true, Labels::parse(&raw, &mut 0).is_err());
+
+        // 3 * (1 + 63) + 1 = 193 is accepted, 4 * (1 + 63) + 1 = 257 is not
+        let max = "a".repeat(63);
+        let mut raw = wire_labels(&[&max, &max, &max]);
+        raw.push(0);
+        assert_eq!(3, Labels::parse(&raw, &mut 0).unwrap().0.len());
+        let mut raw = wire_labels(&[&max, &max, &max, &max]);
+        raw.push(0);
+        let err = Labels::parse(&raw, &mut 0).unwrap_err();
+        assert_eq!(
+            true,
+            err.to_string()
+                .starts_with("the name is longer than 255 octets")
+        );
+
+        // joined by the compression pointer
+        let mut raw = vec![0; HEADER_LEN];
+        raw.extend(wire_labels(&[&max, &max, &max]));
+        raw.push(0);
+        let mut offset = raw.len();
+        raw.extend(wire_labels(&[&max]));
+        raw.extend([0xc0, HEADER_LEN as u8]);
+        assert_eq!(true, Labels::parse(&raw, &mut offset).is_err());
+    }
+
+    #[test]
+    fn test_labels_pointer_budget() {
+        let raw = pointer_chain(MAX_POINTER_FOLLOWS);
+        let mut offset = raw.len() - 2;
+        assert_eq!(vec!["a"], Labels::parse(&raw, &mut offset).unwrap().0);
+
+        let raw = pointer_chain(20);
+        let mut offset = raw.len() - 2;
+        assert_eq!(true, Labels::parse(&raw, &mut offset).is_err());
+    }
+
+    #[test]
+    fn test_labels_extended_label_type() {
+        for raw in [
+            vec![0x41, b'a', 0x00],
+            vec![0x81, b'a', 0x00],
+            // after a label of the compressed name
+            vec![0x01, b'a', 0x41, b'b', 0xc0, 0x00],
+        ] {
+            let mut offset = 0;
+            let err = Labels::parse(&raw, &mut offset).unwrap_err();
+            assert_eq!(
+                true,
+                err.to_string()
+                    .starts_with("unsupported extended label type")
+            );
+        }
+    }
+
+    #[test]
+    fn test_labels_pointer_backward() {
+        // the pointer to the offset 0 points into the header
+        let mut raw = vec![0; HEADER_LEN];
+        raw.extend([1, b'a', 0xc0, 0x00]);
+        let mut offset = HEADER_LEN;
+        assert_eq!(true, Labels::parse(&raw, &mut offset).is_err());
+
+        // the pointer to itself or forward
+        for target in [HEADER_LEN + 2, HEADER_LEN + 4] {
+            let mut raw = vec![0; HEADER_LEN];
+            raw.extend([1, b'a', 0xc0, target as u8, 1, b'b', 0]);
+            let mut offset = HEADER_LEN;
+            assert_eq!(true, Labels::parse(&raw, &mut offset).is_err());
+        }
+    }
+
+    #[test]
+    fn test_labels_pointer_loop() {
+        // the pointer at the offset 12 points to itself
+        let mut raw = vec![0; HEADER_LEN];
+        raw.extend([0xc0, HEADER_LEN as u8]);
+        let mut offset = HEADER_LEN;
+        let err = Labels::parse(&raw, &mut offset).unwrap_err();
+        assert_eq!(
+            true,
+            err.to_string().starts_with("compression pointer loop")
+        );
+
+        // the pointers to each other
+        raw.extend([
+            1,
+            b'a',
+            0xc0,
+            HEADER_LEN as u8 + 6,
+            1,
+            b'b',
+            0xc0,
+            HEADER_LEN as u8 + 2,
+        ]);
+        let mut offset = HEADER_LEN + 2;
+        let err = Labels::parse(&raw, &mut offset).unwrap_err();
+        assert_eq!(
+            true,
+            err.to_string().starts_with("compression pointer loop")
+        );
+    }
 }
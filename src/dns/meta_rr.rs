@@ -0,0 +1,205 @@
+/*!
+ref: https://www.rfc-editor.org/rfc/rfc6891#section-6.1.2
+
+# The OPT RR(pseudo RR) has a fixed part and a variable set of options expressed as {attribute, value} pairs.
+The fixed part holds some DNS metadata, and also a small collection of basic extension elements:
+```shell
++------------+--------------+------------------------------+
+| Field Name | Field Type   | Description                  |
++------------+--------------+------------------------------+
+| NAME       | domain name  | MUST be 0 (root domain)      |
+| TYPE       | u_int16_t    | OPT (41)                     |
+| CLASS      | u_int16_t    | requestor's UDP payload size |
+| TTL        | u_int32_t    | extended RCODE and flags     |
+| RDLEN      | u_int16_t    | length of all RDATA          |
+| RDATA      | octet stream | {attribute,value} pairs      |
++------------+--------------+------------------------------+
+```
+
+The extended RCODE and flags, which OPT stores in the RR Time to Live (TTL) field, are structured as follows:
+```shell
+                +0 (MSB)                            +1 (LSB)
+     +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+  0: |         EXTENDED-RCODE        |            VERSION            |
+     +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+  2: | DO|                           Z                               |
+     +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+```
+ */
+
+use super::{
+    rdata::{
+        opt::{EDNS_OPTION_DAU, EDNS_OPTION_DHU, EDNS_OPTION_EDE, EDNS_OPTION_N3U},
+        RDataType,
+    },
+    RR,
+};
+
+/// the DNSSEC OK bit in the TTL field.
+const DO_BIT: u32 = 1 << 15;
+
+/// MetaRR: a view of the OPT RR, operate the fields as their meaning in EDNS(0).
+#[derive(Debug)]
+pub struct MetaRR<'a>(&'a mut RR);
+
+/// the OPT RR is called pseudo RR in the RFC.
+pub type PseudoRR<'a> = MetaRR<'a>;
+
+impl<'a> MetaRR<'a> {
+    pub fn from(rr: &'a mut RR) -> Self {
+        Self(rr)
+    }
+
+    pub fn udp_payload(&self) -> u16 {
+        self.0.class()
+    }
+
+    pub fn with_udp_payload(&mut self, udp_payload: u16) -> &mut Self {
+        self.0.with_class(udp_payload);
+        return self;
+    }
+
+    pub fn extended_rcode(&self) -> u8 {
+        (self.0.ttl() >> 24) as u8
+    }
+
+    pub fn with_extended_rcode(&mut self, rcode: u8) -> &mut Self {
+        let ttl = self.0.ttl() & 0x00FF_FFFF | (rcode as u32) << 24;
+        self.0.with_ttl(ttl);
+        return self;
+    }
+
+    pub fn version(&self) -> u8 {
+        (self.0.ttl() >> 16) as u8
+    }
+
+    pub fn with_version(&mut self, version: u8) -> &mut Self {
+        let ttl = self.0.ttl() & 0xFF00_FFFF | (version as u32) << 16;
+        self.0.with_ttl(ttl);
+        return self;
+    }
+
+    pub fn dnssec_ok(&self) -> bool {
+        self.0.ttl() & DO_BIT != 0
+    }
+
+    pub fn with_dnssec_ok(&mut self, dnssec_ok: bool) -> &mut Self {
+        let ttl = if dnssec_ok {
+            self.0.ttl() | DO_BIT
+        } else {
+            self.0.ttl() & !DO_BIT
+        };
+        self.0.with_ttl(ttl);
+        return self;
+    }
+
+    /// get the data of the option with the code.
+    pub fn option(&self, code: u16) -> Option<&[u8]> {
+        match self.0.rdata() {
+            RDataType::OPT(opt) => opt.option(code),
+            _ => None,
+        }
+    }
+
+    /// set the option with the code, it replace the option with the same code.
+    pub fn with_option(&mut self, code: u16, data: &[u8]) -> &mut Self {
+        if let RDataType::OPT(opt) = self.0.rdata_mut() {
+            opt.with_option(code, data);
+        }
+        return self;
+    }
+
+    /// the DNSSEC algorithms understood by the requestor.
+    pub fn dau(&self) -> Option<&[u8]> {
+        self.option(EDNS_OPTION_DAU)
+    }
+
+    pub fn with_dau(&mut self, algorithms: &[u8]) -> &mut Self {
+        self.with_option(EDNS_OPTION_DAU, algorithms)
+    }
+
+    /// the DS hash algorithms understood by the requestor.
+    pub fn dhu(&self) -> Option<&[u8]> {
+        self.option(EDNS_OPTION_DHU)
+    }
+
+    pub fn with_dhu(&mut self, algorithms: &[u8]) -> &mut Self {
+        self.with_option(EDNS_OPTION_DHU, algorithms)
+    }
+
+    /// the NSEC3 hash algorithms understood by the requestor.
+    pub fn n3u(&self) -> Option<&[u8]> {
+        self.option(EDNS_OPTION_N3U)
+    }
+
+    pub fn with_n3u(&mut self, algorithms: &[u8]) -> &mut Self {
+        self.with_option(EDNS_OPTION_N3U, algorithms)
+    }
+
+    /// the INFO-CODE and the EXTRA-TEXT of the extended DNS error explaining the failure.
+    pub fn extended_error(&self) -> Option<(u16, String)> {
+        let data = self.option(EDNS_OPTION_EDE)?;
+        if data.len() < 2 {
+            return None;
+        }
+        let text = String::from_utf8_lossy(&data[2..]).to_string();
+        Some((u16::from_be_bytes([data[0], data[1]]), text))
+    }
+
+    pub fn with_extended_error(&mut self, info_code: u16, text: &str) -> &mut Self {
+        let mut data = info_code.to_be_bytes().to_vec();
+        data.extend(text.as_bytes());
+        self.with_option(EDNS_OPTION_EDE, &data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::{
+        compress_list::CompressList,
+        rdata::{opt::OPT, RDataOperation},
+        TYPE_OPT,
+    };
+
+    #[test]
+    fn test_meta_rr_flags() {
+        let mut rr = RR::new();
+        rr.with_type(TYPE_OPT)
+            .with_rdata(RDataType::OPT(OPT::new()));
+        let mut prr = rr.convert_pseudo().unwrap();
+        prr.with_udp_payload(1232)
+            .with_extended_rcode(1)
+            .with_version(0)
+            .with_dnssec_ok(true);
+        assert_eq!(1232, prr.udp_payload());
+        assert_eq!(1, prr.extended_rcode());
+        assert_eq!(0, prr.version());
+        assert_eq!(true, prr.dnssec_ok());
+        assert_eq!(0x0100_8000, rr.ttl());
+    }
+
+    #[test]
+    fn test_meta_rr_dau() {
+        let mut rr = RR::new();
+        rr.with_type(TYPE_OPT)
+            .with_rdata(RDataType::OPT(OPT::new()));
+        rr.convert_pseudo()
+            .unwrap()
+            .with_dau(&[8, 13, 15])
+            .with_dhu(&[2])
+            .with_n3u(&[1]);
+
+        let mut rdata = vec![];
+        rr.rdata()
+            .encode(&mut rdata, &mut CompressList::new(), false)
+            .unwrap();
+        let mut read = RR::new();
+        read.with_type(TYPE_OPT)
+            .with_rdata(RDataType::OPT(OPT::from(&[], &rdata, 0).unwrap()));
+        let prr = read.convert_pseudo().unwrap();
+        assert_eq!(Some(&[8u8, 13, 15][..]), prr.dau());
+        assert_eq!(Some(&[2u8][..]), prr.dhu());
+        assert_eq!(Some(&[1u8][..]), prr.n3u());
+    }
+}
@@ -6,20 +6,40 @@ pub mod meta_rr;
 pub mod question;
 pub mod rdata;
 mod rr;
+pub mod tcp;
 
-pub use dns::DNS;
+pub use compress_list::CompressList;
+pub use dns::{ParseWarning, Section, DNS};
 pub use header::Header;
+pub use labels::Labels;
 pub use question::Question;
-pub use rr::RR;
-use std::{cell::RefCell, rc::Rc, sync::Arc};
+pub use rr::{detect_cname_chain_loop, detect_cname_loop, RRs, RR};
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::{Arc, RwLock},
+};
 
 pub type RcRf<T> = Rc<RefCell<T>>;
-pub type ArcRf<T> = Arc<RefCell<T>>;
+pub type ArcRf<T> = Arc<RwLock<T>>;
 pub type VecRcRf<T> = Vec<RcRf<T>>;
+pub type VecArcRf<T> = Vec<ArcRf<T>>;
 
 pub type Type = u16;
 pub type Class = u16;
 
+/// the default UDP payload size advertised in the EDNS OPT RR, the messages of the size are not
+/// fragmented on the most networks.
+///
+/// ref: https://www.dnsflagday.net/2020/
+pub const DEFAULT_UDP_PAYLOAD: u16 = 1232;
+
+/// the UDP payload size every requestor can reassemble, the messages over UDP without EDNS are
+/// limited to it.
+///
+/// ref: https://www.rfc-editor.org/rfc/rfc1035#section-2.3.4
+pub const MIN_UDP_PAYLOAD: u16 = 512;
+
 /// a host address
 pub const TYPE_A: Type = 1;
 
@@ -68,9 +88,24 @@ pub const TYPE_MX: Type = 15;
 /// text strings
 pub const TYPE_TXT: Type = 16;
 
+/// a IPv6 host address
+///
+/// ref: https://www.rfc-editor.org/rfc/rfc3596
+pub const TYPE_AAAA: Type = 28;
+
+/// SRV, the location of the services
+///
+/// ref: https://www.rfc-editor.org/rfc/rfc2782
+pub const TYPE_SRV: Type = 33;
+
 /// OPT
 pub const TYPE_OPT: Type = 41;
 
+/// APL, the address prefix list
+///
+/// ref: https://www.rfc-editor.org/rfc/rfc3123
+pub const TYPE_APL: Type = 42;
+
 /// DS
 pub const TYPE_DS: Type = 43;
 
@@ -83,6 +118,21 @@ pub const TYPE_NSEC: Type = 47;
 /// DNSKEY
 pub const TYPE_DNSKEY: Type = 48;
 
+/// NSEC3
+///
+/// ref: https://www.rfc-editor.org/rfc/rfc5155
+pub const TYPE_NSEC3: Type = 50;
+
+/// TSIG, the transaction signature
+///
+/// ref: https://www.rfc-editor.org/rfc/rfc8945
+pub const TYPE_TSIG: Type = 250;
+
+/// for QType, incremental zone transfer
+///
+/// ref: https://www.rfc-editor.org/rfc/rfc1995
+pub const TYPE_IXFR: Type = 251;
+
 /// for QType
 pub const TYPE_AXFR: Type = 252;
 
@@ -109,8 +159,173 @@ pub const CLASS_HS: Class = 4;
 /// for QClass
 pub const CLASS_ANY: Class = 255;
 
-// TODO:
-pub const ERR_BADSIG: u8 = 16;
-pub const ERR_BADKEY: u8 = 16;
-pub const ERR_BADTIME: u8 = 16;
-pub const ERR_BADVERS: u8 = 16;
+/// is_meta_type: whether the type is only used in the questions (IXFR, AXFR, MAILB, MAILA and ANY),
+/// the RRs of the type are never stored in the zones.
+///
+/// ref: https://www.rfc-editor.org/rfc/rfc6895#section-3.1
+pub fn is_meta_type(typ: Type) -> bool {
+    (TYPE_IXFR..=TYPE_ANY).contains(&typ)
+}
+
+/// is_meta_class: whether the class is only used in the questions (ANY).
+pub fn is_meta_class(class: Class) -> bool {
+    class == CLASS_ANY
+}
+
+/// type_mnemonic: the mnemonic of the type in the presentation format, the type without a mnemonic is
+/// `TYPE` followed by its value, such as `TYPE65280`.
+///
+/// ref: https://www.rfc-editor.org/rfc/rfc3597#section-5
+pub fn type_mnemonic(typ: Type) -> String {
+    let mnemonic = match typ {
+        TYPE_A => "A",
+        TYPE_NS => "NS",
+        TYPE_MD => "MD",
+        TYPE_MF => "MF",
+        TYPE_CNAME => "CNAME",
+        TYPE_SOA => "SOA",
+        TYPE_MB => "MB",
+        TYPE_MG => "MG",
+        TYPE_MR => "MR",
+        TYPE_NULL => "NULL",
+        TYPE_WKS => "WKS",
+        TYPE_PTR => "PTR",
+        TYPE_HINFO => "HINFO",
+        TYPE_MINFO => "MINFO",
+        TYPE_MX => "MX",
+        TYPE_TXT => "TXT",
+        TYPE_AAAA => "AAAA",
+        TYPE_SRV => "SRV",
+        TYPE_OPT => "OPT",
+        TYPE_APL => "APL",
+        TYPE_DS => "DS",
+        TYPE_RRSIG => "RRSIG",
+        TYPE_NSEC => "NSEC",
+        TYPE_DNSKEY => "DNSKEY",
+        TYPE_NSEC3 => "NSEC3",
+        TYPE_TSIG => "TSIG",
+        TYPE_IXFR => "IXFR",
+        TYPE_AXFR => "AXFR",
+        TYPE_MAILB => "MAILB",
+        TYPE_MAILA => "MAILA",
+        TYPE_ANY => "ANY",
+        _ => return format!("TYPE{}", typ),
+    };
+
+    mnemonic.to_string()
+}
+
+/// class_mnemonic: the mnemonic of the class in the presentation format, the class without a mnemonic
+/// is `CLASS` followed by its value.
+///
+/// ref: https://www.rfc-editor.org/rfc/rfc3597#section-5
+pub fn class_mnemonic(class: Class) -> String {
+    let mnemonic = match class {
+        CLASS_IN => "IN",
+        CLASS_CS => "CS",
+        CLASS_CH => "CH",
+        CLASS_HS => "HS",
+        CLASS_ANY => "ANY",
+        _ => return format!("CLASS{}", class),
+    };
+
+    mnemonic.to_string()
+}
+
+/// align_columns: join the fields by the tabs in the dig style, each field starts at its column,
+/// a tab moves to the next multiple of 8, and at least one tab follows the longer field.
+pub(crate) fn align_columns(fields: &[(&str, usize)]) -> String {
+    let mut text = String::new();
+    // the column of the end of the text
+    let mut column = 0;
+    for (field, to) in fields {
+        while !text.is_empty() {
+            text.push('\t');
+            column = (column / 8 + 1) * 8;
+            if column >= *to {
+                break;
+            }
+        }
+        text.push_str(field);
+        column += field.len();
+    }
+
+    text
+}
+
+/// No error condition
+pub const RCODE_NOERROR: u8 = 0;
+/// Format error - The name server was unable to interpret the query.
+pub const RCODE_FORMERR: u8 = 1;
+/// Server failure - The name server was unable to process this query due to a problem with the name server.
+pub const RCODE_SERVFAIL: u8 = 2;
+/// Name Error - the domain name referenced in the query does not exist.
+pub const RCODE_NXDOMAIN: u8 = 3;
+/// Not Implemented - The name server does not support the requested kind of query.
+pub const RCODE_NOTIMP: u8 = 4;
+/// Refused - The name server refuses to perform the specified operation for policy reasons.
+pub const RCODE_REFUSED: u8 = 5;
+/// Not Authorized - The server is not authorized for the zone, or the TSIG of the request failed.
+///
+/// ref: https://www.rfc-editor.org/rfc/rfc8945#section-5.2
+pub const RCODE_NOTAUTH: u8 = 9;
+
+// The extended RCODEs exceed the 4 bits RCODE of the header, they are carried by the EDNS OPT RR or
+// the TSIG RR.
+//
+// ref: https://www.iana.org/assignments/dns-parameters/dns-parameters.xhtml#dns-parameters-6
+/// Bad OPT Version (in the OPT RR).
+pub const ERR_BADVERS: u16 = 16;
+/// TSIG Signature Failure (in the TSIG RR), the same value as BADVERS.
+pub const ERR_BADSIG: u16 = 16;
+/// Key not recognized.
+pub const ERR_BADKEY: u16 = 17;
+/// Signature out of time window.
+pub const ERR_BADTIME: u16 = 18;
+/// Bad TKEY Mode.
+pub const ERR_BADMODE: u16 = 19;
+/// Duplicate key name.
+pub const ERR_BADNAME: u16 = 20;
+/// Algorithm not supported.
+pub const ERR_BADALG: u16 = 21;
+/// Bad Truncation.
+pub const ERR_BADTRUNC: u16 = 22;
+/// Bad/missing Server Cookie.
+pub const ERR_BADCOOKIE: u16 = 23;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_meta() {
+        assert_eq!(true, is_meta_type(TYPE_ANY));
+        assert_eq!(true, is_meta_type(TYPE_AXFR));
+        assert_eq!(true, is_meta_type(TYPE_IXFR));
+        assert_eq!(false, is_meta_type(TYPE_A));
+        assert_eq!(false, is_meta_type(TYPE_MX));
+
+        assert_eq!(true, is_meta_class(CLASS_ANY));
+        assert_eq!(false, is_meta_class(CLASS_IN));
+        assert_eq!(false, is_meta_class(CLASS_CH));
+    }
+
+    #[test]
+    fn test_extended_rcodes() {
+        // the IANA DNS RCODEs registry
+        assert_eq!(
+            vec![16, 16, 17, 18, 19, 20, 21, 22, 23],
+            vec![
+                ERR_BADVERS,
+                ERR_BADSIG,
+                ERR_BADKEY,
+                ERR_BADTIME,
+                ERR_BADMODE,
+                ERR_BADNAME,
+                ERR_BADALG,
+                ERR_BADTRUNC,
+                ERR_BADCOOKIE,
+            ]
+        );
+    }
+}
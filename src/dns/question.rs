@@ -1,5 +1,8 @@
-use super::{compress_list::CompressList, labels::Labels, Class, Type};
-use anyhow::Error;
+use super::{
+    align_columns, class_mnemonic, compress_list::CompressList, labels::Labels, type_mnemonic,
+    Class, Type,
+};
+use anyhow::{anyhow, Error};
 
 /**
 The question section is used to carry the "question" in most queries,
@@ -20,7 +23,7 @@ contains QDCOUNT (usually 1) entries, each of the following format:
 +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
 ```
 */
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Question {
     // record the question length. Not in standard Question protocol
     // init with 5: 2 byte qtype, 2 byte qclass, and 1 byte '\x00' of end in qname
@@ -61,7 +64,7 @@ impl Question {
     }
 
     pub fn from(raw: &[u8], offset: &mut usize) -> Result<Self, Error> {
-        let pkg_err = Err(Error::msg("the question package not incomplete"));
+        let pkg_err = Err(Error::msg("the question package is incomplete"));
         if raw.len() == 0 {
             return pkg_err;
         }
@@ -93,13 +96,18 @@ impl Question {
         return Ok(ques);
     }
 
-    pub fn clone(&self) -> Self {
-        let mut ques = Question::new();
-        ques.length = self.length;
-        ques.qname = self.qname.clone();
-        ques.qtype = self.qtype;
-        ques.qclass = self.qclass;
-        ques
+    /// matches: whether the other question asks the same: the qname is compared case-insensitively
+    /// per the DNS rules, the qtype and qclass must be exactly the same.
+    pub fn matches(&self, other: &Question) -> bool {
+        let labels = |q: &Question| -> Vec<String> {
+            q.qname
+                .0
+                .iter()
+                .filter(|l| !l.is_empty())
+                .map(|l| l.to_ascii_lowercase())
+                .collect()
+        };
+        self.qtype == other.qtype && self.qclass == other.qclass && labels(self) == labels(other)
     }
 
     pub fn qname(&self) -> &Labels {
@@ -134,17 +142,25 @@ impl Question {
         return self;
     }
 
-    pub fn encode(&self) -> Vec<u8> {
-        let mut result = Vec::<u8>::new();
+    /**
+    to_presentation: the question in the dig style, such as `;example.com.\t\t\tIN\tA`. The name is
+    commented out by the leading semicolon, then the columns are aligned by the tabs as the records of
+    dig: the class at the column 32 and the type at the column 40, or a tab after the longer field.
+     */
+    pub fn to_presentation(&self) -> String {
+        align_columns(&[
+            (
+                &format!(";{}.", self.qname.encode_to_str().trim_end_matches('.')),
+                0,
+            ),
+            (&class_mnemonic(self.qclass), 32),
+            (&type_mnemonic(self.qtype), 40),
+        ])
+    }
 
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
         // encode domain qname
-        for name in &self.qname.0 {
-            result.push(name.len() as u8);
-            for v in name.as_bytes() {
-                result.push(*v);
-            }
-        }
-        result.push(b'\x00');
+        let mut result = self.qname.to_wire()?;
 
         // encode qtype
         for v in self.qtype.to_be_bytes() {
@@ -155,11 +171,11 @@ impl Question {
             result.push(v);
         }
 
-        return result;
+        return Ok(result);
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Questions(pub Vec<Question>);
 
 impl Questions {
@@ -167,6 +183,23 @@ impl Questions {
         Self { 0: vec![] }
     }
 
+    /// parse exactly count questions from the offset of raw, error if the raw runs out.
+    pub fn parse(raw: &[u8], offset: &mut usize, count: u16) -> Result<Self, Error> {
+        let mut quess = Self::new();
+        for i in 0..count {
+            if *offset >= raw.len() {
+                return Err(anyhow!(
+                    "the question section is incomplete: parsed {} of {} questions",
+                    i,
+                    count
+                ));
+            }
+            quess.push(Question::from(raw, offset)?);
+        }
+
+        Ok(quess)
+    }
+
     pub fn len(&self) -> usize {
         self.0.len()
     }
@@ -179,20 +212,25 @@ impl Questions {
         self.0.pop()
     }
 
-    pub fn encode(&self, raw: &mut Vec<u8>, cl: &mut CompressList) {
+    /// encode the questions after the header, the qnames are never compressed, but registered in the
+    /// CompressList at their offsets, so the names of the RRs can point back to them.
+    pub fn encode(&self, raw: &mut Vec<u8>, cl: &mut CompressList) -> Result<(), Error> {
         let mut offset = 12;
         for ques in &self.0 {
-            raw.extend_from_slice(&ques.encode());
+            raw.extend_from_slice(&ques.encode()?);
             let domain = ques.qname().encode_to_str();
             cl.push(domain.as_str(), offset);
             offset += ques.length;
         }
+
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dns::{rdata::encode_domain_name, CLASS_CH, CLASS_IN, TYPE_A};
 
     #[test]
     pub fn test_question_from() {
@@ -229,6 +267,103 @@ mod tests {
         }
     }
 
+    #[test]
+    pub fn test_question_matches() {
+        let new_ques = |names: &[&str], qtype, qclass| {
+            let mut ques = Question::new();
+            for name in names {
+                ques.with_name(name);
+            }
+            ques.with_qtype(qtype).with_qclass(qclass);
+            ques
+        };
+        let ques = new_ques(&["www", "example", "com"], 1, 1);
+
+        // the response echoes the name in different case
+        assert_eq!(
+            true,
+            ques.matches(&new_ques(&["WwW", "EXAMPLE", "com"], 1, 1))
+        );
+        assert_eq!(
+            true,
+            ques.matches(&new_ques(&["www", "example", "com", ""], 1, 1))
+        );
+
+        assert_eq!(
+            false,
+            ques.matches(&new_ques(&["www", "example", "net"], 1, 1))
+        );
+        assert_eq!(
+            false,
+            ques.matches(&new_ques(&["www", "example", "com"], 28, 1))
+        );
+        assert_eq!(
+            false,
+            ques.matches(&new_ques(&["www", "example", "com"], 1, 3))
+        );
+    }
+
+    #[test]
+    pub fn test_questions_parse() {
+        let raw = vec![
+            // google com
+            0x06, 0x67, 0x6f, 0x6f, 0x67, 0x6c, 0x65, 0x03, 0x63, 0x6f, 0x6d, 0x00,
+            // type & qclass
+            0x00, 0x01, 0x00, 0x01, //
+            // www com
+            0x03, 0x77, 0x77, 0x77, 0x03, 0x63, 0x6f, 0x6d, 0x00, //
+            // type & qclass
+            0x00, 0x1c, 0x00, 0x01,
+        ];
+        let mut offset = 0;
+        let quess = Questions::parse(&raw, &mut offset, 2).unwrap();
+        assert_eq!(2, quess.len());
+        assert_eq!("google.com", quess.0[0].qname().encode_to_str());
+        assert_eq!(28, quess.0[1].qtype());
+
+        // the second question is truncated
+        let mut offset = 0;
+        assert_eq!(
+            true,
+            Questions::parse(&raw[..16 + 3], &mut offset, 2).is_err()
+        );
+        let mut offset = 0;
+        assert_eq!(true, Questions::parse(&raw[..16], &mut offset, 2).is_err());
+    }
+
+    #[test]
+    pub fn test_question_from_long_name() {
+        // 4 * (1 + 63) + 1 > 255
+        let mut raw = vec![];
+        for _ in 0..4 {
+            raw.push(63);
+            raw.extend([b'a'; 63]);
+        }
+        raw.extend([0, 0, 1, 0, 1]);
+        assert_eq!(true, Question::from(&raw, &mut 0).is_err());
+    }
+
+    #[test]
+    pub fn test_question_to_presentation() {
+        let mut ques = Question::new();
+        ques.with_name("example")
+            .with_name("com")
+            .with_qtype(TYPE_A)
+            .with_qclass(CLASS_IN);
+        assert_eq!(";example.com.\t\t\tIN\tA", ques.to_presentation());
+
+        let mut ques = Question::new();
+        ques.with_name("a-very-long-label-of-the-name")
+            .with_name("example")
+            .with_name("com")
+            .with_qtype(65280)
+            .with_qclass(CLASS_CH);
+        assert_eq!(
+            ";a-very-long-label-of-the-name.example.com.\tCH\tTYPE65280",
+            ques.to_presentation()
+        );
+    }
+
     #[test]
     pub fn test_question_encode() {
         // correct
@@ -257,7 +392,12 @@ mod tests {
             0x11, 0x22, 0x33, 0x43,
         ];
 
-        assert_eq!(raw1, ques.encode());
-        assert_ne!(raw2, ques.encode());
+        assert_eq!(raw1, ques.encode().unwrap());
+        assert_ne!(raw2, ques.encode().unwrap());
+
+        // the same bytes as the rdata domain name
+        let mut name = encode_domain_name("google.com").unwrap();
+        name.extend([0x11, 0x22, 0x33, 0x44]);
+        assert_eq!(name, ques.encode().unwrap());
     }
 }
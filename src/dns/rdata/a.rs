@@ -25,7 +25,7 @@ use crate::dns::{compress_list::CompressList, rdata::ERR_RDATE_MSG};
 use anyhow::{anyhow, Error, Ok};
 use std::net::Ipv4Addr;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct A(pub Ipv4Addr);
 
 impl A {
@@ -33,24 +33,22 @@ impl A {
         Self { 0: ipv4 }
     }
 
-    pub fn from(raw: &[u8], rdata: &[u8]) -> Result<Self, Error> {
-        let mut a = Self {
-            0: Ipv4Addr::new(127, 0, 0, 0),
-        };
-        a.decode(raw, rdata)?;
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<Self, Error> {
+        let mut a = Self::new(Ipv4Addr::UNSPECIFIED);
+        a.decode(raw, rdata, rdata_start)?;
 
         Ok(a)
     }
 }
 
 impl RDataOperation for A {
-    fn decode(&mut self, _raw: &[u8], rdata: &[u8]) -> Result<(), Error> {
+    fn decode(&mut self, _raw: &[u8], rdata: &[u8], _rdata_start: usize) -> Result<usize, Error> {
         if rdata.len() < 4 {
             return Err(anyhow!(ERR_RDATE_MSG));
         }
         self.0 = Ipv4Addr::new(rdata[0], rdata[1], rdata[2], rdata[3]);
 
-        Ok(())
+        Ok(4)
     }
 
     fn encode(
@@ -0,0 +1,69 @@
+/*!
+ref: https://www.rfc-editor.org/rfc/rfc3596#section-2.2
+
+# AAAA RDATA format
+```shell
+    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    |                                               |
+    |                                               |
+    |                    ADDRESS                    |
+    |                                               |
+    |                                               |
+    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+```
+where:
+
+ADDRESS         A 128 bit IPv6 address is encoded in the data portion of an AAAA
+                resource record in network byte order (high-order byte first).
+
+AAAA query for a specified domain name in the Internet class returns all
+associated AAAA resource records in the answer section of a response.
+
+A type AAAA query does not trigger additional section processing.
+ */
+
+use super::RDataOperation;
+use crate::dns::{compress_list::CompressList, rdata::ERR_RDATE_MSG};
+use anyhow::{anyhow, Error, Ok};
+use std::net::Ipv6Addr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AAAA(pub Ipv6Addr);
+
+impl AAAA {
+    pub fn new(ipv6: Ipv6Addr) -> Self {
+        Self { 0: ipv6 }
+    }
+
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<Self, Error> {
+        let mut aaaa = Self::new(Ipv6Addr::UNSPECIFIED);
+        aaaa.decode(raw, rdata, rdata_start)?;
+
+        Ok(aaaa)
+    }
+}
+
+impl RDataOperation for AAAA {
+    fn decode(&mut self, _raw: &[u8], rdata: &[u8], _rdata_start: usize) -> Result<usize, Error> {
+        if rdata.len() < 16 {
+            return Err(anyhow!(ERR_RDATE_MSG));
+        }
+        let mut octets = [0_u8; 16];
+        octets.copy_from_slice(&rdata[..16]);
+        self.0 = Ipv6Addr::from(octets);
+
+        Ok(16)
+    }
+
+    fn encode(
+        &self,
+        raw: &mut Vec<u8>,
+        _hm: &mut CompressList,
+        _is_compressed: bool,
+    ) -> Result<usize, Error> {
+        let encoded = self.0.octets().to_vec();
+        raw.extend_from_slice(&encoded);
+
+        Ok(encoded.len())
+    }
+}
@@ -0,0 +1,161 @@
+/*!
+ref: https://www.rfc-editor.org/rfc/rfc3123#section-4
+
+# APL RDATA format
+The RDATA is a list of the address prefixes, each of them is:
+```shell
+    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    |                 ADDRESSFAMILY                 |
+    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    |        PREFIX         | N|     AFDLENGTH      |
+    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    /                    AFDPART                    /
+    |                                               |
+    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+```
+where:
+
+ADDRESSFAMILY   16 bit unsigned value as assigned by IANA, 1 for IPv4 and 2 for IPv6.
+
+PREFIX          8 bit unsigned binary coded prefix length.
+
+N               Negation flag, indicates the presence of the "!" character in the textual format.
+
+AFDLENGTH       7 bit unsigned length in octets of the following address family dependent part.
+
+AFDPART         Address family dependent part, the address with the trailing zero octets omitted.
+
+The APL RRs are used to list the address ranges, such as the networks a service is available from.
+ */
+
+use super::RDataOperation;
+use crate::dns::compress_list::CompressList;
+use crate::dns::rdata::ERR_RDATE_MSG;
+use anyhow::{anyhow, Error};
+
+/// the address family of IPv4.
+pub const ADDRESS_FAMILY_IPV4: u16 = 1;
+
+/// the address family of IPv6.
+pub const ADDRESS_FAMILY_IPV6: u16 = 2;
+
+/// the max AFDLENGTH, which is 7 bits.
+const MAX_AFDLENGTH: usize = 0x7F;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AplItem {
+    pub family: u16,
+    pub prefix: u8,
+    /// the prefix is excluded from the list.
+    pub negation: bool,
+    pub afdpart: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct APL(pub Vec<AplItem>);
+
+impl APL {
+    pub fn new() -> Self {
+        APL(vec![])
+    }
+
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<Self, Error> {
+        let mut apl = Self::new();
+        apl.decode(raw, rdata, rdata_start)?;
+
+        Ok(apl)
+    }
+}
+
+impl RDataOperation for APL {
+    fn decode(&mut self, _raw: &[u8], rdata: &[u8], _rdata_start: usize) -> Result<usize, Error> {
+        self.0.clear();
+        let mut offset = 0;
+        while offset < rdata.len() {
+            if offset + 4 > rdata.len() {
+                return Err(anyhow!(ERR_RDATE_MSG));
+            }
+            let family = u16::from_be_bytes([rdata[offset], rdata[offset + 1]]);
+            let prefix = rdata[offset + 2];
+            let negation = rdata[offset + 3] & 0x80 != 0;
+            let afdlength = (rdata[offset + 3] & 0x7F) as usize;
+            offset += 4;
+            let afdpart = rdata
+                .get(offset..offset + afdlength)
+                .ok_or(anyhow!(ERR_RDATE_MSG))?;
+            offset += afdlength;
+
+            self.0.push(AplItem {
+                family,
+                prefix,
+                negation,
+                afdpart: afdpart.to_vec(),
+            });
+        }
+
+        Ok(offset)
+    }
+
+    fn encode(
+        &self,
+        raw: &mut Vec<u8>,
+        _hm: &mut CompressList,
+        _is_compressed: bool,
+    ) -> Result<usize, Error> {
+        let start = raw.len();
+        for item in &self.0 {
+            if item.afdpart.len() > MAX_AFDLENGTH {
+                return Err(anyhow!(
+                    "the AFDPART of APL is longer than {} octets",
+                    MAX_AFDLENGTH
+                ));
+            }
+            raw.extend_from_slice(&item.family.to_be_bytes());
+            raw.push(item.prefix);
+            raw.push((item.negation as u8) << 7 | item.afdpart.len() as u8);
+            raw.extend_from_slice(&item.afdpart);
+        }
+
+        Ok(raw.len() - start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AplItem, ADDRESS_FAMILY_IPV4, APL};
+    use crate::dns::{compress_list::CompressList, rdata::RDataOperation};
+
+    #[test]
+    fn test_apl_roundtrip() {
+        // 1:192.168.32.0/21 !1:192.168.38.0/28
+        let apl = APL(vec![
+            AplItem {
+                family: ADDRESS_FAMILY_IPV4,
+                prefix: 21,
+                negation: false,
+                afdpart: vec![192, 168, 32],
+            },
+            AplItem {
+                family: ADDRESS_FAMILY_IPV4,
+                prefix: 28,
+                negation: true,
+                afdpart: vec![192, 168, 38],
+            },
+        ]);
+
+        let mut raw = vec![];
+        let length = apl
+            .encode(&mut raw, &mut CompressList::new(), false)
+            .unwrap();
+        assert_eq!(
+            vec![0, 1, 21, 0x03, 192, 168, 32, 0, 1, 28, 0x83, 192, 168, 38],
+            raw
+        );
+        assert_eq!(raw.len(), length);
+        assert_eq!(apl, APL::from(&raw, &raw, 0).unwrap());
+
+        // the AFDPART beyond the rdata
+        assert_eq!(true, APL::from(&[], &raw[..6], 0).is_err());
+        assert_eq!(true, APL::from(&[], &raw[..2], 0).is_err());
+    }
+}
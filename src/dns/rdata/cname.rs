@@ -18,30 +18,35 @@ choose to restart the query at the canonical name in certain cases.  See
 the description of name server logic in [RFC-1034] for details.
 */
 
-use super::{encode_domain_name_wrap, parse_domain_name_without_len, RDataOperation};
+use super::{encode_domain_name_wrap, parse_domain_name, RDataOperation, ERR_RDATE_MSG};
 use crate::dns::compress_list::CompressList;
 use anyhow::Error;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CName(pub String);
 
 impl CName {
-    pub fn from(raw: &[u8], rdata: &[u8]) -> Result<Self, Error> {
-        let mut cname = Self { 0: "".to_string() };
-        cname.decode(raw, rdata)?;
+    pub fn new() -> Self {
+        Self { 0: "".to_string() }
+    }
+
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<Self, Error> {
+        let mut cname = Self::new();
+        cname.decode(raw, rdata, rdata_start)?;
 
         Ok(cname)
     }
 }
 
 impl RDataOperation for CName {
-    fn decode(&mut self, raw: &[u8], rdata: &[u8]) -> Result<(), Error> {
-        self.0 = parse_domain_name_without_len(raw, rdata)?
+    fn decode(&mut self, raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<usize, Error> {
+        let (list, length) = parse_domain_name(raw, rdata, rdata_start, 1)?;
+        self.0 = list
             .get(0)
-            .unwrap()
+            .ok_or(Error::msg(ERR_RDATE_MSG))?
             .encode_to_str();
 
-        Ok(())
+        Ok(length)
     }
 
     fn encode(
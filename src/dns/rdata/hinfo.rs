@@ -24,12 +24,19 @@ when talking between machines or operating systems of the same type.
 */
 
 use super::RDataOperation;
-use crate::dns::{compress_list::CompressList, rdata::parse_charactor_string};
-use anyhow::Error;
+use crate::dns::{
+    compress_list::CompressList,
+    rdata::{
+        character_string_to_presentation, character_strings_from_presentation,
+        parse_charactor_string,
+    },
+};
+use anyhow::{anyhow, Error};
+use std::{fmt, str::FromStr};
 
 // adapt RFC8482
 // ref: https://www.rfc-editor.org/rfc/rfc8482#section-4.2
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct HInfo {
     /// weather the HInfo is synthesized.
     ///
@@ -44,31 +51,77 @@ pub struct HInfo {
 }
 
 impl HInfo {
-    pub fn from(raw: &[u8], rdata: &[u8]) -> Result<Self, Error> {
-        let mut hinfo = Self {
+    pub fn new() -> Self {
+        Self {
             synthesized: false,
             cpu: "".to_string(),
             os: "".to_string(),
-        };
-        hinfo.decode(raw, rdata)?;
+        }
+    }
+
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<Self, Error> {
+        let mut hinfo = Self::new();
+        hinfo.decode(raw, rdata, rdata_start)?;
+
+        Ok(hinfo)
+    }
+
+    /// synthesized_rfc8482: the HINFO answered to the ANY query instead of all the RRsets of the name,
+    /// its CPU is "RFC8482" and the OS is empty.
+    ///
+    /// ref: https://www.rfc-editor.org/rfc/rfc8482#section-4.2
+    pub fn synthesized_rfc8482() -> Self {
+        Self {
+            synthesized: true,
+            cpu: "RFC8482".to_string(),
+            os: "".to_string(),
+        }
+    }
+}
+
+/// the presentation format: the quoted CPU and OS.
+impl fmt::Display for HInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            character_string_to_presentation(self.cpu.as_bytes()),
+            character_string_to_presentation(self.os.as_bytes())
+        )
+    }
+}
+
+impl FromStr for HInfo {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let list = character_strings_from_presentation(s)?;
+        if list.len() != 2 {
+            return Err(anyhow!("the HINFO must have the CPU and OS: {}", s));
+        }
+        let mut hinfo = Self::new();
+        hinfo.cpu = String::from_utf8(list[0].clone())?;
+        hinfo.os = String::from_utf8(list[1].clone())?;
+        hinfo.synthesized = hinfo.os.is_empty();
 
         Ok(hinfo)
     }
 }
 
 impl RDataOperation for HInfo {
-    fn decode(&mut self, _raw: &[u8], rdata: &[u8]) -> Result<(), Error> {
+    fn decode(&mut self, _raw: &[u8], rdata: &[u8], _rdata_start: usize) -> Result<usize, Error> {
         let list = parse_charactor_string(rdata)?;
         if list.len() >= 1 {
             self.synthesized = true;
             self.cpu = String::from_utf8(list.get(0).unwrap().to_vec())?;
         }
         if list.len() >= 2 {
-            self.synthesized = false;
             self.os = String::from_utf8(list.get(1).unwrap().to_vec())?;
+            // the RFC 8482 synthesized HINFO has an empty OS
+            self.synthesized = self.os.is_empty();
         }
 
-        Ok(())
+        Ok(rdata.len())
     }
 
     fn encode(
@@ -77,6 +130,9 @@ impl RDataOperation for HInfo {
         _hm: &mut CompressList,
         _is_compressed: bool,
     ) -> Result<usize, Error> {
+        if self.cpu.len() > u8::MAX as usize || self.os.len() > u8::MAX as usize {
+            return Err(anyhow!("the character-string is longer than 255"));
+        }
         raw.push(self.cpu.len() as u8);
         let encoded_cpu = self.cpu.as_bytes();
         raw.extend_from_slice(encoded_cpu);
@@ -87,3 +143,65 @@ impl RDataOperation for HInfo {
         Ok(1 + encoded_cpu.len() + 1 + encoded_os.len())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::{rdata::RDataType, CLASS_IN, RR, TYPE_HINFO};
+
+    #[test]
+    fn test_hinfo_synthesized_rfc8482() {
+        let hinfo = HInfo::synthesized_rfc8482();
+        let mut raw = vec![];
+        hinfo
+            .encode(&mut raw, &mut CompressList::new(), false)
+            .unwrap();
+        assert_eq!(b"\x07RFC8482\x00".to_vec(), raw);
+
+        let decoded = HInfo::from(&raw, &raw, 0).unwrap();
+        assert_eq!(hinfo, decoded);
+    }
+
+    #[test]
+    fn test_hinfo_presentation() {
+        let mut hinfo = HInfo::new();
+        hinfo.cpu = "INTEL-386".to_string();
+        hinfo.os = "\"UNIX\"".to_string();
+        let presentation = hinfo.to_string();
+        assert_eq!(r#""INTEL-386" "\"UNIX\"""#, presentation);
+        assert_eq!(hinfo, presentation.parse::<HInfo>().unwrap());
+
+        assert_eq!(true, "INTEL-386".parse::<HInfo>().is_err());
+    }
+
+    #[test]
+    fn test_hinfo_rdlength() {
+        let mut hinfo = HInfo::new();
+        hinfo.cpu = "INTEL-386".to_string();
+        hinfo.os = "UNIX".to_string();
+        let mut rr = RR::new();
+        rr.with_name("host.example.com")
+            .with_type(TYPE_HINFO)
+            .with_class(CLASS_IN)
+            .with_rdata(RDataType::HInfo(hinfo.clone()));
+        let mut raw = vec![];
+        rr.encode(&mut raw, &mut CompressList::new(), false)
+            .unwrap();
+
+        // name, TYPE, CLASS, TTL, then the RDLENGTH
+        let rdlength = 18 + 8;
+        let rdata = &raw[rdlength + 2..];
+        assert_eq!(b"\x09INTEL-386\x04UNIX".to_vec(), rdata);
+        assert_eq!(
+            rdata.len() as u16,
+            u16::from_be_bytes([raw[rdlength], raw[rdlength + 1]])
+        );
+        let decoded = RR::from(&raw, &mut 0, &mut false).unwrap();
+        assert_eq!(&RDataType::HInfo(hinfo.clone()), decoded.rdata());
+
+        hinfo.os = "x".repeat(256);
+        let mut raw = vec![];
+        let encoded = hinfo.encode(&mut raw, &mut CompressList::new(), false);
+        assert_eq!(true, encoded.is_err());
+    }
+}
@@ -14,30 +14,35 @@ MADNAME         A <domain-name> which specifies a host which has the
                 specified mailbox.
  */
 
-use super::{encode_domain_name_wrap, parse_domain_name_without_len, RDataOperation};
+use super::{encode_domain_name_wrap, parse_domain_name, RDataOperation, ERR_RDATE_MSG};
 use crate::dns::compress_list::CompressList;
 use anyhow::Error;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MB(pub String);
 
 impl MB {
-    pub fn from(raw: &[u8], rdata: &[u8]) -> Result<Self, Error> {
-        let mut mb = Self { 0: "".to_string() };
-        mb.decode(raw, rdata)?;
+    pub fn new() -> Self {
+        Self { 0: "".to_string() }
+    }
+
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<Self, Error> {
+        let mut mb = Self::new();
+        mb.decode(raw, rdata, rdata_start)?;
 
         Ok(mb)
     }
 }
 
 impl RDataOperation for MB {
-    fn decode(&mut self, raw: &[u8], rdata: &[u8]) -> Result<(), Error> {
-        self.0 = parse_domain_name_without_len(raw, rdata)?
+    fn decode(&mut self, raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<usize, Error> {
+        let (list, length) = parse_domain_name(raw, rdata, rdata_start, 1)?;
+        self.0 = list
             .get(0)
-            .unwrap()
+            .ok_or(Error::msg(ERR_RDATE_MSG))?
             .encode_to_str();
 
-        Ok(())
+        Ok(length)
     }
 
     fn encode(
@@ -23,30 +23,35 @@ a master file is to reject them, or to convert them to MX RRs with a
 preference of 10.
  */
 
-use super::{encode_domain_name_wrap, parse_domain_name_without_len, RDataOperation};
+use super::{encode_domain_name_wrap, parse_domain_name, RDataOperation, ERR_RDATE_MSG};
 use crate::dns::compress_list::CompressList;
 use anyhow::Error;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MF(pub String);
 
 impl MF {
-    pub fn from(raw: &[u8], rdata: &[u8]) -> Result<Self, Error> {
-        let mut mf = Self { 0: "".to_string() };
-        mf.decode(raw, rdata)?;
+    pub fn new() -> Self {
+        Self { 0: "".to_string() }
+    }
+
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<Self, Error> {
+        let mut mf = Self::new();
+        mf.decode(raw, rdata, rdata_start)?;
 
         Ok(mf)
     }
 }
 
 impl RDataOperation for MF {
-    fn decode(&mut self, raw: &[u8], rdata: &[u8]) -> Result<(), Error> {
-        self.0 = parse_domain_name_without_len(raw, rdata)?
+    fn decode(&mut self, raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<usize, Error> {
+        let (list, length) = parse_domain_name(raw, rdata, rdata_start, 1)?;
+        self.0 = list
             .get(0)
-            .unwrap()
+            .ok_or(Error::msg(ERR_RDATE_MSG))?
             .encode_to_str();
 
-        Ok(())
+        Ok(length)
     }
 
     fn encode(
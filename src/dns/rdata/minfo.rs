@@ -32,38 +32,39 @@ records can be associated with a simple mailbox, they are usually used
 with a mailing list.
  */
 
-use super::{encode_domain_name_wrap, parse_domain_name_without_len, RDataOperation};
-use crate::dns::{compress_list::CompressList, rdata::ERR_RDATE_MSG};
-use anyhow::{anyhow, Error};
+use super::{encode_domain_name_wrap, parse_domain_name, RDataOperation};
+use crate::dns::compress_list::CompressList;
+use anyhow::Error;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MInfo {
     pub rmail_bx: String,
     pub email_bx: String,
 }
 
 impl MInfo {
-    pub fn from(raw: &[u8], rdata: &[u8]) -> Result<Self, Error> {
-        let mut minfo = Self {
+    pub fn new() -> Self {
+        Self {
             rmail_bx: "".to_string(),
             email_bx: "".to_string(),
-        };
-        minfo.decode(raw, rdata)?;
+        }
+    }
+
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<Self, Error> {
+        let mut minfo = Self::new();
+        minfo.decode(raw, rdata, rdata_start)?;
 
         Ok(minfo)
     }
 }
 
 impl RDataOperation for MInfo {
-    fn decode(&mut self, raw: &[u8], rdata: &[u8]) -> Result<(), Error> {
-        let list = parse_domain_name_without_len(raw, rdata)?;
-        if list.len() < 2 {
-            return Err(anyhow!(ERR_RDATE_MSG));
-        }
+    fn decode(&mut self, raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<usize, Error> {
+        let (list, length) = parse_domain_name(raw, rdata, rdata_start, 2)?;
         self.rmail_bx = list.get(0).unwrap().encode_to_str();
         self.email_bx = list.get(1).unwrap().encode_to_str();
 
-        Ok(())
+        Ok(length)
     }
 
     fn encode(
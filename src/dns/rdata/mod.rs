@@ -13,6 +13,8 @@ length (including the length octet).
  */
 
 pub mod a;
+pub mod aaaa;
+pub mod apl;
 pub mod cname;
 pub mod hinfo;
 pub mod mb;
@@ -28,27 +30,46 @@ pub mod opt;
 pub mod ptr;
 pub mod sec;
 pub mod soa;
+pub mod srv;
 pub mod tsig;
 pub mod txt;
 pub mod wks;
 
 use self::{
-    a::A, cname::CName, hinfo::HInfo, mb::MB, md::MD, mf::MF, mg::MG, minfo::MInfo, mr::MR, mx::MX,
-    ns::NS, null::Null, opt::OPT, ptr::PTR, sec::dnskey::DNSKEY, soa::SOA, tsig::TSig, txt::TXT,
-    wks::WKS,
+    a::A, aaaa::AAAA, apl::APL, cname::CName, hinfo::HInfo, mb::MB, md::MD, mf::MF, mg::MG,
+    minfo::MInfo, mr::MR, mx::MX, ns::NS, null::Null, opt::OPT, ptr::PTR, sec::dnskey::DNSKEY,
+    sec::nsec::NSEC, sec::rrsig::RRSig, soa::SOA, srv::SRV, tsig::TSig, txt::TXT, wks::WKS,
 };
 use super::{
-    compress_list::CompressList, labels::Labels, Type, TYPE_A, TYPE_CNAME, TYPE_HINFO, TYPE_MB,
-    TYPE_MD, TYPE_MF, TYPE_MG, TYPE_MINFO, TYPE_MR, TYPE_MX, TYPE_NS, TYPE_NULL, TYPE_OPT,
-    TYPE_PTR, TYPE_SOA, TYPE_TXT, TYPE_WKS,
+    compress_list::CompressList,
+    labels::{check_label_type, check_pointer, follow_pointer, Labels, MAX_POINTER_FOLLOWS},
+    Type, TYPE_A, TYPE_AAAA, TYPE_APL, TYPE_CNAME, TYPE_HINFO, TYPE_MB, TYPE_MD, TYPE_MF, TYPE_MG,
+    TYPE_MINFO, TYPE_MR, TYPE_MX, TYPE_NS, TYPE_NULL, TYPE_OPT, TYPE_PTR, TYPE_SOA, TYPE_SRV,
+    TYPE_TSIG, TYPE_TXT, TYPE_WKS,
 };
 use crate::util;
 use anyhow::{bail, Error};
-use std::fmt::Debug;
+use std::{
+    fmt::{self, Debug},
+    net::{Ipv4Addr, Ipv6Addr},
+};
 
 const ERR_RDATE_MSG: &str = "not completed rdate";
 const ERR_RDATE_TYPE: &str = "not standard rdata type";
 
+/// the types decoded into their own [`RDataType`] variants, the others are [`RDataType::Unknown`].
+const SUPPORTED_TYPES: &[Type] = &[
+    TYPE_A, TYPE_NS, TYPE_MD, TYPE_MF, TYPE_CNAME, TYPE_SOA, TYPE_MB, TYPE_MG, TYPE_MR, TYPE_NULL,
+    TYPE_WKS, TYPE_PTR, TYPE_HINFO, TYPE_MINFO, TYPE_MX, TYPE_TXT, TYPE_AAAA, TYPE_SRV, TYPE_OPT,
+    TYPE_APL, TYPE_TSIG,
+];
+
+/// supported_types: the types the rdata can be parsed of, in the order of the type value. The rdata of
+/// the other types is kept as the opaque octets.
+pub fn supported_types() -> &'static [Type] {
+    SUPPORTED_TYPES
+}
+
 /**
    RDateOperation contains decode and encode
    decode: decode the radate that u8 slice to the concrete rdata object.
@@ -56,8 +77,11 @@ const ERR_RDATE_TYPE: &str = "not standard rdata type";
 */
 
 pub trait RDataOperation: Debug + PartialEq {
-    /// decode: decode the radate that u8 slice to the concrete rdata object.
-    fn decode(&mut self, raw: &[u8], rdata: &[u8]) -> Result<(), Error>;
+    /// decode: decode the radate that u8 slice to the concrete rdata object, the rdata starts at the
+    /// rdata_start of the raw message to check the compression pointers of the names, see
+    /// [`check_pointer`].
+    /// return the length of the rdata consumed.
+    fn decode(&mut self, raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<usize, Error>;
 
     /// encode: encode the concrete rdata object to u8 slice.
     fn encode(
@@ -71,7 +95,7 @@ pub trait RDataOperation: Debug + PartialEq {
 /**
 RDateType union all the Object that impl the RDataOperation
  */
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RDataType {
     None,
     CName(CName),
@@ -89,10 +113,19 @@ pub enum RDataType {
     SOA(SOA),
     TXT(TXT),
     A(A),
+    AAAA(AAAA),
     WKS(WKS),
     TSig(TSig),
     OPT(OPT),
     DNSKEY(DNSKEY),
+    RRSig(RRSig),
+    NSEC(NSEC),
+    APL(APL),
+    SRV(SRV),
+    /// the rdata of the type not supported, kept as opaque octets.
+    ///
+    /// ref: https://www.rfc-editor.org/rfc/rfc3597
+    Unknown(Null),
 }
 
 impl RDataType {
@@ -100,26 +133,68 @@ impl RDataType {
         RDataType::None
     }
 
-    pub fn from(raw: &[u8], _rdata: &[u8], typ: Type) -> Result<Self, Error> {
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize, typ: Type) -> Result<Self, Error> {
+        Self::parse(raw, rdata, rdata_start, typ, false)
+    }
+
+    /// from_strict: same as [`RDataType::from`], but error when the rdata decoded by the concrete type
+    /// not consume exactly the RDLENGTH octets.
+    pub fn from_strict(
+        raw: &[u8],
+        rdata: &[u8],
+        rdata_start: usize,
+        typ: Type,
+    ) -> Result<Self, Error> {
+        Self::parse(raw, rdata, rdata_start, typ, true)
+    }
+
+    fn parse(
+        raw: &[u8],
+        rdata: &[u8],
+        rdata_start: usize,
+        typ: Type,
+        strict: bool,
+    ) -> Result<Self, Error> {
+        let mut rdt = Self::of_type(typ);
+        let used = rdt.decode(raw, rdata, rdata_start)?;
+        if strict && used != rdata.len() {
+            bail!(
+                "rdlength mismatch for type {}: rdlength is {}, but consumed {}",
+                typ,
+                rdata.len(),
+                used
+            );
+        }
+
+        Ok(rdt)
+    }
+
+    /// of_type: the empty rdata of the type to decode into, [`RDataType::Unknown`] for the type not in
+    /// [`supported_types`].
+    fn of_type(typ: Type) -> Self {
         match typ {
-            TYPE_CNAME => Ok(RDataType::CName(CName::from(raw, _rdata)?)),
-            TYPE_HINFO => Ok(RDataType::HInfo(HInfo::from(raw, _rdata)?)),
-            TYPE_MB => Ok(RDataType::MB(MB::from(raw, _rdata)?)),
-            TYPE_MD => Ok(RDataType::MD(MD::from(raw, _rdata)?)),
-            TYPE_MF => Ok(RDataType::MF(MF::from(raw, _rdata)?)),
-            TYPE_MG => Ok(RDataType::MG(MG::from(raw, _rdata)?)),
-            TYPE_MINFO => Ok(RDataType::MG(MG::from(raw, _rdata)?)),
-            TYPE_MR => Ok(RDataType::MR(MR::from(raw, _rdata)?)),
-            TYPE_MX => Ok(RDataType::MX(MX::from(raw, _rdata)?)),
-            TYPE_NULL => Ok(RDataType::Null(Null::from(raw, _rdata)?)),
-            TYPE_NS => Ok(RDataType::NS(NS::from(raw, _rdata)?)),
-            TYPE_PTR => Ok(RDataType::PTR(PTR::from(raw, _rdata)?)),
-            TYPE_SOA => Ok(RDataType::SOA(SOA::from(raw, _rdata)?)),
-            TYPE_TXT => Ok(RDataType::TXT(TXT::from(raw, _rdata)?)),
-            TYPE_A => Ok(RDataType::A(A::from(raw, _rdata)?)),
-            TYPE_WKS => Ok(RDataType::WKS(WKS::from(raw, _rdata)?)),
-            TYPE_OPT => Ok(RDataType::OPT(OPT::from(raw, _rdata)?)),
-            _ => bail!(ERR_RDATE_TYPE),
+            TYPE_CNAME => RDataType::CName(CName::new()),
+            TYPE_HINFO => RDataType::HInfo(HInfo::new()),
+            TYPE_MB => RDataType::MB(MB::new()),
+            TYPE_MD => RDataType::MD(MD::new()),
+            TYPE_MF => RDataType::MF(MF::new()),
+            TYPE_MG => RDataType::MG(MG::new()),
+            TYPE_MINFO => RDataType::MG(MG::new()),
+            TYPE_MR => RDataType::MR(MR::new()),
+            TYPE_MX => RDataType::MX(MX::new()),
+            TYPE_NULL => RDataType::Null(Null::new()),
+            TYPE_NS => RDataType::NS(NS::new()),
+            TYPE_PTR => RDataType::PTR(PTR::new()),
+            TYPE_SOA => RDataType::SOA(SOA::new()),
+            TYPE_TXT => RDataType::TXT(TXT::new()),
+            TYPE_A => RDataType::A(A::new(Ipv4Addr::UNSPECIFIED)),
+            TYPE_AAAA => RDataType::AAAA(AAAA::new(Ipv6Addr::UNSPECIFIED)),
+            TYPE_WKS => RDataType::WKS(WKS::new()),
+            TYPE_OPT => RDataType::OPT(OPT::new()),
+            TYPE_TSIG => RDataType::TSig(TSig::new()),
+            TYPE_APL => RDataType::APL(APL::new()),
+            TYPE_SRV => RDataType::SRV(SRV::new()),
+            _ => RDataType::Unknown(Null::new()),
         }
     }
 
@@ -127,32 +202,172 @@ impl RDataType {
         return "";
     }
 
+    /// debug_summary: the one line summary of the rdata for the logs, the mnemonic of the type followed
+    /// by the display of the rdata, such as `A 10.0.0.1` and `MX 10 mail.example.com.`.
+    pub fn debug_summary(&self) -> String {
+        let mnemonic = match self {
+            RDataType::None => return "NONE".to_string(),
+            RDataType::CName(_) => "CNAME",
+            RDataType::HInfo(_) => "HINFO",
+            RDataType::MB(_) => "MB",
+            RDataType::MD(_) => "MD",
+            RDataType::MF(_) => "MF",
+            RDataType::MG(_) => "MG",
+            RDataType::MInfo(_) => "MINFO",
+            RDataType::MR(_) => "MR",
+            RDataType::MX(_) => "MX",
+            RDataType::Null(_) => "NULL",
+            RDataType::NS(_) => "NS",
+            RDataType::PTR(_) => "PTR",
+            RDataType::SOA(_) => "SOA",
+            RDataType::TXT(_) => "TXT",
+            RDataType::A(_) => "A",
+            RDataType::AAAA(_) => "AAAA",
+            RDataType::WKS(_) => "WKS",
+            RDataType::TSig(_) => "TSIG",
+            RDataType::OPT(_) => "OPT",
+            RDataType::DNSKEY(_) => "DNSKEY",
+            RDataType::RRSig(_) => "RRSIG",
+            RDataType::NSEC(_) => "NSEC",
+            RDataType::APL(_) => "APL",
+            RDataType::SRV(_) => "SRV",
+            RDataType::Unknown(_) => "UNKNOWN",
+        };
+
+        format!("{} {}", mnemonic, self)
+    }
+
+    /// estimated_len: the length of the rdata encoded without the compression, the rdata without
+    /// the domain names is measured by encoding it.
+    pub fn estimated_len(&self) -> usize {
+        match self {
+            RDataType::None => 0,
+            RDataType::CName(CName(name))
+            | RDataType::MB(MB(name))
+            | RDataType::MD(MD(name))
+            | RDataType::MF(MF(name))
+            | RDataType::MG(MG(name))
+            | RDataType::MR(MR(name))
+            | RDataType::NS(NS(name))
+            | RDataType::PTR(PTR(name)) => domain_name_len(name),
+            RDataType::MInfo(minfo) => {
+                domain_name_len(&minfo.rmail_bx) + domain_name_len(&minfo.email_bx)
+            }
+            RDataType::MX(mx) => 2 + domain_name_len(&mx.exchange),
+            // PRIORITY, WEIGHT, PORT and TARGET
+            RDataType::SRV(srv) => 6 + domain_name_len(&srv.target),
+            // MNAME, RNAME, and the five 32 bit fields
+            RDataType::SOA(soa) => domain_name_len(&soa.mname) + domain_name_len(&soa.rname) + 20,
+            RDataType::A(_) => 4,
+            RDataType::AAAA(_) => 16,
+            _ => {
+                let mut raw = vec![];
+                self.encode(&mut raw, &mut CompressList::new(), false)
+                    .unwrap_or(raw.len())
+            }
+        }
+    }
+
     pub fn update(&mut self, rdate: &RDataType) -> Result<(), Error> {
         Ok(())
     }
 }
 
+/**
+the presentation format of the rdata, the domain names are absolute (end with the dot). The rdata
+without a text form here is in the generic `\# <length> <hex>` form.
+
+ref: https://www.rfc-editor.org/rfc/rfc1035#section-5.1, https://www.rfc-editor.org/rfc/rfc3597#section-5
+ */
+impl fmt::Display for RDataType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let absolute = |name: &str| format!("{}.", name.trim_end_matches('.'));
+        match self {
+            RDataType::CName(CName(name))
+            | RDataType::MB(MB(name))
+            | RDataType::MD(MD(name))
+            | RDataType::MF(MF(name))
+            | RDataType::MG(MG(name))
+            | RDataType::MR(MR(name))
+            | RDataType::NS(NS(name))
+            | RDataType::PTR(PTR(name)) => write!(f, "{}", absolute(name)),
+            RDataType::MInfo(minfo) => write!(
+                f,
+                "{} {}",
+                absolute(&minfo.rmail_bx),
+                absolute(&minfo.email_bx)
+            ),
+            RDataType::MX(mx) => write!(f, "{} {}", mx.preference, absolute(&mx.exchange)),
+            RDataType::SRV(srv) => write!(
+                f,
+                "{} {} {} {}",
+                srv.priority,
+                srv.weight,
+                srv.port,
+                absolute(&srv.target)
+            ),
+            RDataType::SOA(soa) => write!(
+                f,
+                "{} {} {} {} {} {} {}",
+                absolute(&soa.mname),
+                absolute(&soa.rname),
+                soa.serial,
+                soa.refresh,
+                soa.retry,
+                soa.expire,
+                soa.minimum
+            ),
+            RDataType::HInfo(hinfo) => write!(f, "{}", hinfo),
+            RDataType::TXT(txt) => write!(f, "{}", txt),
+            RDataType::A(a) => write!(f, "{}", a.0),
+            RDataType::AAAA(aaaa) => write!(f, "{}", aaaa.0),
+            _ => {
+                let mut raw = vec![];
+                // the rdata failed to encode is commented out
+                if let Err(e) = self.encode(&mut raw, &mut CompressList::new(), false) {
+                    return write!(f, "; {}", e);
+                }
+                write!(f, "\\# {}", raw.len())?;
+                if !raw.is_empty() {
+                    write!(f, " ")?;
+                }
+                for b in raw {
+                    write!(f, "{:02x}", b)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
 impl RDataOperation for RDataType {
-    fn decode(&mut self, raw: &[u8], rdata: &[u8]) -> Result<(), Error> {
+    fn decode(&mut self, raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<usize, Error> {
         match self {
-            RDataType::CName(cname) => cname.decode(raw, rdata),
-            RDataType::HInfo(hinfo) => hinfo.decode(raw, rdata),
-            RDataType::MB(mb) => mb.decode(raw, rdata),
-            RDataType::MD(md) => md.decode(raw, rdata),
-            RDataType::MF(mf) => mf.decode(raw, rdata),
-            RDataType::MG(mg) => mg.decode(raw, rdata),
-            RDataType::MInfo(minfo) => minfo.decode(raw, rdata),
-            RDataType::MR(mr) => mr.decode(raw, rdata),
-            RDataType::MX(mx) => mx.decode(raw, rdata),
-            RDataType::Null(null) => null.decode(raw, rdata),
-            RDataType::NS(ns) => ns.decode(raw, rdata),
-            RDataType::PTR(ptr) => ptr.decode(raw, rdata),
-            RDataType::SOA(soa) => soa.decode(raw, rdata),
-            RDataType::TXT(txt) => txt.decode(raw, rdata),
-            RDataType::A(a) => a.decode(raw, rdata),
-            RDataType::WKS(wks) => wks.decode(raw, rdata),
-            RDataType::TSig(tsig) => tsig.decode(raw, rdata),
-            RDataType::OPT(opt) => opt.decode(raw, rdata),
+            RDataType::CName(cname) => cname.decode(raw, rdata, rdata_start),
+            RDataType::HInfo(hinfo) => hinfo.decode(raw, rdata, rdata_start),
+            RDataType::MB(mb) => mb.decode(raw, rdata, rdata_start),
+            RDataType::MD(md) => md.decode(raw, rdata, rdata_start),
+            RDataType::MF(mf) => mf.decode(raw, rdata, rdata_start),
+            RDataType::MG(mg) => mg.decode(raw, rdata, rdata_start),
+            RDataType::MInfo(minfo) => minfo.decode(raw, rdata, rdata_start),
+            RDataType::MR(mr) => mr.decode(raw, rdata, rdata_start),
+            RDataType::MX(mx) => mx.decode(raw, rdata, rdata_start),
+            RDataType::Null(null) => null.decode(raw, rdata, rdata_start),
+            RDataType::NS(ns) => ns.decode(raw, rdata, rdata_start),
+            RDataType::PTR(ptr) => ptr.decode(raw, rdata, rdata_start),
+            RDataType::SOA(soa) => soa.decode(raw, rdata, rdata_start),
+            RDataType::TXT(txt) => txt.decode(raw, rdata, rdata_start),
+            RDataType::A(a) => a.decode(raw, rdata, rdata_start),
+            RDataType::AAAA(aaaa) => aaaa.decode(raw, rdata, rdata_start),
+            RDataType::WKS(wks) => wks.decode(raw, rdata, rdata_start),
+            RDataType::TSig(tsig) => tsig.decode(raw, rdata, rdata_start),
+            RDataType::OPT(opt) => opt.decode(raw, rdata, rdata_start),
+            RDataType::RRSig(rrsig) => rrsig.decode(raw, rdata, rdata_start),
+            RDataType::NSEC(nsec) => nsec.decode(raw, rdata, rdata_start),
+            RDataType::APL(apl) => apl.decode(raw, rdata, rdata_start),
+            RDataType::SRV(srv) => srv.decode(raw, rdata, rdata_start),
+            RDataType::Unknown(unknown) => unknown.decode(raw, rdata, rdata_start),
             _ => bail!(ERR_RDATE_TYPE),
         }
     }
@@ -179,40 +394,154 @@ impl RDataOperation for RDataType {
             RDataType::SOA(soa) => soa.encode(raw, cl, is_compressed),
             RDataType::TXT(txt) => txt.encode(raw, cl, is_compressed),
             RDataType::A(a) => a.encode(raw, cl, is_compressed),
+            RDataType::AAAA(aaaa) => aaaa.encode(raw, cl, is_compressed),
             RDataType::WKS(wks) => wks.encode(raw, cl, is_compressed),
             RDataType::TSig(tsig) => tsig.encode(raw, cl, is_compressed),
             RDataType::OPT(opt) => opt.encode(raw, cl, is_compressed),
+            RDataType::RRSig(rrsig) => rrsig.encode(raw, cl, is_compressed),
+            RDataType::NSEC(nsec) => nsec.encode(raw, cl, is_compressed),
+            RDataType::APL(apl) => apl.encode(raw, cl, is_compressed),
+            RDataType::SRV(srv) => srv.encode(raw, cl, is_compressed),
+            RDataType::Unknown(unknown) => unknown.encode(raw, cl, is_compressed),
             _ => bail!(ERR_RDATE_TYPE),
         }
     }
 }
 
+/// parse the <character-string>s, each of them is a single length octet followed by that number of characters.
 pub fn parse_charactor_string(_rdata: &[u8]) -> Result<Vec<Vec<u8>>, Error> {
-    let mut iter: std::slice::Iter<'_, u8> = _rdata.iter();
-    let mut next = iter.next();
     let mut start = 0_usize;
     let mut list = vec![];
-    while next.is_some() {
-        let length = *next.unwrap() as usize;
-        if length == 0 {
-            return Ok(list);
-        }
+    while start < _rdata.len() {
+        let length = _rdata[start] as usize;
         start += 1;
         if start + length > _rdata.len() {
             return Err(Error::msg("not completed charactor string"));
         }
         list.push(_rdata[start..start + length].to_vec());
-        next = iter.clone().skip(length).next();
+        start += length;
     }
     return Ok(list);
 }
 
+/**
+character_string_to_presentation: the presentation format of the <character-string>, it is quoted,
+the `"` and `\\` are escaped by a backslash, and the non-printable octets are escaped as `\ddd`.
+
+ref: https://www.rfc-editor.org/rfc/rfc1035#section-5.1
+ */
+pub fn character_string_to_presentation(s: &[u8]) -> String {
+    let mut result = String::with_capacity(s.len() + 2);
+    result.push('"');
+    for &b in s {
+        match b {
+            b'"' | b'\\' => {
+                result.push('\\');
+                result.push(b as char);
+            }
+            0x20..=0x7e => result.push(b as char),
+            _ => result.push_str(&format!("\\{:03}", b)),
+        }
+    }
+    result.push('"');
+
+    result
+}
+
+/// character_string_from_presentation: the inverse of [`character_string_to_presentation`], the
+/// surrounding quotes are optional.
+pub fn character_string_from_presentation(s: &str) -> Result<Vec<u8>, Error> {
+    let s = match s.len() >= 2 && s.starts_with('"') && s.ends_with('"') {
+        true => &s[1..s.len() - 1],
+        false => s,
+    };
+    let bts = s.as_bytes();
+    let mut result = Vec::with_capacity(bts.len());
+    let mut i = 0;
+    while i < bts.len() {
+        if bts[i] != b'\\' {
+            result.push(bts[i]);
+            i += 1;
+            continue;
+        }
+        match bts.get(i + 1..i + 4) {
+            Some(ddd) if ddd.iter().all(|d| d.is_ascii_digit()) => {
+                let v = ddd.iter().fold(0_u32, |v, d| v * 10 + (d - b'0') as u32);
+                if v > u8::MAX as u32 {
+                    bail!("the escaped \\ddd is larger than 255: {}", s);
+                }
+                result.push(v as u8);
+                i += 4;
+            }
+            _ => match bts.get(i + 1) {
+                Some(c) if !c.is_ascii_digit() => {
+                    result.push(*c);
+                    i += 2;
+                }
+                _ => bail!("invalid escape in the character-string: {}", s),
+            },
+        }
+    }
+
+    Ok(result)
+}
+
+/// split the presentation of the <character-string>s by the whitespaces, a quoted one may contain
+/// the whitespaces, each of them is decoded by [`character_string_from_presentation`].
+pub fn character_strings_from_presentation(s: &str) -> Result<Vec<Vec<u8>>, Error> {
+    let bts = s.as_bytes();
+    let mut list = vec![];
+    let mut i = 0;
+    while i < bts.len() {
+        if bts[i].is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let quoted = bts[i] == b'"';
+        if quoted {
+            i += 1;
+        }
+        loop {
+            match bts.get(i) {
+                None if quoted => bail!("unterminated quoted character-string: {}", s),
+                None => break,
+                Some(b'\\') => i += 2,
+                Some(b'"') if quoted => {
+                    i += 1;
+                    break;
+                }
+                Some(c) if !quoted && c.is_ascii_whitespace() => break,
+                Some(_) => i += 1,
+            }
+        }
+        list.push(character_string_from_presentation(
+            &s[start..i.min(bts.len())],
+        )?);
+    }
+
+    Ok(list)
+}
+
 ///  all domain names in the RDATA section of these RRs may be compressed, so we will check weather it compressed.
-pub fn parse_domain_name(raw: &[u8], rdata: &[u8]) -> Result<(Vec<Labels>, usize), Error> {
+/// A name follows at most [`MAX_POINTER_FOLLOWS`] compression pointers, the longer chain is an error,
+/// and each pointer must point backward, see [`check_pointer`]. The rdata starts at the rdata_start of
+/// the raw, and holds exactly the count names, the missing name or the trailing octet is an error.
+pub fn parse_domain_name(
+    raw: &[u8],
+    rdata: &[u8],
+    rdata_start: usize,
+    count: usize,
+) -> Result<(Vec<Labels>, usize), Error> {
     let mut list = vec![];
     let mut offset = 0;
-    while offset < rdata.len() {
+    for _ in 0..count {
+        if offset >= rdata.len() {
+            return Err(Error::msg(ERR_RDATE_MSG));
+        }
         let mut labels = Labels::new();
+        let mut budget = MAX_POINTER_FOLLOWS;
+        let name_start = rdata_start + offset;
         loop {
             if rdata[offset] == b'\x00' {
                 offset += 1;
@@ -221,11 +550,21 @@ pub fn parse_domain_name(raw: &[u8], rdata: &[u8]) -> Result<(Vec<Labels>, usize
             let (mut compressed_offset, is_compressed) = util::is_compressed_wrap(&rdata[offset..]);
             if is_compressed {
                 offset += 2;
-                labels.extend(Labels::parse(raw, &mut compressed_offset)?);
+                check_pointer(compressed_offset, name_start)?;
+                follow_pointer(&mut budget)?;
+                labels.extend(Labels::parse_with_budget(
+                    raw,
+                    &mut compressed_offset,
+                    &mut budget,
+                )?);
                 break;
             } else {
                 let len = rdata[offset];
+                check_label_type(len)?;
                 let start = offset + 1;
+                if start + len as usize > rdata.len() {
+                    return Err(Error::msg(ERR_RDATE_MSG));
+                }
                 labels.extend(Labels::from(
                     String::from_utf8(rdata[start..start + len as usize].to_vec())?.as_str(),
                 )?);
@@ -235,32 +574,42 @@ pub fn parse_domain_name(raw: &[u8], rdata: &[u8]) -> Result<(Vec<Labels>, usize
                 return Err(Error::msg(ERR_RDATE_MSG));
             }
         }
+        labels.check_len()?;
         list.push(labels)
     }
+    if offset != rdata.len() {
+        return Err(Error::msg(ERR_RDATE_MSG));
+    }
 
     Ok((list, offset))
 }
 
-pub fn parse_domain_name_without_len(raw: &[u8], rdata: &[u8]) -> Result<Vec<Labels>, Error> {
-    let (list, _) = parse_domain_name(raw, rdata)?;
+/// parse_domain_name_without_len: the count domain names in the rdata, same as
+/// [`parse_domain_name`] without the consumed length, for the names followed by the fixed fields, such as SOA.
+pub fn parse_domain_name_without_len(
+    raw: &[u8],
+    rdata: &[u8],
+    rdata_start: usize,
+    count: usize,
+) -> Result<Vec<Labels>, Error> {
+    let (list, _) = parse_domain_name(raw, rdata, rdata_start, count)?;
 
     Ok(list)
 }
 
-/// encode domain name
-pub fn encode_domain_name(domain_name: &str) -> Vec<u8> {
-    let mut r: Vec<u8> = vec![];
-
-    let mut names = domain_name.split(".").into_iter();
-    let mut iter = names.next();
-    while iter.is_some() && iter.as_ref().unwrap().len() != 0 {
-        r.push(iter.unwrap().len() as u8);
-        r.extend_from_slice(&iter.unwrap().as_bytes().to_vec());
-        iter = names.next();
-    }
-    r.push(b'\x00');
+/// encode domain name, see [`Labels::to_wire`].
+pub fn encode_domain_name(domain_name: &str) -> Result<Vec<u8>, Error> {
+    Labels::from_domain_name(domain_name).to_wire()
+}
 
-    r
+/// domain_name_len: the length of the domain name encoded by [`encode_domain_name`].
+pub fn domain_name_len(domain_name: &str) -> usize {
+    domain_name
+        .split(".")
+        .filter(|label| label.len() != 0)
+        .map(|label| 1 + label.len())
+        .sum::<usize>()
+        + 1
 }
 
 pub fn encode_domain_name_wrap(
@@ -270,8 +619,10 @@ pub fn encode_domain_name_wrap(
     raw_offset: usize,
 ) -> Result<Vec<u8>, Error> {
     if !is_compressed {
-        return Ok(encode_domain_name(domain_name));
+        return encode_domain_name(domain_name);
     }
+    // the compressed name is checked as the uncompressed one, see [`Labels::check_len`]
+    Labels::from_domain_name(domain_name).check_len()?;
     let encode = |domain: &str| -> Vec<u8> {
         let mut r: Vec<u8> = vec![];
         let mut names = domain.split(".").into_iter();
@@ -377,7 +728,7 @@ pub fn encode_domain_name_wrap(
     // update the exist domain_name in CompressList
     cl.push(domain_name, raw_offset);
 
-    Ok(encode_domain_name(domain_name))
+    encode_domain_name(domain_name)
 }
 // /**
 // RDate define the RDate structure
@@ -434,6 +785,36 @@ pub fn encode_domain_name_wrap(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dns::{labels::pointer_chain, CLASS_IN, RR};
+    use crate::DNS;
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn test_character_string_presentation() {
+        let raw = b"say \"hi\" \\ \x07".to_vec();
+        let presentation = character_string_to_presentation(&raw);
+        assert_eq!(r#""say \"hi\" \\ \007""#, presentation);
+        assert_eq!(
+            raw,
+            character_string_from_presentation(&presentation).unwrap()
+        );
+        assert_eq!(
+            b"abc".to_vec(),
+            character_string_from_presentation("abc").unwrap()
+        );
+
+        assert_eq!(true, character_string_from_presentation("\\256").is_err());
+        assert_eq!(true, character_string_from_presentation("abc\\").is_err());
+
+        assert_eq!(
+            vec![b"a b".to_vec(), b"c\"".to_vec(), b"d".to_vec()],
+            character_strings_from_presentation(r#""a b" "c\"" d"#).unwrap()
+        );
+        assert_eq!(
+            true,
+            character_strings_from_presentation(r#""a b"#).is_err()
+        );
+    }
 
     #[test]
     fn test_parse_domain_name_without_raw() {
@@ -446,7 +827,7 @@ mod tests {
         ];
 
         for rdata in rdatas {
-            let labels = parse_domain_name(&[], rdata.0);
+            let labels = parse_domain_name(&[], rdata.0, 0, 2);
             assert_eq!(rdata.1, labels.is_ok());
             if labels.is_ok() {
                 println!("labels = {:?}", labels);
@@ -454,10 +835,246 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_domain_name_without_len() {
+        // the second name is compressed to the "example.com" of the raw after the header
+        let raw = [
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 7, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 3,
+            b'c', b'o', b'm', 0,
+        ];
+        let rdata = [
+            4, b'm', b'a', b'i', b'l', 0, 5, b'a', b'd', b'm', b'i', b'n', 0xC0, 12,
+        ];
+        let list = parse_domain_name_without_len(&raw, &rdata, raw.len(), 2).unwrap();
+        assert_eq!(2, list.len());
+        assert_eq!("mail", list[0].encode_to_str());
+        assert_eq!("admin.example.com", list[1].encode_to_str());
+
+        assert_eq!(
+            true,
+            parse_domain_name_without_len(&raw, &rdata[..4], raw.len(), 2).is_err()
+        );
+        // the missing name and the trailing names
+        assert_eq!(
+            true,
+            parse_domain_name_without_len(&raw, &rdata[..6], raw.len(), 2).is_err()
+        );
+        assert_eq!(
+            true,
+            parse_domain_name_without_len(&raw, &rdata, raw.len(), 1).is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_domain_name_trailing_octet() {
+        let rdata = [4, b'm', b'a', b'i', b'l', 0, 0];
+        assert_eq!(true, parse_domain_name(&[], &rdata, 0, 1).is_err());
+        let (list, length) = parse_domain_name(&[], &rdata[..6], 0, 1).unwrap();
+        assert_eq!("mail", list[0].encode_to_str());
+        assert_eq!(6, length);
+
+        // the trailing octet after the names of the CNAME, the MX and the SOA
+        let name = &rdata[..6];
+        for (typ, names, fixed) in [
+            (TYPE_CNAME, name.to_vec(), vec![]),
+            (TYPE_MX, [&[0, 10], name].concat(), vec![]),
+            (TYPE_SOA, [name, name].concat(), vec![0; 20]),
+        ] {
+            let valid = [&names[..], &fixed[..]].concat();
+            assert_eq!(true, RDataType::from(&[], &valid, 0, typ).is_ok());
+            let trailing = [&names[..], &[0], &fixed[..]].concat();
+            assert_eq!(true, RDataType::from(&[], &trailing, 0, typ).is_err());
+        }
+    }
+
+    #[test]
+    fn test_empty_rdata() {
+        // the single-name types error cleanly
+        for typ in [
+            TYPE_CNAME, TYPE_NS, TYPE_PTR, TYPE_MB, TYPE_MD, TYPE_MF, TYPE_MG, TYPE_MR, TYPE_MX,
+        ] {
+            assert_eq!(true, RDataType::from(&[], &[], 0, typ).is_err());
+        }
+        assert_eq!(
+            ERR_RDATE_MSG,
+            RDataType::from(&[], &[], 0, TYPE_CNAME)
+                .unwrap_err()
+                .to_string()
+        );
+
+        // the opaque types accept zero length and roundtrip
+        for (typ, expect) in [
+            (TYPE_NULL, RDataType::Null(Null::new())),
+            (65280, RDataType::Unknown(Null::new())),
+        ] {
+            let rdt = RDataType::from_strict(&[], &[], 0, typ).unwrap();
+            assert_eq!(expect, rdt);
+            let mut raw = vec![];
+            assert_eq!(
+                0,
+                rdt.encode(&mut raw, &mut CompressList::new(), false)
+                    .unwrap()
+            );
+        }
+    }
+
     #[test]
     fn test_encode_domain_name() {
-        println!("rr={:?}", encode_domain_name(""));
-        println!("rr={:?}", encode_domain_name("com"));
-        println!("rr={:?}", encode_domain_name("baidu.com"));
+        println!("rr={:?}", encode_domain_name("").unwrap());
+        println!("rr={:?}", encode_domain_name("com").unwrap());
+        println!("rr={:?}", encode_domain_name("baidu.com").unwrap());
+    }
+
+    #[test]
+    fn test_encode_domain_name_wrap_len() {
+        let long_label = format!("{}.example.com", "a".repeat(64));
+        let long_name = format!("{}.example.com", vec!["a".repeat(63); 4].join("."));
+        for is_compressed in [false, true] {
+            // the suffix "example.com" is compressible after the first name
+            let mut cl = CompressList::new();
+            encode_domain_name_wrap("example.com", &mut cl, is_compressed, 12).unwrap();
+            for name in [&long_label, &long_name] {
+                assert_eq!(
+                    true,
+                    encode_domain_name_wrap(name, &mut cl, is_compressed, 30).is_err()
+                );
+            }
+            let name = format!("{}.example.com", "a".repeat(63));
+            assert_eq!(
+                true,
+                encode_domain_name_wrap(&name, &mut cl, is_compressed, 30).is_ok()
+            );
+        }
+    }
+
+    #[test]
+    fn test_mailbox_names_compression() {
+        let cases: [(Type, fn(String) -> RDataType); 5] = [
+            (TYPE_MB, |name| RDataType::MB(MB(name))),
+            (TYPE_MD, |name| RDataType::MD(MD(name))),
+            (TYPE_MF, |name| RDataType::MF(MF(name))),
+            (TYPE_MG, |name| RDataType::MG(MG(name))),
+            (TYPE_MR, |name| RDataType::MR(MR(name))),
+        ];
+        for (typ, new) in cases {
+            let mut dns = DNS::new();
+            for name in ["mail1.example.com", "mail2.example.com"] {
+                let mut rr = RR::new();
+                rr.with_name("example.com")
+                    .with_type(typ)
+                    .with_class(CLASS_IN)
+                    .with_ttl(60)
+                    .with_rdata(new(name.to_string()));
+                dns.add_answer_rr(Rc::new(RefCell::new(rr)));
+            }
+            let raw = dns.encode(true).unwrap();
+
+            // the second rdata is the label "mail2" followed by the pointer to "example.com"
+            let (rdlength, rdata) = raw[raw.len() - 10..].split_at(2);
+            assert_eq!(&[0, 8], rdlength);
+            assert_eq!(b"\x05mail2", &rdata[..6]);
+            assert_eq!(0xC0, rdata[6] & 0xC0);
+
+            let decoded = DNS::from(&raw).unwrap();
+            assert_eq!(
+                &new("mail2.example.com".to_string()),
+                decoded.answers().0[1].borrow().rdata()
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_domain_name_pointer_chain() {
+        let raw = pointer_chain(20);
+        let start = raw.len() - 2;
+        assert_eq!(
+            true,
+            parse_domain_name(&raw, &raw[start..], start, 1).is_err()
+        );
+
+        // the pointer of the rdata counts, so 15 pointers in the raw are followed
+        let start = 15 + 14 * 2;
+        let (list, length) = parse_domain_name(&raw, &raw[start..start + 2], start, 1).unwrap();
+        assert_eq!("a", list[0].encode_to_str());
+        assert_eq!(2, length);
+    }
+
+    #[test]
+    fn test_parse_domain_name_pointer_backward() {
+        // the name "a" after the header, then the rdatas of the pointer to it and the pointer to itself
+        let mut raw = vec![0; 12];
+        raw.extend([1, b'a', 0, 0xC0, 12, 0xC0, 17]);
+        let (list, _) = parse_domain_name(&raw, &raw[15..17], 15, 1).unwrap();
+        assert_eq!("a", list[0].encode_to_str());
+        assert_eq!(true, parse_domain_name(&raw, &raw[17..], 17, 1).is_err());
+
+        // the pointer into the header
+        assert_eq!(
+            true,
+            parse_domain_name(&raw, &[0xC0, 0], raw.len(), 1).is_err()
+        );
+        // the pointer at the offset 12 points to itself, in the raw or followed from the detached rdata
+        let mut self_pointer = vec![0; 12];
+        self_pointer.extend([0xC0, 12]);
+        for (rdata, start) in [(&self_pointer[12..], 12), (&[0xC0, 12], self_pointer.len())] {
+            let err = parse_domain_name(&self_pointer, rdata, start, 1).unwrap_err();
+            assert_eq!(
+                true,
+                err.to_string().starts_with("compression pointer loop")
+            );
+        }
+        // the extended label type is not a pointer
+        assert_eq!(
+            true,
+            parse_domain_name(&raw, &[0x41, b'a', 0], raw.len(), 1).is_err()
+        );
+    }
+
+    #[test]
+    fn test_rdata_display() {
+        assert_eq!(
+            "ns.example.com.",
+            RDataType::NS(NS("ns.example.com".to_string())).to_string()
+        );
+        let mut soa = SOA::new();
+        soa.mname = "ns.example.com.".to_string();
+        soa.rname = "admin.example.com".to_string();
+        soa.serial = 1;
+        assert_eq!(
+            "ns.example.com. admin.example.com. 1 0 0 0 0",
+            RDataType::SOA(soa).to_string()
+        );
+        assert_eq!(
+            "\\# 2 abcd",
+            RDataType::Unknown(Null::from(&[], &[0xab, 0xcd], 0).unwrap()).to_string()
+        );
+        assert_eq!("\\# 0", RDataType::Null(Null::new()).to_string());
+    }
+
+    #[test]
+    fn test_supported_types() {
+        let types = supported_types();
+        for typ in [TYPE_A, TYPE_SOA, TYPE_AAAA] {
+            assert_eq!(true, types.contains(&typ));
+        }
+        for typ in 0..=u16::MAX {
+            let unknown = RDataType::of_type(typ) == RDataType::Unknown(Null::new());
+            assert_eq!(!unknown, types.contains(&typ), "type {}", typ);
+        }
+    }
+
+    #[test]
+    fn test_rdata_debug_summary() {
+        let rdata = RDataType::A(A::new(Ipv4Addr::new(10, 0, 0, 1)));
+        assert_eq!("A 10.0.0.1", rdata.debug_summary());
+
+        let mut mx = MX::new();
+        mx.preference = 10;
+        mx.exchange = "mail.example.com".to_string();
+        assert_eq!("MX 10 mail.example.com.", RDataType::MX(mx).debug_summary());
+        assert_eq!(
+            "UNKNOWN \\# 2 abcd",
+            RDataType::Unknown(Null::from(&[], &[0xab, 0xcd], 0).unwrap()).debug_summary()
+        );
     }
 }
@@ -24,37 +24,45 @@ specified by EXCHANGE.  The use of MX RRs is explained in detail in
 [RFC-974].
  */
 
-use super::{encode_domain_name_wrap, parse_domain_name_without_len, RDataOperation};
+use super::{encode_domain_name_wrap, parse_domain_name, RDataOperation, ERR_RDATE_MSG};
 use crate::dns::compress_list::CompressList;
 use anyhow::Error;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MX {
     pub preference: u16,
     pub exchange: String,
 }
 
 impl MX {
-    pub fn from(raw: &[u8], rdata: &[u8]) -> Result<Self, Error> {
-        let mut mr = Self {
+    pub fn new() -> Self {
+        Self {
             preference: 0,
             exchange: "".to_string(),
-        };
-        mr.decode(raw, rdata)?;
+        }
+    }
+
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<Self, Error> {
+        let mut mr = Self::new();
+        mr.decode(raw, rdata, rdata_start)?;
 
         Ok(mr)
     }
 }
 
 impl RDataOperation for MX {
-    fn decode(&mut self, raw: &[u8], rdata: &[u8]) -> Result<(), Error> {
+    fn decode(&mut self, raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<usize, Error> {
+        if rdata.len() < 2 {
+            return Err(Error::msg(ERR_RDATE_MSG));
+        }
         self.preference = u16::from_be_bytes(rdata[..2].try_into().expect("get preference failed"));
-        self.exchange = parse_domain_name_without_len(raw, &rdata[2..])?
+        let (list, length) = parse_domain_name(raw, &rdata[2..], rdata_start + 2, 1)?;
+        self.exchange = list
             .get(0)
-            .unwrap()
+            .ok_or(Error::msg(ERR_RDATE_MSG))?
             .encode_to_str();
 
-        Ok(())
+        Ok(2 + length)
     }
 
     fn encode(
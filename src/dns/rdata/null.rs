@@ -20,23 +20,27 @@ use super::RDataOperation;
 use crate::dns::compress_list::CompressList;
 use anyhow::Error;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Null(Vec<u8>);
 
 impl Null {
-    pub fn from(raw: &[u8], rdata: &[u8]) -> Result<Self, Error> {
-        let mut mr = Self { 0: vec![] };
-        mr.decode(raw, rdata)?;
+    pub fn new() -> Self {
+        Self { 0: vec![] }
+    }
+
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<Self, Error> {
+        let mut mr = Self::new();
+        mr.decode(raw, rdata, rdata_start)?;
 
         Ok(mr)
     }
 }
 
 impl RDataOperation for Null {
-    fn decode(&mut self, _raw: &[u8], rdata: &[u8]) -> Result<(), Error> {
+    fn decode(&mut self, _raw: &[u8], rdata: &[u8], _rdata_start: usize) -> Result<usize, Error> {
         self.0 = rdata.to_vec();
 
-        Ok(())
+        Ok(rdata.len())
     }
 
     fn encode(
@@ -21,6 +21,12 @@ structured as zero or more of the following:
    OPTION-LENGTH  Size (in octets) of OPTION-DATA.
 
    OPTION-DATA    Varies per OPTION-CODE.
+
+# Repeated options
+An option code may appear more than once in the OPT, such as the multiple EDE options each of them
+reports an error (ref: https://www.rfc-editor.org/rfc/rfc8914#section-2). The codes in
+[`SINGLE_OPTION_CODES`] must appear at most once, the OPT with more than one COOKIE option is malformed
+(ref: https://www.rfc-editor.org/rfc/rfc7873#section-5.2).
  */
 
 use super::RDataOperation;
@@ -30,36 +36,108 @@ use anyhow::anyhow;
 use anyhow::Error;
 use anyhow::Ok;
 
-#[derive(Debug, PartialEq, Eq)]
-pub struct OPT {
+/// DNSSEC Algorithm Understood, ref: https://www.rfc-editor.org/rfc/rfc6975#section-3
+pub const EDNS_OPTION_DAU: u16 = 5;
+
+/// DS Hash Understood
+pub const EDNS_OPTION_DHU: u16 = 6;
+
+/// NSEC3 Hash Understood
+pub const EDNS_OPTION_N3U: u16 = 7;
+
+/// DNS Cookies, ref: https://www.rfc-editor.org/rfc/rfc7873#section-4
+pub const EDNS_OPTION_COOKIE: u16 = 10;
+
+/// Extended DNS Errors, ref: https://www.rfc-editor.org/rfc/rfc8914#section-2
+pub const EDNS_OPTION_EDE: u16 = 15;
+
+/// the option codes appear at most once in an OPT, the others can be repeated.
+pub const SINGLE_OPTION_CODES: [u16; 1] = [EDNS_OPTION_COOKIE];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptOption {
     pub code: u16,
     pub length: u16,
     pub data: Vec<u8>,
 }
 
+impl OptOption {
+    pub fn new(code: u16, data: &[u8]) -> Self {
+        Self {
+            code,
+            length: data.len() as u16,
+            data: data.to_vec(),
+        }
+    }
+}
+
+/// OPT: zero or more options
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OPT {
+    pub options: Vec<OptOption>,
+}
+
 impl OPT {
-    pub fn from(raw: &[u8], rdata: &[u8]) -> Result<Self, Error> {
-        let mut opt = Self {
-            code: 0,
-            length: 0,
-            data: Vec::new(),
-        };
-        opt.decode(raw, rdata)?;
+    pub fn new() -> Self {
+        Self {
+            options: Vec::new(),
+        }
+    }
+
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<Self, Error> {
+        let mut opt = Self::new();
+        opt.decode(raw, rdata, rdata_start)?;
 
         Ok(opt)
     }
+
+    /// get the data of the first option with the code.
+    pub fn option(&self, code: u16) -> Option<&[u8]> {
+        self.options
+            .iter()
+            .find(|opt| opt.code == code)
+            .map(|opt| opt.data.as_slice())
+    }
+
+    /// options_by_code: all the options with the code in order, such as the repeated EDE options.
+    pub fn options_by_code(&self, code: u16) -> Vec<&OptOption> {
+        self.options.iter().filter(|opt| opt.code == code).collect()
+    }
+
+    /// set the option, the options with the same code will be replaced.
+    pub fn with_option(&mut self, code: u16, data: &[u8]) -> &mut Self {
+        self.options.retain(|opt| opt.code != code);
+        self.options.push(OptOption::new(code, data));
+        return self;
+    }
 }
 
 impl RDataOperation for OPT {
-    fn decode(&mut self, _raw: &[u8], rdata: &[u8]) -> Result<(), Error> {
-        if rdata.len() < 4 {
-            return Err(anyhow!(ERR_RDATE_MSG));
+    fn decode(&mut self, _raw: &[u8], rdata: &[u8], _rdata_start: usize) -> Result<usize, Error> {
+        self.options.clear();
+        let mut offset = 0;
+        while offset < rdata.len() {
+            if rdata.len() < offset + 4 {
+                return Err(anyhow!(ERR_RDATE_MSG));
+            }
+            let code = u16::from_be_bytes(rdata[offset..offset + 2].try_into().unwrap());
+            let length = u16::from_be_bytes(rdata[offset + 2..offset + 4].try_into().unwrap());
+            offset += 4;
+            if rdata.len() < offset + length as usize {
+                return Err(anyhow!(ERR_RDATE_MSG));
+            }
+            if SINGLE_OPTION_CODES.contains(&code) && self.option(code).is_some() {
+                return Err(anyhow!("the option {} is repeated in the OPT", code));
+            }
+            self.options.push(OptOption {
+                code,
+                length,
+                data: rdata[offset..offset + length as usize].to_vec(),
+            });
+            offset += length as usize;
         }
-        self.code = u16::from_be_bytes(rdata[..2].try_into().unwrap());
-        self.length = u16::from_be_bytes(rdata[2..4].try_into().unwrap());
-        self.data = rdata[4..].to_vec();
 
-        Ok(())
+        Ok(offset)
     }
 
     fn encode(
@@ -68,10 +146,55 @@ impl RDataOperation for OPT {
         _hm: &mut CompressList,
         _is_compressed: bool,
     ) -> Result<usize, Error> {
-        raw.extend(self.code.to_be_bytes());
-        raw.extend(self.length.to_be_bytes());
-        raw.extend(&self.data);
+        let mut length = 0;
+        for opt in &self.options {
+            raw.extend(opt.code.to_be_bytes());
+            raw.extend((opt.data.len() as u16).to_be_bytes());
+            raw.extend(&opt.data);
+            length += 2 + 2 + opt.data.len();
+        }
+
+        Ok(length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opt_decode_encode() {
+        let rdata = [0, 5, 0, 3, 8, 13, 15, 0, 10, 0, 0];
+        let opt = OPT::from(&[], &rdata, 0).unwrap();
+        assert_eq!(2, opt.options.len());
+        assert_eq!(Some(&[8u8, 13, 15][..]), opt.option(EDNS_OPTION_DAU));
+        assert_eq!(Some(&[][..]), opt.option(10));
+
+        let mut raw = vec![];
+        opt.encode(&mut raw, &mut CompressList::new(), false)
+            .unwrap();
+        assert_eq!(rdata.to_vec(), raw);
+
+        assert_eq!(true, OPT::from(&[], &[], 0).unwrap().options.is_empty());
+        assert_eq!(true, OPT::from(&[], &[0, 5, 0, 3, 8], 0).is_err());
+    }
+
+    #[test]
+    fn test_opt_repeated_options() {
+        let mut rdata = vec![];
+        rdata.extend([0, 15, 0, 2, 0, 6]);
+        rdata.extend([0, 10, 0, 8, 1, 2, 3, 4, 5, 6, 7, 8]);
+        rdata.extend([0, 15, 0, 6, 0, 9, b'k', b'e', b'y', b's']);
+        let opt = OPT::from(&[], &rdata, 0).unwrap();
+        let ede = opt.options_by_code(EDNS_OPTION_EDE);
+        assert_eq!(2, ede.len());
+        assert_eq!(vec![0, 6], ede[0].data);
+        assert_eq!(vec![0, 9, b'k', b'e', b'y', b's'], ede[1].data);
+        assert_eq!(1, opt.options_by_code(EDNS_OPTION_COOKIE).len());
+        assert_eq!(true, opt.options_by_code(EDNS_OPTION_DAU).is_empty());
 
-        Ok(2 + 2 + self.data.len())
+        // the repeated COOKIE is malformed
+        rdata.extend([0, 10, 0, 8, 1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(true, OPT::from(&[], &rdata, 0).is_err());
     }
 }
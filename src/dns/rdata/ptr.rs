@@ -19,30 +19,45 @@ similar to that performed by CNAME, which identifies aliases.  See the
 description of the IN-ADDR.ARPA domain for an example.
  */
 
-use super::{encode_domain_name_wrap, parse_domain_name_without_len, RDataOperation};
+use super::{encode_domain_name_wrap, parse_domain_name, RDataOperation, ERR_RDATE_MSG};
 use crate::dns::compress_list::CompressList;
 use anyhow::Error;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PTR(pub String);
 
 impl PTR {
-    pub fn from(raw: &[u8], rdata: &[u8]) -> Result<Self, Error> {
-        let mut cname = Self { 0: "".to_string() };
-        cname.decode(raw, rdata)?;
+    pub fn new() -> Self {
+        Self { 0: "".to_string() }
+    }
+
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<Self, Error> {
+        let mut cname = Self::new();
+        cname.decode(raw, rdata, rdata_start)?;
 
         Ok(cname)
     }
+
+    /// the domain name which the PTR point to.
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+
+    pub fn with_name(&mut self, name: &str) -> &mut Self {
+        self.0 = name.trim_end_matches('.').to_string();
+        return self;
+    }
 }
 
 impl RDataOperation for PTR {
-    fn decode(&mut self, raw: &[u8], rdata: &[u8]) -> Result<(), Error> {
-        self.0 = parse_domain_name_without_len(raw, rdata)?
+    fn decode(&mut self, raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<usize, Error> {
+        let (list, length) = parse_domain_name(raw, rdata, rdata_start, 1)?;
+        self.0 = list
             .get(0)
-            .unwrap()
+            .ok_or(Error::msg(ERR_RDATE_MSG))?
             .encode_to_str();
 
-        Ok(())
+        Ok(length)
     }
 
     fn encode(
@@ -57,3 +72,34 @@ impl RDataOperation for PTR {
         Ok(encoded.len())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::rdata::encode_domain_name;
+
+    #[test]
+    fn test_ptr_roundtrip() {
+        let mut ptr = PTR::new();
+        ptr.with_name("host.example.com.");
+        assert_eq!("host.example.com", ptr.name());
+
+        let mut raw = vec![];
+        let length = ptr
+            .encode(&mut raw, &mut CompressList::new(), false)
+            .unwrap();
+        assert_eq!(encode_domain_name("host.example.com").unwrap(), raw);
+        assert_eq!(raw.len(), length);
+        assert_eq!(ptr, PTR::from(&raw, &raw, 0).unwrap());
+
+        // compressed with the example.com after the header
+        let mut raw = vec![0; 12];
+        raw.extend(encode_domain_name("example.com").unwrap());
+        let mut cl = CompressList::new();
+        cl.push("example.com", 12);
+        let start = raw.len();
+        let length = ptr.encode(&mut raw, &mut cl, true).unwrap();
+        assert_eq!(1 + 4 + 2, length);
+        assert_eq!(ptr, PTR::from(&raw, &raw[start..], start).unwrap());
+    }
+}
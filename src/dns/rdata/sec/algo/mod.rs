@@ -66,7 +66,7 @@ algorithm with Transaction Security.
 [RFC8078]: https://www.rfc-editor.org/rfc/rfc8078.html
 [RFC8080]: https://www.rfc-editor.org/rfc/rfc8080.html
 */
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DNSSecAlgorithm(u8);
 
 impl DNSSecAlgorithm {
@@ -91,6 +91,11 @@ impl DNSSecAlgorithm {
 /// ref: https://www.rfc-editor.org/rfc/rfc2537.html
 pub const DNSSEC_ALGORITHM1: u8 = 1;
 
+/// RSA/SHA-1
+///
+/// ref: https://www.rfc-editor.org/rfc/rfc3110.html
+pub const DNSSEC_ALGORITHM5: u8 = 5;
+
 /**
 # Digest Algorithms
 ## Registration Procedure(s)
@@ -116,7 +121,7 @@ RFC Required
 [RFC6605]: https://www.rfc-editor.org/rfc/rfc6605.html
 [RFC9157]: https://www.rfc-editor.org/rfc/rfc9157.html
  */
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DigestAlgorithm(u8);
 
 impl DigestAlgorithm {
@@ -10,7 +10,8 @@ use rsa::{
 use sha1::Sha1;
 use std::{fs::File, path::Path};
 
-use super::common::hash_sha1;
+use super::{common::hash_sha1, DNSSEC_ALGORITHM5};
+use crate::dns::rdata::sec::signer::{Signer, Verifier};
 
 pub struct RsaSha1 {
     pub_key: RsaPublicKey,
@@ -184,6 +185,23 @@ impl RsaSha1 {
     }
 }
 
+impl Signer for RsaSha1 {
+    fn algorithm(&self) -> u8 {
+        DNSSEC_ALGORITHM5
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let (_, signature) = self.sign_digest(data)?;
+        Ok(signature)
+    }
+}
+
+impl Verifier for RsaSha1 {
+    fn verify(&self, data: &[u8], signature: &[u8]) -> Result<(), Error> {
+        self.verify_digest(data, signature)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
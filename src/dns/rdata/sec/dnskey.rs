@@ -13,6 +13,9 @@ const ZONE_KEY_POS: u8 = 0;
 const SECURE_ENTRY_POINT: u8 = 0b0000_0001;
 const SECURE_ENTRY_POINT_POS: u8 = 0;
 
+/// the only valid value of the Protocol Field.
+pub const DNSKEY_PROTOCOL: u8 = 3;
+
 /**
   The RDATA for a DNSKEY RR consists of a 2 octet Flags Field, a 1
   octet Protocol Field, a 1 octet Algorithm Field, and the Public Key
@@ -30,7 +33,7 @@ const SECURE_ENTRY_POINT_POS: u8 = 0;
   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
   ```
 */
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DNSKEY {
     /**
     Bit 7 of the Flags field is the Zone Key flag.  If bit 7 has value 1,
@@ -83,7 +86,7 @@ impl DNSKEY {
     pub fn new() -> Self {
         Self {
             flags: 0,
-            protocol: 3,
+            protocol: DNSKEY_PROTOCOL,
             algorithm: DNSSecAlgorithm::new(0),
             pub_key: Vec::new(),
         }
@@ -103,13 +106,23 @@ impl DNSKEY {
         r
     }
 
-    pub fn from(raw: &[u8], rdata: &[u8]) -> Result<Self, Error> {
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<Self, Error> {
         let mut dnskey = Self::new();
-        dnskey.decode(raw, rdata)?;
+        dnskey.decode(raw, rdata, rdata_start)?;
 
         Ok(dnskey)
     }
 
+    pub fn with_protocol(&mut self, protocol: u8) -> &mut Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// is_valid_protocol: the protocol must be 3, or the key is invalid for the signature verification.
+    pub fn is_valid_protocol(&self) -> bool {
+        self.protocol == DNSKEY_PROTOCOL
+    }
+
     pub fn flag_zone_key(&self) -> bool {
         self.flags.to_be_bytes()[0] & ZONE_KEY_FLAG == ZONE_KEY_FLAG
     }
@@ -144,7 +157,7 @@ impl DNSKEY {
 }
 
 impl RDataOperation for DNSKEY {
-    fn decode(&mut self, _raw: &[u8], rdata: &[u8]) -> Result<(), Error> {
+    fn decode(&mut self, _raw: &[u8], rdata: &[u8], _rdata_start: usize) -> Result<usize, Error> {
         if rdata.len() < 4 {
             return Err(anyhow!(ERR_RDATE_MSG));
         }
@@ -152,7 +165,7 @@ impl RDataOperation for DNSKEY {
         self.protocol = rdata[2];
         self.algorithm = DNSSecAlgorithm::new(rdata[3]);
         self.pub_key = BASE64_ENGINE.decode(rdata[4..].to_vec())?;
-        Ok(())
+        Ok(rdata.len())
     }
 
     fn encode(
@@ -194,6 +207,16 @@ mod tests {
         assert_eq!(0, dnskey.flags);
     }
 
+    #[test]
+    fn test_dnskey_protocol() {
+        let mut dnskey = DNSKEY::new();
+        assert_eq!(3, dnskey.protocol);
+        assert_eq!(true, dnskey.is_valid_protocol());
+
+        dnskey.with_protocol(2);
+        assert_eq!(false, dnskey.is_valid_protocol());
+    }
+
     #[test]
     fn test_dnskey_decode() {
         let pub_key = "AQPSKmynfzW4kyBv015MUG2DeIQ3Cbl+BBZH4b/0PY1kxkmvHjcZc8nokfzj31GajIQKY+5CptLr3buXA10hWqTkF7H6RfoRqXQeogmMHfpftf6zMv1LyBUgia7za6ZEzOJBOztyvhjL742iU/TpPSEDhm2SNKLijfUppn1UaNvv4w==";
@@ -201,7 +224,7 @@ mod tests {
         rdata.extend(pub_key.as_bytes());
 
         let mut dnskey = DNSKEY::new();
-        let result = dnskey.decode(&rdata, &rdata);
+        let result = dnskey.decode(&rdata, &rdata, 0);
         assert_eq!(false, result.is_err());
     }
 }
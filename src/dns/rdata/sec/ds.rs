@@ -46,7 +46,7 @@ use anyhow::{anyhow, Error};
   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
   ```
 */
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DS {
     /**
     The Key Tag field lists the key tag of the DNSKEY RR referred to by
@@ -105,16 +105,16 @@ impl DS {
         }
     }
 
-    pub fn from(raw: &[u8], rdata: &[u8]) -> Result<Self, Error> {
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<Self, Error> {
         let mut ds = Self::new();
-        ds.decode(raw, rdata)?;
+        ds.decode(raw, rdata, rdata_start)?;
 
         Ok(ds)
     }
 }
 
 impl RDataOperation for DS {
-    fn decode(&mut self, _raw: &[u8], rdata: &[u8]) -> Result<(), Error> {
+    fn decode(&mut self, _raw: &[u8], rdata: &[u8], _rdata_start: usize) -> Result<usize, Error> {
         if rdata.len() < 4 {
             return Err(anyhow!(ERR_RDATE_MSG));
         }
@@ -123,7 +123,7 @@ impl RDataOperation for DS {
         self.digest_type = rdata[3];
         self.digest = rdata[4..].to_vec();
 
-        Ok(())
+        Ok(rdata.len())
     }
 
     fn encode(
@@ -44,7 +44,7 @@ use super::{algo::DNSSEC_ALGORITHM1, dnskey::DNSKEY};
     The input is the wire format of the RDATA portion of the DNSKEY RR.
     The code is written for clarity, not efficiency.
 */
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct KeyTag(u16);
 
 impl KeyTag {
@@ -86,26 +86,7 @@ impl KeyTag {
     }
 
     fn _calc(dnskey: &DNSKEY) -> u16 {
-        if dnskey.algorithm.algo() == DNSSEC_ALGORITHM1 {
-            return Self::calc_key_tag_for_1(dnskey);
-        }
-
-        let key = dnskey.as_bytes();
-        let keysize = dnskey.all_len(); /* the RDLENGTH */
-
-        let mut ac: usize = 0; /* assumed to be 32 bits or larger */
-        let mut i = 0; /* loop index */
-        while i < keysize {
-            if i & 1 > 0 {
-                ac += *key.get(i).unwrap() as usize;
-            } else {
-                continue;
-            }
-            i += 1;
-        }
-        ac += (ac >> 16) & 0xFFFF;
-
-        (ac & 0xFFFF) as u16
+        calc_key_tag_from_rdata(&dnskey.as_bytes(), dnskey.algorithm.algo()).0
     }
 
     /// return the index of the valid rr in rrs
@@ -136,13 +117,81 @@ impl KeyTag {
 
       Please note that Algorithm 1 is NOT RECOMMENDED.
     */
-    fn calc_key_tag_for_1(dnskey: &DNSKEY) -> u16 {
-        if dnskey.algorithm.algo() != DNSSEC_ALGORITHM1 {
+    fn calc_key_tag_for_1(pub_key: &[u8]) -> u16 {
+        let len = pub_key.len();
+        if len < 3 {
             return 0;
         }
-        let len = dnskey.pub_key.len();
-        let kt = [dnskey.pub_key[len - 3], dnskey.pub_key[len - 2]];
+        let kt = [pub_key[len - 3], pub_key[len - 2]];
 
         u16::from_be_bytes(kt)
     }
 }
+
+/// calc_key_tag_from_rdata: calculate the key tag from the wire format of the DNSKEY RDATA,
+/// so the key tag of the records parsed as unknown type can be computed too.
+pub fn calc_key_tag_from_rdata(rdata: &[u8], algorithm: u8) -> KeyTag {
+    if algorithm == DNSSEC_ALGORITHM1 {
+        // the public key follows the flags, protocol and algorithm
+        return KeyTag::new(KeyTag::calc_key_tag_for_1(
+            rdata.get(4..).unwrap_or_default(),
+        ));
+    }
+
+    let mut ac: usize = 0; /* assumed to be 32 bits or larger */
+    for (i, b) in rdata.iter().enumerate() {
+        if i & 1 > 0 {
+            ac += *b as usize;
+        } else {
+            ac += (*b as usize) << 8;
+        }
+    }
+    ac += (ac >> 16) & 0xFFFF;
+
+    KeyTag::new((ac & 0xFFFF) as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dns::rdata::sec::algo::{DNSSecAlgorithm, DNSSEC_ALGORITHM5},
+        util::BASE64_ENGINE,
+    };
+    use base64::Engine as _;
+
+    #[test]
+    fn test_calc_key_tag_from_rdata() {
+        // ref: https://www.rfc-editor.org/rfc/rfc4034#section-5.4
+        let mut dnskey = DNSKEY::new();
+        dnskey.flags = 256;
+        dnskey.protocol = 3;
+        dnskey.algorithm = DNSSecAlgorithm::new(DNSSEC_ALGORITHM5);
+        dnskey.pub_key = BASE64_ENGINE
+            .decode(
+                "AQOeiiR0GOMYkDshWoSKz9XzfwJr1AYtsmx3TGkJaNXVbfi/2pHm822aJ5iI9BMzNXxeYCmZDRD99WYwYqUSdjMmmAphXdvxegXd/M5+X7OrzKBaMbCVdFLUUh6DhweJBjEVv5f2wwjM9XzcnOf+EPbtG9DMBmADjFDc2w/rljwvFw==",
+            )
+            .unwrap();
+
+        let mut key_tag = KeyTag::new(0);
+        key_tag.calc(&dnskey);
+        assert_eq!(60485, key_tag.key_tag());
+        assert_eq!(
+            key_tag,
+            calc_key_tag_from_rdata(&dnskey.as_bytes(), DNSSEC_ALGORITHM5)
+        );
+
+        // algorithm 1
+        dnskey.algorithm = DNSSecAlgorithm::new(DNSSEC_ALGORITHM1);
+        key_tag.calc(&dnskey);
+        let len = dnskey.pub_key.len();
+        assert_eq!(
+            u16::from_be_bytes([dnskey.pub_key[len - 3], dnskey.pub_key[len - 2]]),
+            key_tag.key_tag()
+        );
+        assert_eq!(
+            key_tag,
+            calc_key_tag_from_rdata(&dnskey.as_bytes(), DNSSEC_ALGORITHM1)
+        );
+    }
+}
@@ -4,3 +4,4 @@ pub mod ds;
 pub mod key_tag;
 pub mod nsec;
 pub mod rrsig;
+pub mod signer;
@@ -27,7 +27,14 @@ The NSEC resource record lists two separate things: the next owner
    field.  This is in the spirit of negative caching ([RFC2308]).
  */
 
-use crate::dns::rdata::{encode_domain_name, parse_domain_name, RDataOperation, ERR_RDATE_MSG};
+use crate::{
+    dns::{
+        labels::{check_label_type, check_pointer, Labels},
+        rdata::{encode_domain_name, RDataOperation, ERR_RDATE_MSG},
+        Type,
+    },
+    util,
+};
 use anyhow::{anyhow, Error};
 
 /**
@@ -43,7 +50,7 @@ The RDATA of the NSEC RR is as shown below:
    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 ```
   */
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NSEC {
     /**
     The Next Domain field contains the next owner name (in the canonical
@@ -122,23 +129,78 @@ impl NSEC {
             type_bit_maps: vec![],
         }
     }
-    pub fn from(raw: &[u8], rdata: &[u8]) -> Result<Self, Error> {
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<Self, Error> {
         let mut nsec = Self::new();
-        nsec.decode(raw, rdata)?;
+        nsec.decode(raw, rdata, rdata_start)?;
 
         Ok(nsec)
     }
-}
-impl RDataOperation for NSEC {
-    fn decode(&mut self, raw: &[u8], rdata: &[u8]) -> Result<(), Error> {
+
+    /**
+    decode_strict: same as the decode, but reject the next domain name that contains a compression
+    pointer, a validator must treat such NSEC as bogus.
+
+    ref: https://www.rfc-editor.org/rfc/rfc4034#section-4.1.1
+     */
+    pub fn decode_strict(
+        &mut self,
+        raw: &[u8],
+        rdata: &[u8],
+        rdata_start: usize,
+    ) -> Result<usize, Error> {
+        self.decode_with(raw, rdata, rdata_start, true)
+    }
+
+    fn decode_with(
+        &mut self,
+        raw: &[u8],
+        rdata: &[u8],
+        rdata_start: usize,
+        strict: bool,
+    ) -> Result<usize, Error> {
         if rdata.len() < 8 {
             return Err(anyhow!(ERR_RDATE_MSG));
         }
-        let (domain_names, length) = parse_domain_name(raw, rdata)?;
-        self.next_domain_name = domain_names.get(0).unwrap().encode_to_str();
+        let (next_domain_name, length) = decode_next_domain_name(raw, rdata, rdata_start, strict)?;
+        self.next_domain_name = next_domain_name;
         self.type_bit_maps = rdata[length..].to_vec();
 
-        Ok(())
+        Ok(rdata.len())
+    }
+
+    /// with_types: encode the types into the type_bit_maps by window blocks.
+    pub fn with_types(&mut self, types: &[Type]) -> &mut Self {
+        let mut types = types.to_vec();
+        types.sort();
+        types.dedup();
+
+        self.type_bit_maps.clear();
+        let mut iter = types.iter().peekable();
+        while let Some(&first) = iter.peek() {
+            let window = (first >> 8) as u8;
+            let mut bitmap = [0_u8; 32];
+            let mut length = 0;
+            while let Some(&&typ) = iter.peek() {
+                if (typ >> 8) as u8 != window {
+                    break;
+                }
+                let low = (typ & 0xFF) as usize;
+                bitmap[low / 8] |= 0b1000_0000 >> (low % 8);
+                length = low / 8 + 1;
+                iter.next();
+            }
+            self.type_bit_maps.push(window);
+            self.type_bit_maps.push(length as u8);
+            self.type_bit_maps.extend_from_slice(&bitmap[..length]);
+        }
+
+        self
+    }
+}
+impl RDataOperation for NSEC {
+    /// the compressed next domain name is followed for the interoperability, see [`NSEC::decode_strict`].
+    fn decode(&mut self, raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<usize, Error> {
+        self.decode_with(raw, rdata, rdata_start, false)
     }
 
     fn encode(
@@ -147,10 +209,90 @@ impl RDataOperation for NSEC {
         _cl: &mut crate::dns::compress_list::CompressList,
         _is_compressed: bool,
     ) -> Result<usize, anyhow::Error> {
-        let encoded_domain_name = encode_domain_name(self.next_domain_name.as_str());
+        let encoded_domain_name = encode_domain_name(self.next_domain_name.as_str())?;
         raw.extend(&encoded_domain_name);
         raw.extend(&self.type_bit_maps);
 
         Ok(encoded_domain_name.len() + self.type_bit_maps.len())
     }
 }
+
+/// decode the next domain name at the head of the rdata, return it and the length it occupied in the rdata.
+/// the compression pointer is followed in the raw, or rejected when strict.
+fn decode_next_domain_name(
+    raw: &[u8],
+    rdata: &[u8],
+    rdata_start: usize,
+    strict: bool,
+) -> Result<(String, usize), Error> {
+    let mut labels = Labels::new();
+    let mut offset = 0;
+    loop {
+        let len = *rdata.get(offset).ok_or(anyhow!(ERR_RDATE_MSG))? as usize;
+        if len == 0 {
+            offset += 1;
+            break;
+        }
+        let (mut compressed_offset, is_compressed) = util::is_compressed_wrap(&rdata[offset..]);
+        if is_compressed {
+            if strict {
+                return Err(anyhow!(
+                    "the next domain name of NSEC must not be compressed"
+                ));
+            }
+            check_pointer(compressed_offset, rdata_start + offset)?;
+            labels.extend(Labels::parse(raw, &mut compressed_offset)?);
+            offset += 2;
+            break;
+        }
+        check_label_type(len as u8)?;
+        let label = rdata
+            .get(offset + 1..offset + 1 + len)
+            .ok_or(anyhow!(ERR_RDATE_MSG))?;
+        labels.0.push(String::from_utf8(label.to_vec())?);
+        offset += 1 + len;
+    }
+    labels.check_len()?;
+
+    Ok((labels.encode_to_str(), offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::{compress_list::CompressList, TYPE_A, TYPE_NSEC, TYPE_RRSIG};
+
+    #[test]
+    fn test_nsec_decode_strict() {
+        let mut nsec = NSEC::new();
+        nsec.next_domain_name = "host.example.com".to_string();
+        nsec.with_types(&[TYPE_A, TYPE_RRSIG, TYPE_NSEC]);
+        let mut rdata = vec![];
+        nsec.encode(&mut rdata, &mut CompressList::new(), false)
+            .unwrap();
+
+        let mut decoded = NSEC::new();
+        assert_eq!(
+            rdata.len(),
+            decoded.decode_strict(&rdata, &rdata, 0).unwrap()
+        );
+        assert_eq!(nsec, decoded);
+
+        // the raw holds "example.com" at offset 12, the next domain name is "host" + pointer to it
+        let mut raw = vec![0_u8; 12];
+        raw.extend(encode_domain_name("example.com").unwrap());
+        let mut rdata = vec![4, b'h', b'o', b's', b't', 0xC0, 12];
+        rdata.extend(&nsec.type_bit_maps);
+        let start = raw.len();
+        raw.extend(&rdata);
+
+        assert_eq!(
+            true,
+            NSEC::new().decode_strict(&raw, &rdata, start).is_err()
+        );
+
+        let mut decoded = NSEC::new();
+        decoded.decode(&raw, &rdata, start).unwrap();
+        assert_eq!(nsec, decoded);
+    }
+}
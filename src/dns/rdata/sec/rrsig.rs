@@ -34,7 +34,7 @@ use base64::Engine as _;
     +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
     ```
 */
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RRSig {
     /**
     The Type Covered field identifies the type of the RRset that is
@@ -213,16 +213,16 @@ impl RRSig {
         }
     }
 
-    pub fn from(raw: &[u8], rdata: &[u8]) -> Result<Self, Error> {
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<Self, Error> {
         let mut rrsig = Self::new();
-        rrsig.decode(raw, rdata)?;
+        rrsig.decode(raw, rdata, rdata_start)?;
 
         Ok(rrsig)
     }
 }
 
 impl RDataOperation for RRSig {
-    fn decode(&mut self, _raw: &[u8], rdata: &[u8]) -> Result<(), Error> {
+    fn decode(&mut self, _raw: &[u8], rdata: &[u8], _rdata_start: usize) -> Result<usize, Error> {
         if rdata.len() < 18 {
             return Err(anyhow!(ERR_RDATE_MSG));
         }
@@ -236,7 +236,7 @@ impl RDataOperation for RRSig {
 
         todo!("signer_name and signature");
 
-        Ok(())
+        Ok(rdata.len())
     }
 
     fn encode(
@@ -0,0 +1,385 @@
+/*!
+ref: https://www.rfc-editor.org/rfc/rfc4034#section-3.1.8.1
+
+# Signature Calculation
+
+A signature covers the RRSIG RDATA field (excluding the signature)
+and all matching RRs in the RRset, all in canonical form:
+```shell
+    signature = sign(RRSIG_RDATA | RR(1) | RR(2)... )
+```
+where:
+
+"|" denotes concatenation;
+
+RRSIG_RDATA is the wire format of the RRSIG RDATA fields with the
+Signer's Name field in canonical form and the Signature field excluded;
+
+RR(i) = owner | type | class | TTL | RDATA length | RDATA
+
+The RRset MUST be sorted in canonical order.
+ */
+
+use super::{algo::DNSSecAlgorithm, dnskey::DNSKEY, key_tag::KeyTag, nsec::NSEC, rrsig::RRSig};
+use crate::dns::{
+    compress_list::CompressList,
+    rdata::{encode_domain_name, RDataOperation, RDataType},
+    rr::{is_same_rrset, RRs},
+    RcRf, RR, TYPE_NSEC, TYPE_RRSIG, TYPE_SOA,
+};
+use anyhow::{anyhow, Error};
+use std::{cell::RefCell, rc::Rc};
+
+/// Signer sign the data with the private key of the DNSKEY.
+pub trait Signer {
+    /// the DNSSEC algorithm number of the signer.
+    fn algorithm(&self) -> u8;
+
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>, Error>;
+}
+
+/// Verifier verify the signature with the public key of the DNSKEY.
+pub trait Verifier {
+    fn verify(&self, data: &[u8], signature: &[u8]) -> Result<(), Error>;
+}
+
+/**
+sign_zone: sign the zone offline.
+
+The records will be canonicalized in place, then every RRset is signed by the signer with the key,
+and the NSEC chain is synthesized across the sorted owner names (the NSEC RRsets are signed too).
+The owner of the SOA record is used as the signer's name.
+
+Return the RRSIG and NSEC records that should be added to the zone.
+ */
+pub fn sign_zone(
+    records: &mut Vec<RcRf<RR>>,
+    key: &DNSKEY,
+    signer: &dyn Signer,
+    inception: u32,
+    expiration: u32,
+) -> Result<Vec<RR>, Error> {
+    if !key.is_valid_protocol() {
+        return Err(anyhow!(
+            "the protocol of the key({}) is not 3",
+            key.protocol
+        ));
+    }
+    if signer.algorithm() != key.algorithm.algo() {
+        return Err(anyhow!(
+            "the algorithm of signer({}) not match the key({})",
+            signer.algorithm(),
+            key.algorithm.algo()
+        ));
+    }
+
+    let mut rrs = RRs(std::mem::take(records));
+    rrs.canonicalize();
+    *records = rrs.0;
+
+    let soa = records
+        .iter()
+        .find(|rr| rr.borrow().typ() == TYPE_SOA)
+        .ok_or(anyhow!("the zone has no SOA record"))?;
+    let (apex, class, minimum) = {
+        let soa = soa.borrow();
+        let minimum = match soa.rdata() {
+            RDataType::SOA(rdata) => rdata.minimum,
+            _ => soa.ttl(),
+        };
+        (soa.name().to_string(), soa.class(), minimum)
+    };
+
+    let mut key_tag = KeyTag::new(0);
+    key_tag.calc(key);
+
+    let rrsets = group_rrsets(records);
+
+    // synthesize the NSEC chain
+    let mut owners: Vec<(String, Vec<u16>)> = vec![];
+    for rrset in &rrsets {
+        let rr = rrset[0].borrow();
+        match owners.last_mut() {
+            Some((name, types)) if name == rr.name() => types.push(rr.typ()),
+            _ => owners.push((rr.name().to_string(), vec![rr.typ()])),
+        }
+    }
+    let mut nsecs = vec![];
+    for (i, (name, types)) in owners.iter().enumerate() {
+        let mut types = types.clone();
+        types.extend([TYPE_RRSIG, TYPE_NSEC]);
+        let mut nsec = NSEC::new();
+        nsec.next_domain_name = owners[(i + 1) % owners.len()].0.clone();
+        nsec.with_types(&types);
+
+        let mut rr = RR::new();
+        rr.with_name(name)
+            .with_type(TYPE_NSEC)
+            .with_class(class)
+            .with_ttl(minimum)
+            .with_rdata(RDataType::NSEC(nsec));
+        nsecs.push(Rc::new(RefCell::new(rr)));
+    }
+
+    let mut result = vec![];
+    let all_rrsets = rrsets
+        .iter()
+        .map(|rrset| rrset.as_slice())
+        .chain(nsecs.iter().map(std::slice::from_ref));
+    for rrset in all_rrsets {
+        let mut rrsig = RRSig::new();
+        {
+            let rr = rrset[0].borrow();
+            rrsig.type_covered = rr.typ();
+            rrsig.algorithm = DNSSecAlgorithm::new(key.algorithm.algo());
+            rrsig.labels = count_labels(rr.name());
+            rrsig.origin_ttl = rr.ttl();
+        }
+        rrsig.sig_expiration = expiration;
+        rrsig.sig_inception = inception;
+        rrsig.key_tag = KeyTag::new(key_tag.key_tag());
+        rrsig.signer_name = encode_domain_name(&apex)?;
+        rrsig.signature = signer.sign(&signed_data(rrset, &rrsig)?)?;
+
+        let mut rr = RR::new();
+        {
+            let covered = rrset[0].borrow();
+            rr.with_name(covered.name())
+                .with_type(TYPE_RRSIG)
+                .with_class(covered.class())
+                .with_ttl(covered.ttl())
+                .with_rdata(RDataType::RRSig(rrsig));
+        }
+        result.push(rr);
+    }
+
+    for nsec in nsecs {
+        result.push(Rc::try_unwrap(nsec).unwrap().into_inner());
+    }
+
+    Ok(result)
+}
+
+/// verify_rrsig: verify the rrsig over the rrset with the verifier.
+pub fn verify_rrsig(
+    rrset: &[RcRf<RR>],
+    rrsig: &RRSig,
+    verifier: &dyn Verifier,
+) -> Result<(), Error> {
+    if rrset.is_empty() {
+        return Err(anyhow!("empty rrset"));
+    }
+    let mut sorted = rrset.to_vec();
+    sorted.sort_by(|a, b| a.borrow().canonical_cmp(&b.borrow()));
+
+    verifier.verify(&signed_data(&sorted, rrsig)?, &rrsig.signature)
+}
+
+/// verify_rrsig_with_key: same as [`verify_rrsig`], but the DNSKEY must be valid for the verification:
+/// its protocol is 3, the algorithm and key tag match the rrsig.
+pub fn verify_rrsig_with_key(
+    rrset: &[RcRf<RR>],
+    rrsig: &RRSig,
+    key: &DNSKEY,
+    verifier: &dyn Verifier,
+) -> Result<(), Error> {
+    if !key.is_valid_protocol() {
+        return Err(anyhow!(
+            "the protocol of the key({}) is not 3",
+            key.protocol
+        ));
+    }
+    if key.algorithm.algo() != rrsig.algorithm.algo() {
+        return Err(anyhow!(
+            "the algorithm of the key({}) not match the rrsig({})",
+            key.algorithm.algo(),
+            rrsig.algorithm.algo()
+        ));
+    }
+    let mut key_tag = KeyTag::new(0);
+    key_tag.calc(key);
+    if key_tag.key_tag() != rrsig.key_tag.key_tag() {
+        return Err(anyhow!(
+            "the key tag of the key({}) not match the rrsig({})",
+            key_tag.key_tag(),
+            rrsig.key_tag.key_tag()
+        ));
+    }
+
+    verify_rrsig(rrset, rrsig, verifier)
+}
+
+/// group the canonical sorted records into RRsets, the RRSIG and NSEC records are skipped.
+fn group_rrsets(records: &[RcRf<RR>]) -> Vec<Vec<RcRf<RR>>> {
+    let mut rrsets: Vec<Vec<RcRf<RR>>> = vec![];
+    for rr in records {
+        let typ = rr.borrow().typ();
+        if typ == TYPE_RRSIG || typ == TYPE_NSEC {
+            continue;
+        }
+        match rrsets.last_mut() {
+            Some(rrset) if is_same_rrset(&rrset[0].borrow(), &rr.borrow()) => {
+                rrset.push(rr.clone())
+            }
+            _ => rrsets.push(vec![rr.clone()]),
+        }
+    }
+
+    rrsets
+}
+
+/// the number of labels in the owner name, the root and the leftmost wildcard label are not counted.
+fn count_labels(name: &str) -> u8 {
+    name.trim_end_matches('.')
+        .split('.')
+        .filter(|l| !l.is_empty())
+        .enumerate()
+        .filter(|(i, l)| !(*i == 0 && *l == "*"))
+        .count() as u8
+}
+
+/// RRSIG_RDATA | RR(1) | RR(2)...
+fn signed_data(rrset: &[RcRf<RR>], rrsig: &RRSig) -> Result<Vec<u8>, Error> {
+    let mut data = vec![];
+    data.extend(rrsig.type_covered.to_be_bytes());
+    data.push(rrsig.algorithm.algo());
+    data.push(rrsig.labels);
+    data.extend(rrsig.origin_ttl.to_be_bytes());
+    data.extend(rrsig.sig_expiration.to_be_bytes());
+    data.extend(rrsig.sig_inception.to_be_bytes());
+    data.extend(rrsig.key_tag.key_tag().to_be_bytes());
+    data.extend(rrsig.signer_name.to_ascii_lowercase());
+
+    for rr in rrset {
+        let rr = rr.borrow();
+        let mut rdata = vec![];
+        rr.rdata()
+            .encode(&mut rdata, &mut CompressList::new(), false)?;
+
+        data.extend(encode_domain_name(&rr.name().to_ascii_lowercase())?);
+        data.extend(rr.typ().to_be_bytes());
+        data.extend(rr.class().to_be_bytes());
+        data.extend(rrsig.origin_ttl.to_be_bytes());
+        data.extend((rdata.len() as u16).to_be_bytes());
+        data.extend(rdata);
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::{
+        rdata::sec::algo::{rsa_sha1::RsaSha1, DNSSEC_ALGORITHM5},
+        rdata::{a::A, soa::SOA},
+        CLASS_IN, TYPE_A,
+    };
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_sign_zone() {
+        let new_rr = |name: &str, typ, rdata| {
+            let mut rr = RR::new();
+            rr.with_name(name)
+                .with_type(typ)
+                .with_class(CLASS_IN)
+                .with_ttl(3600)
+                .with_rdata(rdata);
+            Rc::new(RefCell::new(rr))
+        };
+        let mut soa = SOA::new();
+        soa.mname = "ns.example.com".to_string();
+        soa.rname = "admin.example.com".to_string();
+        soa.minimum = 300;
+
+        let mut records = vec![
+            new_rr(
+                "www.example.com",
+                TYPE_A,
+                RDataType::A(A::new(Ipv4Addr::new(10, 0, 0, 1))),
+            ),
+            new_rr("Example.com", TYPE_SOA, RDataType::SOA(soa)),
+            new_rr(
+                "example.com",
+                TYPE_A,
+                RDataType::A(A::new(Ipv4Addr::new(10, 0, 0, 2))),
+            ),
+        ];
+
+        let rsa = RsaSha1::from_file(
+            "./src/dns/rdata/sec/algo/test_data/rsa_sha1.pub",
+            "./src/dns/rdata/sec/algo/test_data/rsa_sha1.priv.pem",
+        )
+        .unwrap();
+        let mut key = DNSKEY::new();
+        key.algorithm = DNSSecAlgorithm::new(DNSSEC_ALGORITHM5);
+        key.pub_key = vec![1, 2, 3, 4];
+
+        let signed = sign_zone(&mut records, &key, &rsa, 1_700_000_000, 1_800_000_000).unwrap();
+        // example.com: A, SOA, NSEC; www.example.com: A, NSEC
+        assert_eq!(7, signed.len());
+
+        let nsecs: Vec<&RR> = signed.iter().filter(|rr| rr.typ() == TYPE_NSEC).collect();
+        assert_eq!(2, nsecs.len());
+        match nsecs[0].rdata() {
+            RDataType::NSEC(nsec) => assert_eq!("www.example.com", nsec.next_domain_name),
+            _ => panic!("not nsec"),
+        }
+        match nsecs[1].rdata() {
+            RDataType::NSEC(nsec) => assert_eq!("example.com", nsec.next_domain_name),
+            _ => panic!("not nsec"),
+        }
+
+        let rrset_of = |name: &str, typ| -> Vec<RcRf<RR>> {
+            records
+                .iter()
+                .filter(|r| r.borrow().name() == name && r.borrow().typ() == typ)
+                .cloned()
+                .collect()
+        };
+        let rrsigs: Vec<(&str, &RRSig)> = signed
+            .iter()
+            .filter_map(|rr| match rr.rdata() {
+                RDataType::RRSig(rrsig) if rrsig.type_covered != TYPE_NSEC => {
+                    Some((rr.name(), rrsig))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(3, rrsigs.len());
+        for (name, rrsig) in &rrsigs {
+            let rrset = rrset_of(name, rrsig.type_covered);
+            assert_eq!(true, verify_rrsig(&rrset, rrsig, &rsa).is_ok());
+            assert_eq!(
+                true,
+                verify_rrsig_with_key(&rrset, rrsig, &key, &rsa).is_ok()
+            );
+        }
+
+        // the key with the protocol other than 3 is invalid
+        let mut invalid_key = key.clone();
+        invalid_key.with_protocol(2);
+        let (name, rrsig) = rrsigs[0];
+        let rrset = rrset_of(name, rrsig.type_covered);
+        assert_eq!(
+            true,
+            verify_rrsig_with_key(&rrset, rrsig, &invalid_key, &rsa).is_err()
+        );
+        assert_eq!(
+            true,
+            sign_zone(&mut records.clone(), &invalid_key, &rsa, 0, 1).is_err()
+        );
+
+        // tamper the rdata of www.example.com
+        let rrset = rrset_of("www.example.com", TYPE_A);
+        rrset[0]
+            .borrow_mut()
+            .with_rdata(RDataType::A(A::new(Ipv4Addr::new(10, 0, 0, 9))));
+        let (_, rrsig) = rrsigs
+            .iter()
+            .find(|(name, _)| *name == "www.example.com")
+            .unwrap();
+        assert_eq!(true, verify_rrsig(&rrset, rrsig, &rsa).is_err());
+    }
+}
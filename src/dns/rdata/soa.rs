@@ -71,8 +71,18 @@ change the SOA RR with known semantics.
 use super::{encode_domain_name_wrap, parse_domain_name_without_len, RDataOperation};
 use crate::dns::{compress_list::CompressList, rdata::ERR_RDATE_MSG};
 use anyhow::{anyhow, Error, Ok};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-#[derive(Debug, PartialEq, Eq)]
+/// SerialScheme: how the SERIAL is bumped when the zone is edited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerialScheme {
+    /// the SERIAL + 1 in the sequence space arithmetic.
+    Increment,
+    /// the date based YYYYMMDDnn, the nn counts the edits of the day.
+    Date,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SOA {
     /// The <domain-name> of the name server that was the original or primary source of data for this zone.
     pub mname: String,
@@ -99,8 +109,8 @@ pub struct SOA {
 }
 
 impl SOA {
-    pub fn from(raw: &[u8], rdata: &[u8]) -> Result<Self, Error> {
-        let mut soa = Self {
+    pub fn new() -> Self {
+        Self {
             mname: "".to_string(),
             rname: "".to_string(),
             serial: 0,
@@ -108,19 +118,71 @@ impl SOA {
             retry: 0,
             expire: 0,
             minimum: 0,
-        };
-        soa.decode(raw, rdata)?;
+        }
+    }
+
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<Self, Error> {
+        let mut soa = Self::new();
+        soa.decode(raw, rdata, rdata_start)?;
 
         Ok(soa)
     }
+
+    /// bump_serial: increase the SERIAL by the scheme, the new SERIAL is always greater than the old one.
+    pub fn bump_serial(&mut self, scheme: SerialScheme) -> &mut Self {
+        let next = self.serial.wrapping_add(1);
+        self.serial = match scheme {
+            SerialScheme::Increment => next,
+            SerialScheme::Date => {
+                let today = date_serial(SystemTime::now());
+                match serial_gt(today, self.serial) {
+                    true => today,
+                    false => next,
+                }
+            }
+        };
+
+        self
+    }
+}
+
+/**
+serial_gt: whether the s1 is greater than the s2 in the sequence space arithmetic of the 32 bits SERIAL.
+
+ref: https://www.rfc-editor.org/rfc/rfc1982#section-3.2
+ */
+pub fn serial_gt(s1: u32, s2: u32) -> bool {
+    (s1 < s2 && s2 - s1 > 1 << 31) || (s1 > s2 && s1 - s2 < 1 << 31)
+}
+
+/// the YYYYMMDD00 of the UTC date of the time.
+fn date_serial(time: SystemTime) -> u32 {
+    let days = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / 86400;
+    // ref: http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year * 1_000_000 + month * 10_000 + day * 100) as u32
 }
 
 impl RDataOperation for SOA {
-    fn decode(&mut self, raw: &[u8], rdata: &[u8]) -> Result<(), Error> {
-        let list = parse_domain_name_without_len(raw, &rdata[..rdata.len() - 20])?;
-        if list.len() < 2 {
+    fn decode(&mut self, raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<usize, Error> {
+        // SERIAL, REFRESH, RETRY, EXPIRE and MINIMUM follow the names
+        if rdata.len() < 20 {
             return Err(anyhow!(ERR_RDATE_MSG));
         }
+        let list = parse_domain_name_without_len(raw, &rdata[..rdata.len() - 20], rdata_start, 2)?;
         self.mname = list.get(0).unwrap().encode_to_str();
         self.rname = list.get(1).unwrap().encode_to_str();
 
@@ -140,7 +202,7 @@ impl RDataOperation for SOA {
         self.expire = getu32(&mut offset)?;
         self.minimum = getu32(&mut offset)?;
 
-        Ok(())
+        Ok(offset)
     }
 
     fn encode(
@@ -164,3 +226,46 @@ impl RDataOperation for SOA {
         Ok(encoded_mname.len() + encoded_rname.len() + 4 + 4 + 4 + 4 + 4)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_serial_gt() {
+        assert_eq!(true, serial_gt(2, 1));
+        assert_eq!(false, serial_gt(1, 2));
+        assert_eq!(false, serial_gt(1, 1));
+        // wraps
+        assert_eq!(true, serial_gt(0, u32::MAX));
+        assert_eq!(true, serial_gt(10, u32::MAX - 10));
+    }
+
+    #[test]
+    fn test_soa_bump_serial() {
+        // 2024-02-29
+        let time = UNIX_EPOCH + Duration::from_secs(1_709_208_000);
+        assert_eq!(2024022900, date_serial(time));
+
+        let mut soa = SOA::new();
+        soa.serial = u32::MAX;
+        soa.bump_serial(SerialScheme::Increment);
+        assert_eq!(0, soa.serial);
+
+        soa.serial = 2000010100;
+        soa.bump_serial(SerialScheme::Date);
+        let today = date_serial(SystemTime::now());
+        assert_eq!(today, soa.serial);
+        soa.bump_serial(SerialScheme::Date);
+        assert_eq!(today + 1, soa.serial);
+    }
+
+    #[test]
+    fn test_soa_decode_short_rdata() {
+        let rdata = [0_u8; 10];
+        let err = SOA::from(&rdata, &rdata, 0).unwrap_err();
+        assert_eq!(ERR_RDATE_MSG, err.to_string());
+        assert_eq!(true, SOA::from(&[], &[], 0).is_err());
+    }
+}
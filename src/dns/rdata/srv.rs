@@ -0,0 +1,216 @@
+/*!
+ref: https://www.rfc-editor.org/rfc/rfc2782
+
+# SRV RDATA format
+```shell
+    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    |                   PRIORITY                    |
+    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    |                    WEIGHT                     |
+    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    |                     PORT                      |
+    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    /                    TARGET                     /
+    /                                               /
+    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+```
+where:
+
+PRIORITY        The priority of this target host.  A client MUST attempt to
+                contact the target host with the lowest-numbered priority it can
+                reach; target hosts with the same priority SHOULD be tried in an
+                order defined by the weight field.
+
+WEIGHT          A server selection mechanism.  The weight field specifies a
+                relative weight for entries with the same priority.  Larger
+                weights SHOULD be given a proportionately higher probability of
+                being selected.  Domain administrators SHOULD use Weight 0 when
+                there isn't any server selection to do.  In the presence of
+                records containing weights greater than 0, records with weight 0
+                should have a very small chance of being selected.
+
+PORT            The port on this target host of this service.
+
+TARGET          The domain name of the target host.  A Target of "." means that
+                the service is decidedly not available at this domain.
+ */
+
+use super::{encode_domain_name_wrap, parse_domain_name, RDataOperation, ERR_RDATE_MSG};
+use crate::dns::compress_list::CompressList;
+use anyhow::Error;
+use rand::Rng;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SRV {
+    pub priority: u16,
+    pub weight: u16,
+    pub port: u16,
+    pub target: String,
+}
+
+impl SRV {
+    pub fn new() -> Self {
+        Self {
+            priority: 0,
+            weight: 0,
+            port: 0,
+            target: "".to_string(),
+        }
+    }
+
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<Self, Error> {
+        let mut srv = Self::new();
+        srv.decode(raw, rdata, rdata_start)?;
+
+        Ok(srv)
+    }
+}
+
+impl RDataOperation for SRV {
+    fn decode(&mut self, raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<usize, Error> {
+        if rdata.len() < 6 {
+            return Err(Error::msg(ERR_RDATE_MSG));
+        }
+        self.priority = u16::from_be_bytes([rdata[0], rdata[1]]);
+        self.weight = u16::from_be_bytes([rdata[2], rdata[3]]);
+        self.port = u16::from_be_bytes([rdata[4], rdata[5]]);
+        let (list, length) = parse_domain_name(raw, &rdata[6..], rdata_start + 6, 1)?;
+        self.target = list
+            .get(0)
+            .ok_or(Error::msg(ERR_RDATE_MSG))?
+            .encode_to_str();
+
+        Ok(6 + length)
+    }
+
+    fn encode(
+        &self,
+        raw: &mut Vec<u8>,
+        cl: &mut CompressList,
+        is_compressed: bool,
+    ) -> Result<usize, Error> {
+        raw.extend_from_slice(&self.priority.to_be_bytes());
+        raw.extend_from_slice(&self.weight.to_be_bytes());
+        raw.extend_from_slice(&self.port.to_be_bytes());
+        let encoded_target =
+            encode_domain_name_wrap(self.target.as_str(), cl, is_compressed, raw.len())?;
+        raw.extend_from_slice(&encoded_target);
+
+        Ok(6 + encoded_target.len())
+    }
+}
+
+/**
+select: pick the target to contact from the records by the algorithm of RFC 2782:
+
+1. only the records of the lowest priority are considered.
+2. the records of weight 0 are placed at the beginning, then a random number is chosen between 0 and
+   the sum of the weights (inclusive), the first record whose running sum of the weights is greater
+   than or equal to the random number is selected. So the records of weight 0 are only selected with
+   a very small chance unless all the weights are 0.
+
+The caller tries the next record on failure, and should check the target `.` which means the service
+is not available.
+
+Return None if the records are empty.
+ */
+pub fn select<'a>(records: &'a [SRV], rng: &mut impl Rng) -> Option<&'a SRV> {
+    let priority = records.iter().map(|srv| srv.priority).min()?;
+    let mut group: Vec<&SRV> = records
+        .iter()
+        .filter(|srv| srv.priority == priority)
+        .collect();
+    group.sort_by_key(|srv| srv.weight != 0);
+
+    let total: u32 = group.iter().map(|srv| srv.weight as u32).sum();
+    let random = rng.gen_range(0..=total);
+    let mut sum = 0;
+    group.into_iter().find(|srv| {
+        sum += srv.weight as u32;
+        sum >= random
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{select, SRV};
+    use crate::dns::{
+        compress_list::CompressList,
+        rdata::{RDataOperation, RDataType},
+        TYPE_SRV,
+    };
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn srv(priority: u16, weight: u16, target: &str) -> SRV {
+        let mut srv = SRV::new();
+        srv.priority = priority;
+        srv.weight = weight;
+        srv.port = 5060;
+        srv.target = target.to_string();
+        srv
+    }
+
+    #[test]
+    fn test_srv_decode_encode() {
+        // _sip._tcp.example.com. 86400 IN SRV 10 60 5060 sipserver.example.com.
+        let mut rdata = vec![0, 10, 0, 60, 0x13, 0xc4];
+        rdata.extend(b"\x09sipserver\x07example\x03com\x00");
+        let srv = SRV::from(&[], &rdata, 0).unwrap();
+        assert_eq!(10, srv.priority);
+        assert_eq!(60, srv.weight);
+        assert_eq!(5060, srv.port);
+        assert_eq!("sipserver.example.com", srv.target);
+
+        let mut raw = vec![];
+        let length = srv
+            .encode(&mut raw, &mut CompressList::new(), false)
+            .unwrap();
+        assert_eq!(rdata, raw);
+        assert_eq!(rdata.len(), length);
+        assert_eq!(
+            RDataType::SRV(srv),
+            RDataType::from(&[], &rdata, 0, TYPE_SRV).unwrap()
+        );
+
+        assert_eq!(true, SRV::from(&[], &rdata[..5], 0).is_err());
+    }
+
+    #[test]
+    fn test_srv_select() {
+        let mut rng = ChaCha8Rng::seed_from_u64(2782);
+        assert_eq!(None, select(&[], &mut rng));
+
+        let records = vec![
+            srv(20, 100, "backup.example.com"),
+            srv(10, 60, "a.example.com"),
+            srv(10, 30, "b.example.com"),
+            srv(10, 10, "c.example.com"),
+            srv(10, 0, "d.example.com"),
+        ];
+        let draws = 10000;
+        let mut counts = [0; 5];
+        for _ in 0..draws {
+            let picked = select(&records, &mut rng).unwrap();
+            let pos = records.iter().position(|srv| srv == picked).unwrap();
+            counts[pos] += 1;
+        }
+        // the higher priority is never selected
+        assert_eq!(0, counts[0]);
+        // proportional to the weights, the weight 0 has the chance of 1/101
+        for (pos, weight) in [(1, 60), (2, 30), (3, 10)] {
+            let expected = draws * weight / 101;
+            assert_eq!(true, (counts[pos] as i32 - expected).abs() < draws / 50);
+        }
+        assert_eq!(true, counts[4] < draws / 50);
+
+        // all the weights are 0
+        let records = vec![srv(10, 0, "a.example.com"), srv(10, 0, "b.example.com")];
+        for _ in 0..10 {
+            assert_eq!(
+                "a.example.com",
+                select(&records, &mut rng).unwrap().target.as_str()
+            );
+        }
+    }
+}
@@ -0,0 +1,711 @@
+/*!
+ref: https://www.rfc-editor.org/rfc/rfc8945#section-4.2
+
+# TSIG RDATA format
+```shell
+    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    /                 ALGORITHM NAME                /
+    /                                               /
+    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    |                                               |
+    |                  TIME SIGNED                  |
+    |                                               |
+    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    |                     FUDGE                     |
+    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    |                   MAC SIZE                    |
+    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    /                      MAC                      /
+    /                                               /
+    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    |                  ORIGINAL ID                  |
+    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    |                     ERROR                     |
+    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    |                   OTHER LEN                   |
+    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    /                  OTHER DATA                   /
+    /                                               /
+    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+```
+where:
+
+ALGORITHM NAME  An octet sequence identifying the TSIG algorithm in the domain name
+                syntax.  The name is stored in the DNS name wire format and MUST NOT
+                be compressed.
+
+TIME SIGNED     An unsigned 48-bit integer containing the time the message was
+                signed as seconds since 00:00 on 1970-01-01 UTC, ignoring leap seconds.
+
+FUDGE           An unsigned 16-bit integer specifying the allowed time difference in
+                seconds permitted in the Time Signed field.
+
+MAC SIZE        An unsigned 16-bit integer giving the length of the MAC field in
+                octets.
+
+MAC             A sequence of octets whose contents are defined by the TSIG algorithm
+                used, possibly truncated.
+
+ORIGINAL ID     An unsigned 16-bit integer holding the message ID of the original
+                request message.
+
+ERROR           An unsigned 16-bit integer containing the extended RCODE covering TSIG
+                processing.
+
+OTHER LEN       An unsigned 16-bit integer specifying the length of the Other Data
+                field in octets.
+
+OTHER DATA      Additional data relevant to the TSIG record.  If the Error field is
+                BADTIME, the field will be a 48-bit unsigned integer containing the
+                server's current time.
+
+The TSIG RR MUST be the last record in the additional section, its NAME is the name of the key,
+TYPE is TSIG (250), CLASS is ANY and TTL is 0.
+ */
+
+use super::{encode_domain_name, RDataOperation, RDataType};
+use crate::{
+    dns::{
+        compress_list::CompressList, header::Header, labels::Labels, question::Questions,
+        rdata::ERR_RDATE_MSG, CLASS_ANY, ERR_BADKEY, ERR_BADSIG, ERR_BADTIME, RR, TYPE_TSIG,
+    },
+    DNS,
+};
+use anyhow::{anyhow, Error};
+use crypto::{
+    digest::Digest, hmac::Hmac, mac::Mac, md5::Md5, sha1::Sha1, sha2::Sha256, util::fixed_time_eq,
+};
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// the default FUDGE recommended by RFC 8945.
+const DEFAULT_FUDGE: u16 = 300;
+
+/// the TSIG algorithms, ref: https://www.rfc-editor.org/rfc/rfc8945#section-6
+pub const HMAC_MD5: &str = "hmac-md5.sig-alg.reg.int";
+pub const HMAC_SHA1: &str = "hmac-sha1";
+pub const HMAC_SHA256: &str = "hmac-sha256";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TSig {
+    /// The name of the TSIG algorithm, e.g. hmac-sha256.
+    pub algorithm_name: String,
+
+    /// The 48 bits seconds since the UNIX epoch the message was signed.
+    pub time_signed: u64,
+
+    /// The seconds of the error permitted in the time_signed.
+    pub fudge: u16,
+
+    /// The MAC defined by the algorithm.
+    pub mac: Vec<u8>,
+
+    /// The message ID of the original request.
+    pub original_id: u16,
+
+    /// The extended RCODE covering the TSIG processing.
+    pub error: u16,
+
+    /// The other data, the server's current time when the error is BADTIME.
+    pub other_data: Vec<u8>,
+}
+
+impl TSig {
+    pub fn new() -> Self {
+        Self {
+            algorithm_name: "".to_string(),
+            time_signed: 0,
+            fudge: DEFAULT_FUDGE,
+            mac: vec![],
+            original_id: 0,
+            error: 0,
+            other_data: vec![],
+        }
+    }
+
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<Self, Error> {
+        let mut tsig = Self::new();
+        tsig.decode(raw, rdata, rdata_start)?;
+
+        Ok(tsig)
+    }
+
+    pub fn with_algorithm_name(&mut self, algorithm_name: &str) -> &mut Self {
+        self.algorithm_name = algorithm_name.to_lowercase();
+        return self;
+    }
+
+    /// with_time_signed: set the time signed to the current time.
+    pub fn with_time_signed(&mut self) -> &mut Self {
+        self.time_signed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        return self;
+    }
+
+    pub fn with_fudge(&mut self, fudge: u16) -> &mut Self {
+        self.fudge = fudge;
+        return self;
+    }
+
+    pub fn with_mac(&mut self, mac: &[u8]) -> &mut Self {
+        self.mac = mac.to_vec();
+        return self;
+    }
+
+    pub fn with_original_id(&mut self, original_id: u16) -> &mut Self {
+        self.original_id = original_id;
+        return self;
+    }
+
+    pub fn with_error(&mut self, error: u16) -> &mut Self {
+        self.error = error;
+        return self;
+    }
+
+    pub fn with_other_data(&mut self, other_data: &[u8]) -> &mut Self {
+        self.other_data = other_data.to_vec();
+        return self;
+    }
+
+    /**
+    error_response: the TSIG of the response to the request failed the verification, the error is
+    one of BADKEY, BADSIG and BADTIME. For BADTIME the server_time is carried in the other data as
+    the 48 bits seconds, so the client can correct its clock.
+
+    The caller copies the algorithm name, the fudge and the original ID from the request, and signs
+    the BADTIME response by the request key, see [`TsigFailure::respond`].
+
+    ref: https://www.rfc-editor.org/rfc/rfc8945#section-5.2
+     */
+    pub fn error_response(error: u16, server_time: Option<u64>) -> TSig {
+        let mut tsig = Self::new();
+        tsig.with_time_signed().with_error(error);
+        if let Some(server_time) = server_time {
+            tsig.with_other_data(&server_time.to_be_bytes()[2..]);
+        }
+
+        tsig
+    }
+
+    /// the TSIG variables digested after the message, see [`TsigKey::mac`].
+    ///
+    /// ref: https://www.rfc-editor.org/rfc/rfc8945#section-4.3.3
+    fn variables(&self, key_name: &str) -> Result<Vec<u8>, Error> {
+        let mut raw = encode_domain_name(&key_name.to_lowercase())?;
+        raw.extend_from_slice(&CLASS_ANY.to_be_bytes());
+        raw.extend_from_slice(&0_u32.to_be_bytes());
+        raw.extend_from_slice(&encode_domain_name(&self.algorithm_name.to_lowercase())?);
+        raw.extend_from_slice(&self.time_signed.to_be_bytes()[2..]);
+        raw.extend_from_slice(&self.fudge.to_be_bytes());
+        raw.extend_from_slice(&self.error.to_be_bytes());
+        raw.extend_from_slice(&(self.other_data.len() as u16).to_be_bytes());
+        raw.extend_from_slice(&self.other_data);
+
+        Ok(raw)
+    }
+}
+
+impl RDataOperation for TSig {
+    fn decode(&mut self, _raw: &[u8], rdata: &[u8], _rdata_start: usize) -> Result<usize, Error> {
+        // the algorithm name is never compressed, so it is parsed in the rdata
+        let mut offset = 0;
+        if rdata.is_empty() || rdata[0] & 0b1100_0000 != 0 {
+            return Err(anyhow!(ERR_RDATE_MSG));
+        }
+        self.algorithm_name = Labels::parse(rdata, &mut offset)?.encode_to_str();
+
+        let mut take = |len: usize| -> Result<&[u8], Error> {
+            if offset + len > rdata.len() {
+                return Err(anyhow!(ERR_RDATE_MSG));
+            }
+            offset += len;
+            Ok(&rdata[offset - len..offset])
+        };
+        let mut time_signed = [0_u8; 8];
+        time_signed[2..].copy_from_slice(take(6)?);
+        self.time_signed = u64::from_be_bytes(time_signed);
+        self.fudge = u16::from_be_bytes(take(2)?.try_into().unwrap());
+        let mac_size = u16::from_be_bytes(take(2)?.try_into().unwrap());
+        self.mac = take(mac_size as usize)?.to_vec();
+        self.original_id = u16::from_be_bytes(take(2)?.try_into().unwrap());
+        self.error = u16::from_be_bytes(take(2)?.try_into().unwrap());
+        let other_len = u16::from_be_bytes(take(2)?.try_into().unwrap());
+        self.other_data = take(other_len as usize)?.to_vec();
+
+        Ok(offset)
+    }
+
+    fn encode(
+        &self,
+        raw: &mut Vec<u8>,
+        _hm: &mut CompressList,
+        _is_compressed: bool,
+    ) -> Result<usize, Error> {
+        let start = raw.len();
+        raw.extend_from_slice(&encode_domain_name(&self.algorithm_name)?);
+        raw.extend_from_slice(&self.time_signed.to_be_bytes()[2..]);
+        raw.extend_from_slice(&self.fudge.to_be_bytes());
+        raw.extend_from_slice(&(self.mac.len() as u16).to_be_bytes());
+        raw.extend_from_slice(&self.mac);
+        raw.extend_from_slice(&self.original_id.to_be_bytes());
+        raw.extend_from_slice(&self.error.to_be_bytes());
+        raw.extend_from_slice(&(self.other_data.len() as u16).to_be_bytes());
+        raw.extend_from_slice(&self.other_data);
+
+        Ok(raw.len() - start)
+    }
+}
+
+/// TsigKey: the secret shared by the client and the server, identified by the name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TsigKey {
+    pub name: String,
+    pub algorithm: String,
+    pub secret: Vec<u8>,
+}
+
+impl TsigKey {
+    pub fn new(name: &str, algorithm: &str, secret: &[u8]) -> Self {
+        Self {
+            name: name.trim_end_matches('.').to_lowercase(),
+            algorithm: algorithm.trim_end_matches('.').to_lowercase(),
+            secret: secret.to_vec(),
+        }
+    }
+
+    /// mac: the HMAC of the data by the algorithm of the key.
+    pub fn mac(&self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        fn hmac(digest: impl Digest, secret: &[u8], data: &[u8]) -> Vec<u8> {
+            let mut hmac = Hmac::new(digest, secret);
+            hmac.input(data);
+            hmac.result().code().to_vec()
+        }
+
+        match self.algorithm.as_str() {
+            HMAC_MD5 => Ok(hmac(Md5::new(), &self.secret, data)),
+            HMAC_SHA1 => Ok(hmac(Sha1::new(), &self.secret, data)),
+            HMAC_SHA256 => Ok(hmac(Sha256::new(), &self.secret, data)),
+            algorithm => Err(anyhow!("unsupported TSIG algorithm: {}", algorithm)),
+        }
+    }
+}
+
+/// sign: sign the request by the key, the TSIG RR is appended to the additional section. The MAC
+/// covers the dns encoded with the compression, so it must be sent by `encode(true)` unchanged.
+pub fn sign(dns: &mut DNS, key: &TsigKey, tsig: &mut TSig) -> Result<(), Error> {
+    sign_digest(dns, key, vec![], tsig)
+}
+
+/**
+sign_response: sign the response by the key of the request, the MAC of the request is digested
+before the response, so the response is bound to the request.
+
+ref: https://www.rfc-editor.org/rfc/rfc8945#section-5.3
+ */
+pub fn sign_response(
+    dns: &mut DNS,
+    key: &TsigKey,
+    request_mac: &[u8],
+    tsig: &mut TSig,
+) -> Result<(), Error> {
+    sign_digest(dns, key, request_digest(request_mac), tsig)
+}
+
+/// verify_response: verify the MAC of the response parsed from the wire to the request of the MAC.
+pub fn verify_response(dns: &DNS, key: &TsigKey, request_mac: &[u8]) -> Result<(), Error> {
+    let rr = match dns.additional().0.last() {
+        Some(rr) if rr.borrow().typ() == TYPE_TSIG => rr.borrow(),
+        _ => return Err(anyhow!("the response is not signed")),
+    };
+    let tsig = match rr.rdata() {
+        RDataType::TSig(tsig) => tsig,
+        _ => return Err(anyhow!("the response is not signed")),
+    };
+    let mut data = request_digest(request_mac);
+    data.extend(strip_tsig(dns.raw(), tsig.original_id)?);
+    data.extend(tsig.variables(&key.name)?);
+    if !fixed_time_eq(&key.mac(&data)?, &tsig.mac) {
+        return Err(anyhow!("the MAC of the response mismatches"));
+    }
+
+    Ok(())
+}
+
+/// the MAC of the request prefixed by its 2 octets length.
+fn request_digest(request_mac: &[u8]) -> Vec<u8> {
+    let mut data = (request_mac.len() as u16).to_be_bytes().to_vec();
+    data.extend_from_slice(request_mac);
+    data
+}
+
+/// sign the dns by the key, the prefix is digested before the message.
+fn sign_digest(
+    dns: &mut DNS,
+    key: &TsigKey,
+    prefix: Vec<u8>,
+    tsig: &mut TSig,
+) -> Result<(), Error> {
+    let mut data = prefix;
+    data.extend(dns.encode(true)?);
+    tsig.with_algorithm_name(&key.algorithm)
+        .with_original_id(dns.head().id());
+    data.extend(tsig.variables(&key.name)?);
+    tsig.with_mac(&key.mac(&data)?);
+
+    let mut rr = RR::new();
+    rr.with_name(&key.name)
+        .with_type(TYPE_TSIG)
+        .with_class(CLASS_ANY)
+        .with_ttl(0)
+        .with_rdata(RDataType::TSig(tsig.clone()));
+    dns.add_additional_rr(Rc::new(RefCell::new(rr)));
+
+    Ok(())
+}
+
+/// TsigRequest: the request verified by the key, its response is signed by the key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TsigRequest {
+    pub key: TsigKey,
+    pub request: TSig,
+}
+
+impl TsigRequest {
+    /// sign: sign the response to the request, see [`sign_response`].
+    pub fn sign(&self, dns: &mut DNS) -> Result<(), Error> {
+        let mut tsig = TSig::new();
+        tsig.with_time_signed().with_fudge(self.request.fudge);
+        sign_response(dns, &self.key, &self.request.mac, &mut tsig)
+    }
+}
+
+/// TsigFailure: the request failed the TSIG verification with the error, the key is known only when
+/// the MAC of the request matches, that is BADTIME.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TsigFailure {
+    pub key_name: String,
+    pub key: Option<TsigKey>,
+    pub request: TSig,
+    pub error: u16,
+}
+
+impl TsigFailure {
+    /**
+    respond: append the TSIG RR of the error response to the dns, see [`TSig::error_response`].
+
+    The BADKEY and BADSIG responses are unsigned with the empty MAC. The BADTIME response is signed
+    by the request key with the request MAC, and the time signed is copied from the request, so the
+    client can verify it against the time it sent the request.
+
+    ref: https://www.rfc-editor.org/rfc/rfc8945#section-5.2.3
+     */
+    pub fn respond(&self, dns: &mut DNS, server_time: u64) -> Result<(), Error> {
+        let server_time = (self.error == ERR_BADTIME).then_some(server_time);
+        let mut tsig = TSig::error_response(self.error, server_time);
+        tsig.with_algorithm_name(&self.request.algorithm_name)
+            .with_fudge(self.request.fudge)
+            .with_original_id(self.request.original_id);
+        if let (ERR_BADTIME, Some(key)) = (self.error, &self.key) {
+            tsig.time_signed = self.request.time_signed;
+            return sign_response(dns, key, &self.request.mac, &mut tsig);
+        }
+
+        let mut rr = RR::new();
+        rr.with_name(&self.key_name)
+            .with_type(TYPE_TSIG)
+            .with_class(CLASS_ANY)
+            .with_ttl(0)
+            .with_rdata(RDataType::TSig(tsig));
+        dns.add_additional_rr(Rc::new(RefCell::new(rr)));
+
+        Ok(())
+    }
+}
+
+/// TsigVerification: the result of [`verify`], the response is signed by it in [`sign_verified`].
+pub type TsigVerification = Result<Option<TsigRequest>, TsigFailure>;
+
+/**
+sign_verified: sign the response by the verification of its request. The response to the verified
+request is signed by [`TsigRequest::sign`], the response to the failed one carries the error TSIG RR
+by [`TsigFailure::respond`], and the response to the unsigned request is untouched.
+
+It is the last change of the response before sent, the truncated response is signed again.
+
+ref: https://www.rfc-editor.org/rfc/rfc8945#section-5.3
+ */
+pub fn sign_verified(
+    dns: &mut DNS,
+    verified: &TsigVerification,
+    server_time: u64,
+) -> Result<(), Error> {
+    match verified {
+        Ok(Some(request)) => request.sign(dns),
+        Ok(None) => Ok(()),
+        Err(failure) => failure.respond(dns, server_time),
+    }
+}
+
+/**
+verify: verify the TSIG RR of the request parsed from the wire, the request without the TSIG RR
+passes with None, the verified request is returned to sign the response. The checks are in the
+order of RFC 8945:
+
+1. the key of the name and the algorithm is known, or BADKEY.
+2. the MAC matches, or BADSIG.
+3. the now is in the time signed plus/minus the fudge, or BADTIME.
+
+ref: https://www.rfc-editor.org/rfc/rfc8945#section-5.2
+ */
+pub fn verify(dns: &DNS, keys: &[TsigKey], now: u64) -> TsigVerification {
+    let rr = match dns.additional().0.last() {
+        Some(rr) if rr.borrow().typ() == TYPE_TSIG => rr.borrow(),
+        _ => return Ok(None),
+    };
+    let tsig = match rr.rdata() {
+        RDataType::TSig(tsig) => tsig,
+        _ => return Ok(None),
+    };
+    let key_name = rr.name().trim_end_matches('.').to_lowercase();
+    let failure = |error: u16| TsigFailure {
+        key_name: key_name.clone(),
+        key: None,
+        request: tsig.clone(),
+        error,
+    };
+
+    let key = match keys
+        .iter()
+        .find(|key| key.name == key_name && key.algorithm == tsig.algorithm_name)
+    {
+        Some(key) => key,
+        None => return Err(failure(ERR_BADKEY)),
+    };
+    let mac = match strip_tsig(dns.raw(), tsig.original_id).and_then(|mut data| {
+        data.extend(tsig.variables(&key_name)?);
+        key.mac(&data)
+    }) {
+        Ok(mac) => mac,
+        Err(_) => return Err(failure(ERR_BADSIG)),
+    };
+    if !fixed_time_eq(&mac, &tsig.mac) {
+        return Err(failure(ERR_BADSIG));
+    }
+    if now.abs_diff(tsig.time_signed) > tsig.fudge as u64 {
+        return Err(TsigFailure {
+            key: Some(key.clone()),
+            ..failure(ERR_BADTIME)
+        });
+    }
+
+    Ok(Some(TsigRequest {
+        key: key.clone(),
+        request: tsig.clone(),
+    }))
+}
+
+/// the raw message before the TSIG RR was added: the TSIG RR is removed, the ARCOUNT is decreased
+/// and the ID is the original ID.
+fn strip_tsig(raw: &[u8], original_id: u16) -> Result<Vec<u8>, Error> {
+    let mut offset = 0;
+    let mut header = Header::from(raw, &mut offset)?;
+    let (ancount, nscount, arcount) = (header.ancount(), header.nscount(), header.arcount());
+    if arcount == 0 {
+        return Err(anyhow!(ERR_RDATE_MSG));
+    }
+
+    Questions::parse(raw, &mut offset, header.qdcount())?;
+    for _ in 0..ancount as usize + nscount as usize + arcount as usize - 1 {
+        RR::skip(raw, &mut offset)?;
+    }
+    header.with_id(original_id).with_arcount(arcount - 1);
+    let mut data = header.as_bytes().to_vec();
+    data.extend_from_slice(&raw[data.len()..offset]);
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sign, verify, verify_response, TSig, TsigKey, HMAC_SHA256};
+    use crate::{
+        dns::{
+            compress_list::CompressList,
+            rdata::{RDataOperation, RDataType},
+            CLASS_ANY, CLASS_IN, ERR_BADKEY, ERR_BADSIG, ERR_BADTIME, RR, TYPE_A, TYPE_TSIG,
+        },
+        DNS,
+    };
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn test_tsig_roundtrip() {
+        let mut dns = DNS::new();
+        dns.head().with_id(0x1234);
+        dns.with_ques("example.com", TYPE_A, CLASS_IN);
+
+        let mut tsig = TSig::new();
+        tsig.with_algorithm_name("hmac-sha256")
+            .with_fudge(300)
+            .with_mac(&[0xab; 32])
+            .with_original_id(0x1234)
+            .with_error(18)
+            .with_other_data(&[0, 0, 0x65, 0x4f, 0x2c, 0x10]);
+        tsig.time_signed = 0x0001_6543_2100;
+        let mut rr = RR::new();
+        rr.with_name("key.example.com")
+            .with_type(TYPE_TSIG)
+            .with_class(CLASS_ANY)
+            .with_ttl(0)
+            .with_rdata(RDataType::TSig(tsig.clone()));
+        dns.add_additional_rr(Rc::new(RefCell::new(rr)));
+
+        let raw = dns.encode(true).unwrap();
+        let resp = DNS::from(&raw).unwrap();
+        let additional = resp.additional();
+        assert_eq!(1, additional.0.len());
+        let rr = additional.0[0].borrow();
+        assert_eq!("key.example.com", rr.name());
+        assert_eq!(TYPE_TSIG, rr.typ());
+        assert_eq!(CLASS_ANY, rr.class());
+        let decoded = match rr.rdata() {
+            RDataType::TSig(tsig) => tsig,
+            rdata => panic!("not a TSIG rdata: {:?}", rdata),
+        };
+        assert_eq!("hmac-sha256", decoded.algorithm_name);
+        assert_eq!(0x0001_6543_2100, decoded.time_signed);
+        assert_eq!(300, decoded.fudge);
+        assert_eq!(vec![0xab; 32], decoded.mac);
+        assert_eq!(0x1234, decoded.original_id);
+        assert_eq!(18, decoded.error);
+        assert_eq!(vec![0, 0, 0x65, 0x4f, 0x2c, 0x10], decoded.other_data);
+        assert_eq!(&tsig, decoded);
+
+        // the truncated rdata
+        let mut rdata = vec![];
+        tsig.encode(&mut rdata, &mut CompressList::new(), false)
+            .unwrap();
+        for len in [0, 5, 13, 20, rdata.len() - 1] {
+            assert_eq!(true, TSig::from(&[], &rdata[..len], 0).is_err());
+        }
+        assert_eq!(tsig, TSig::from(&[], &rdata, 0).unwrap());
+    }
+
+    #[test]
+    fn test_tsig_sign_verify() {
+        let key = TsigKey::new("key.example.com.", "HMAC-SHA256", b"key");
+        assert_eq!(
+            hex_literal::hex!("f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8")
+                .to_vec(),
+            key.mac(b"The quick brown fox jumps over the lazy dog")
+                .unwrap()
+        );
+
+        let signed = |key: &TsigKey, time_signed: u64| {
+            let mut dns = DNS::new();
+            dns.head().with_id(0xbeef);
+            dns.with_ques("www.example.com", TYPE_A, CLASS_IN);
+            let mut tsig = TSig::new();
+            tsig.time_signed = time_signed;
+            sign(&mut dns, key, &mut tsig).unwrap();
+            DNS::from(&dns.encode(true).unwrap()).unwrap()
+        };
+        let now = 1_700_000_000;
+        let request = verify(&signed(&key, now), &[key.clone()], now + 300)
+            .unwrap()
+            .unwrap();
+        assert_eq!(key, request.key);
+        assert_eq!(now, request.request.time_signed);
+        // the unsigned request
+        let mut dns = DNS::new();
+        dns.with_ques("www.example.com", TYPE_A, CLASS_IN);
+        assert_eq!(
+            Ok(None),
+            verify(&DNS::from(&dns.encode(false).unwrap()).unwrap(), &[], now)
+        );
+
+        let error = |dns: &DNS, keys: &[TsigKey], now: u64| verify(dns, keys, now).unwrap_err();
+        // the unknown key name or algorithm
+        let failure = error(&signed(&key, now), &[], now);
+        assert_eq!(ERR_BADKEY, failure.error);
+        assert_eq!("key.example.com", failure.key_name);
+        assert_eq!(None, failure.key);
+        assert_eq!(0xbeef, failure.request.original_id);
+        let other = TsigKey::new("key.example.com", "hmac-sha1", b"key");
+        assert_eq!(ERR_BADKEY, error(&signed(&key, now), &[other], now).error);
+        // the other secret
+        let other = TsigKey::new("key.example.com", HMAC_SHA256, b"other");
+        assert_eq!(ERR_BADSIG, error(&signed(&key, now), &[other], now).error);
+        // out of the fudge
+        let failure = error(&signed(&key, now), &[key.clone()], now + 301);
+        assert_eq!(ERR_BADTIME, failure.error);
+        assert_eq!(Some(key.clone()), failure.key);
+    }
+
+    #[test]
+    fn test_tsig_sign_response() {
+        let key = TsigKey::new("key.example.com", HMAC_SHA256, b"key");
+        let now = 1_700_000_000;
+        let mut dns = DNS::new();
+        dns.head().with_id(0xbeef);
+        dns.with_ques("www.example.com", TYPE_A, CLASS_IN);
+        let mut tsig = TSig::new();
+        tsig.time_signed = now;
+        sign(&mut dns, &key, &mut tsig).unwrap();
+        let dns = DNS::from(&dns.encode(true).unwrap()).unwrap();
+
+        let response = |dns: &DNS, now: u64| {
+            let mut resp = DNS::from(dns.raw()).unwrap();
+            resp.into_response();
+            match verify(dns, &[key.clone()], now) {
+                Ok(request) => request.unwrap().sign(&mut resp).unwrap(),
+                Err(failure) => failure.respond(&mut resp, now).unwrap(),
+            }
+            DNS::from(&resp.encode(true).unwrap()).unwrap()
+        };
+        let tsig_of = |dns: &DNS| match dns.additional().0.last().unwrap().borrow().rdata() {
+            RDataType::TSig(tsig) => tsig.clone(),
+            rdata => panic!("not a TSIG rdata: {:?}", rdata),
+        };
+        // the response is bound to the request MAC
+        let resp = response(&dns, now);
+        assert_eq!(true, verify_response(&resp, &key, &tsig.mac).is_ok());
+        assert_eq!(true, verify_response(&resp, &key, &[0; 32]).is_err());
+        let other = TsigKey::new("key.example.com", HMAC_SHA256, b"other");
+        assert_eq!(true, verify_response(&resp, &other, &tsig.mac).is_err());
+
+        // BADTIME is signed with the time signed of the request
+        let resp = response(&dns, now + 3600);
+        let badtime = tsig_of(&resp);
+        assert_eq!(ERR_BADTIME, badtime.error);
+        assert_eq!(now, badtime.time_signed);
+        assert_eq!(false, badtime.mac.is_empty());
+        assert_eq!(true, verify_response(&resp, &key, &tsig.mac).is_ok());
+
+        // BADSIG is unsigned
+        let failure = verify(&dns, &[other], now).unwrap_err();
+        let mut resp = DNS::new();
+        failure.respond(&mut resp, now).unwrap();
+        let badsig = tsig_of(&DNS::from(&resp.encode(false).unwrap()).unwrap());
+        assert_eq!(ERR_BADSIG, badsig.error);
+        assert_eq!(true, badsig.mac.is_empty());
+    }
+
+    #[test]
+    fn test_tsig_error_response() {
+        for error in [ERR_BADKEY, ERR_BADSIG] {
+            let tsig = TSig::error_response(error, None);
+            assert_eq!(error, tsig.error);
+            assert_eq!(true, tsig.mac.is_empty());
+            assert_eq!(true, tsig.other_data.is_empty());
+        }
+        let tsig = TSig::error_response(ERR_BADTIME, Some(0x0001_6543_2100));
+        assert_eq!(ERR_BADTIME, tsig.error);
+        assert_eq!(vec![0x00, 0x01, 0x65, 0x43, 0x21, 0x00], tsig.other_data);
+    }
+}
@@ -13,29 +13,91 @@ TXT-DATA        One or more <character-string>s.
 
 TXT RRs are used to hold descriptive text.  The semantics of the text
 depends on the domain where it is found.
+
+# SPF and DKIM
+The SPF policy of a domain is published in a TXT record of the domain starting with `v=spf1`
+(ref: https://www.rfc-editor.org/rfc/rfc7208#section-3), and the DKIM public key is published in a
+TXT record of `<selector>._domainkey.<domain>` (ref: https://www.rfc-editor.org/rfc/rfc6376#section-3.6.2.2).
+The record longer than 255 octets is split into multiple <character-string>s, they are concatenated
+without any separator, see [`TXT::joined`].
  */
 
 use super::RDataOperation;
-use crate::dns::compress_list::CompressList;
-use anyhow::Error;
+use crate::dns::{
+    compress_list::CompressList,
+    rdata::{
+        character_string_to_presentation, character_strings_from_presentation,
+        parse_charactor_string,
+    },
+};
+use anyhow::{anyhow, Error};
+use std::{fmt, str::FromStr};
 
-#[derive(Debug, PartialEq, Eq)]
-pub struct TXT(pub String);
+/// TXT: the <character-string>s of the TXT-DATA.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TXT(pub Vec<String>);
 
 impl TXT {
-    pub fn from(raw: &[u8], rdata: &[u8]) -> Result<Self, Error> {
-        let mut txt = Self { 0: "".to_string() };
-        txt.decode(raw, rdata)?;
+    pub fn new() -> Self {
+        Self { 0: vec![] }
+    }
+
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<Self, Error> {
+        let mut txt = Self::new();
+        txt.decode(raw, rdata, rdata_start)?;
+
+        Ok(txt)
+    }
+
+    /// joined: concatenate all the <character-string>s, such as the DKIM key split across them.
+    pub fn joined(&self) -> String {
+        self.0.concat()
+    }
+
+    /// is_spf: whether the TXT is a SPF record, the version section is `v=spf1` exactly,
+    /// case-insensitively.
+    pub fn is_spf(&self) -> bool {
+        let joined = self.joined();
+        let mut terms = joined.split(' ');
+        terms
+            .next()
+            .is_some_and(|version| version.eq_ignore_ascii_case("v=spf1"))
+    }
+}
+
+/// the presentation format: the quoted <character-string>s separated by a space.
+impl fmt::Display for TXT {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list: Vec<String> = self
+            .0
+            .iter()
+            .map(|s| character_string_to_presentation(s.as_bytes()))
+            .collect();
+        write!(f, "{}", list.join(" "))
+    }
+}
+
+impl FromStr for TXT {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut txt = Self::new();
+        for s in character_strings_from_presentation(s)? {
+            txt.0.push(String::from_utf8(s)?);
+        }
 
         Ok(txt)
     }
 }
 
 impl RDataOperation for TXT {
-    fn decode(&mut self, _raw: &[u8], rdata: &[u8]) -> Result<(), Error> {
-        self.0 = unsafe { String::from_utf8_unchecked(rdata.to_vec()) };
+    fn decode(&mut self, _raw: &[u8], rdata: &[u8], _rdata_start: usize) -> Result<usize, Error> {
+        self.0.clear();
+        for s in parse_charactor_string(rdata)? {
+            self.0.push(String::from_utf8(s)?);
+        }
 
-        Ok(())
+        Ok(rdata.len())
     }
 
     fn encode(
@@ -44,8 +106,99 @@ impl RDataOperation for TXT {
         _hm: &mut CompressList,
         _is_compressed: bool,
     ) -> Result<usize, Error> {
-        raw.extend_from_slice(&self.0.as_bytes().to_vec());
+        let mut length = 0;
+        for s in &self.0 {
+            if s.len() > u8::MAX as usize {
+                return Err(anyhow!("the character-string is longer than 255"));
+            }
+            raw.push(s.len() as u8);
+            raw.extend_from_slice(s.as_bytes());
+            length += 1 + s.len();
+        }
+
+        Ok(length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::{rdata::RDataType, RR};
+
+    #[test]
+    fn test_txt_decode_encode() {
+        let rdata = b"\x05hello\x00\x05world";
+        let txt = TXT::from(&[], rdata, 0).unwrap();
+        assert_eq!(vec!["hello", "", "world"], txt.0);
+
+        let mut raw = vec![];
+        let length = txt
+            .encode(&mut raw, &mut CompressList::new(), false)
+            .unwrap();
+        assert_eq!(rdata.to_vec(), raw);
+        assert_eq!(rdata.len(), length);
+
+        assert_eq!(true, TXT::from(&[], b"\x05hell", 0).is_err());
+    }
+
+    #[test]
+    fn test_txt_rdlength() {
+        let mut rr = RR::txt(
+            "example.com",
+            300,
+            &["v=spf1 -all", "", "x".repeat(255).as_str()],
+        );
+        let mut raw = vec![];
+        rr.encode(&mut raw, &mut CompressList::new(), false)
+            .unwrap();
+
+        // name, TYPE, CLASS, TTL, then the RDLENGTH
+        let rdlength = 13 + 8;
+        let rdata = &raw[rdlength + 2..];
+        assert_eq!(1 + 11 + 1 + 1 + 255, rdata.len());
+        assert_eq!(
+            rdata.len() as u16,
+            u16::from_be_bytes([raw[rdlength], raw[rdlength + 1]])
+        );
+        let decoded = RR::from(&raw, &mut 0, &mut false).unwrap();
+        assert_eq!(rr.rdata(), decoded.rdata());
+        match decoded.rdata() {
+            RDataType::TXT(txt) => assert_eq!(3, txt.0.len()),
+            rdata => panic!("not a TXT rdata: {:?}", rdata),
+        }
+    }
+
+    #[test]
+    fn test_txt_presentation() {
+        let txt = TXT(vec!["v=spf1 -all".to_string(), "a\"b\\c\x01".to_string()]);
+        let presentation = txt.to_string();
+        assert_eq!(r#""v=spf1 -all" "a\"b\\c\001""#, presentation);
+        assert_eq!(txt, presentation.parse::<TXT>().unwrap());
+    }
+
+    #[test]
+    fn test_txt_joined() {
+        // the DKIM record split into two 200 octets <character-string>s
+        let prefix = "v=DKIM1; k=rsa; p=";
+        let key = "MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8A".repeat(13)[..400 - prefix.len()].to_string();
+        let first = format!("{}{}", prefix, &key[..200 - prefix.len()]);
+        let second = key[200 - prefix.len()..].to_string();
+        assert_eq!((200, 200), (first.len(), second.len()));
+        let txt = TXT(vec![first, second]);
+        assert_eq!(format!("v=DKIM1; k=rsa; p={}", key), txt.joined());
+        assert_eq!(false, txt.is_spf());
 
-        Ok(self.0.len())
+        assert_eq!(true, TXT(vec!["v=spf1 -all".to_string()]).is_spf());
+        assert_eq!(true, TXT(vec!["v=spf1".to_string()]).is_spf());
+        assert_eq!(
+            true,
+            TXT(vec![
+                "v=spf1 include:".to_string(),
+                "_spf.example.com -all".to_string()
+            ])
+            .is_spf()
+        );
+        assert_eq!(false, TXT(vec!["v=spf10 -all".to_string()]).is_spf());
+        assert_eq!(false, TXT(vec![]).is_spf());
     }
 }
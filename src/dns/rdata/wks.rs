@@ -52,7 +52,7 @@ use anyhow::anyhow;
 use anyhow::Error;
 use std::net::Ipv4Addr;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct WKS {
     /// An 32 bit Internet address
     addr: Ipv4Addr,
@@ -65,20 +65,24 @@ pub struct WKS {
 }
 
 impl WKS {
-    pub fn from(raw: &[u8], rdata: &[u8]) -> Result<Self, Error> {
-        let mut wks = WKS {
+    pub fn new() -> Self {
+        WKS {
             addr: Ipv4Addr::new(127, 0, 0, 1),
             protocol: 0,
             bit_map: vec![],
-        };
-        wks.decode(raw, rdata)?;
+        }
+    }
+
+    pub fn from(raw: &[u8], rdata: &[u8], rdata_start: usize) -> Result<Self, Error> {
+        let mut wks = Self::new();
+        wks.decode(raw, rdata, rdata_start)?;
 
         Ok(wks)
     }
 }
 
 impl RDataOperation for WKS {
-    fn decode(&mut self, _raw: &[u8], rdata: &[u8]) -> Result<(), Error> {
+    fn decode(&mut self, _raw: &[u8], rdata: &[u8], _rdata_start: usize) -> Result<usize, Error> {
         if 5 > rdata.len() {
             return Err(anyhow!(ERR_RDATE_MSG));
         }
@@ -86,7 +90,7 @@ impl RDataOperation for WKS {
         self.protocol = rdata[4];
         self.bit_map = rdata[4..].to_vec();
 
-        Ok(())
+        Ok(rdata.len())
     }
 
     fn encode(
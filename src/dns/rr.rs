@@ -1,12 +1,26 @@
 use super::{
+    align_columns, class_mnemonic,
     compress_list::CompressList,
     labels::Labels,
     meta_rr::MetaRR,
-    rdata::{encode_domain_name_wrap, RDataOperation, RDataType},
-    Class, RcRf, Type, VecRcRf, TYPE_OPT,
+    rdata::{
+        a::A, aaaa::AAAA, cname::CName, encode_domain_name_wrap, mx::MX, ns::NS, ptr::PTR,
+        soa::SOA, txt::TXT, RDataOperation, RDataType,
+    },
+    type_mnemonic, Class, RcRf, Type, VecRcRf, CLASS_IN, TYPE_A, TYPE_AAAA, TYPE_CNAME, TYPE_MX,
+    TYPE_NS, TYPE_OPT, TYPE_PTR, TYPE_SOA, TYPE_TXT,
 };
 use crate::util;
 use anyhow::{anyhow, Error};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+/// the max TTL, the most significant bit of the 32 bit TTL is zero.
+pub const MAX_TTL: u32 = 0x7FFF_FFFF;
 
 /// The answer, authority, and additional sections all share the same
 /// format: a variable number of resource records, where the number of
@@ -34,7 +48,7 @@ use anyhow::{anyhow, Error};
 ///     /                                               /
 ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
 /// ```
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RR {
     all_length: usize,
 
@@ -90,7 +104,119 @@ impl RR {
         }
     }
 
+    /// the RR of the class IN.
+    fn new_in(name: &str, ttl: u32, typ: Type, rdata: RDataType) -> Self {
+        let mut rr = Self::new();
+        rr.with_name(name)
+            .with_type(typ)
+            .with_class(CLASS_IN)
+            .with_ttl(ttl)
+            .with_rdata(rdata);
+        rr
+    }
+
+    /// a: the A record of the class IN.
+    pub fn a(name: &str, ttl: u32, ipv4: Ipv4Addr) -> Self {
+        Self::new_in(name, ttl, TYPE_A, RDataType::A(A::new(ipv4)))
+    }
+
+    /// aaaa: the AAAA record of the class IN.
+    pub fn aaaa(name: &str, ttl: u32, ipv6: Ipv6Addr) -> Self {
+        Self::new_in(name, ttl, TYPE_AAAA, RDataType::AAAA(AAAA::new(ipv6)))
+    }
+
+    /// cname: the CNAME record of the class IN.
+    pub fn cname(name: &str, ttl: u32, target: &str) -> Self {
+        Self::new_in(
+            name,
+            ttl,
+            TYPE_CNAME,
+            RDataType::CName(CName(target.to_string())),
+        )
+    }
+
+    /// ns: the NS record of the class IN.
+    pub fn ns(name: &str, ttl: u32, nsdname: &str) -> Self {
+        Self::new_in(name, ttl, TYPE_NS, RDataType::NS(NS(nsdname.to_string())))
+    }
+
+    /// ptr: the PTR record of the class IN.
+    pub fn ptr(name: &str, ttl: u32, ptrdname: &str) -> Self {
+        Self::new_in(
+            name,
+            ttl,
+            TYPE_PTR,
+            RDataType::PTR(PTR(ptrdname.to_string())),
+        )
+    }
+
+    /// mx: the MX record of the class IN.
+    pub fn mx(name: &str, ttl: u32, preference: u16, exchange: &str) -> Self {
+        let mut mx = MX::new();
+        mx.preference = preference;
+        mx.exchange = exchange.to_string();
+        Self::new_in(name, ttl, TYPE_MX, RDataType::MX(mx))
+    }
+
+    /// txt: the TXT record of the class IN, each of the txts is a <character-string>.
+    pub fn txt(name: &str, ttl: u32, txts: &[&str]) -> Self {
+        let txt = TXT(txts.iter().map(|s| s.to_string()).collect());
+        Self::new_in(name, ttl, TYPE_TXT, RDataType::TXT(txt))
+    }
+
+    /// soa: the SOA record of the class IN.
+    pub fn soa(name: &str, ttl: u32, soa: SOA) -> Self {
+        Self::new_in(name, ttl, TYPE_SOA, RDataType::SOA(soa))
+    }
+
     pub fn from(raw: &[u8], offset: &mut usize, is_compressed: &mut bool) -> Result<Self, Error> {
+        Self::parse(raw, offset, is_compressed, false)
+    }
+
+    /// from_strict: same as [`RR::from`], but the rdata must be consumed exactly by RDLENGTH.
+    pub fn from_strict(
+        raw: &[u8],
+        offset: &mut usize,
+        is_compressed: &mut bool,
+    ) -> Result<Self, Error> {
+        Self::parse(raw, offset, is_compressed, true)
+    }
+
+    /// skip: move the offset over the rr without decoding it, error if the rr overruns the raw.
+    pub fn skip(raw: &[u8], offset: &mut usize) -> Result<(), Error> {
+        let packet_err = || anyhow!("skip rr failed cause the raw not completed");
+        let mut pos = *offset;
+        // the owner name ends with the root or a pointer
+        loop {
+            let length = *raw.get(pos).ok_or_else(packet_err)? as usize;
+            if length & 0b1100_0000 == 0b1100_0000 {
+                pos += 2;
+                break;
+            }
+            pos += 1 + length;
+            if length == 0 {
+                break;
+            }
+        }
+        // TYPE, CLASS, TTL and RDLENGTH
+        if pos + 10 > raw.len() {
+            return Err(packet_err());
+        }
+        pos += 10 + u16::from_be_bytes([raw[pos + 8], raw[pos + 9]]) as usize;
+        if pos > raw.len() {
+            return Err(packet_err());
+        }
+        *offset = pos;
+
+        Ok(())
+    }
+
+    fn parse(
+        raw: &[u8],
+        offset: &mut usize,
+        is_compressed: &mut bool,
+        strict: bool,
+    ) -> Result<Self, Error> {
         let mut rr = Self::new();
         let packet_err = Error::msg("parse rr failed cause the raw not completed");
 
@@ -98,19 +224,14 @@ impl RR {
             return Err(packet_err);
         }
 
-        let (compressed_offset, _is_compressed) = util::is_compressed_wrap(&raw[*offset..]);
+        let (_, _is_compressed) = util::is_compressed_wrap(&raw[*offset..]);
         if _is_compressed {
             *is_compressed = _is_compressed;
-            // parse domain_name from the pointer position, that point a labels start position
-            *offset += 2;
-            let mut domain_name_offset = compressed_offset;
-            let labels = Labels::parse(raw, &mut domain_name_offset)?;
-            rr.name = labels.encode_to_str();
-        } else {
-            // parse domain_name from labels directly
-            let labels = Labels::parse(raw, offset)?;
-            rr.name = labels.encode_to_str();
         }
+        // the leading pointer of the owner is checked as the others, it must point before the owner,
+        // see [`check_pointer`](super::labels::check_pointer)
+        let labels = Labels::parse(raw, offset)?;
+        rr.name = labels.encode_to_str();
 
         // validate the rr length
         if *offset + 10 > raw.len() {
@@ -137,7 +258,12 @@ impl RR {
 
         // parse rdata
         // rr.rdata = raw[*offset..*offset + rr.rdlength as usize].to_vec();
-        rr.rdata = RDataType::from(raw, &raw[*offset..*offset + rr.rdlength as usize], rr.typ)?;
+        let rdata = &raw[*offset..*offset + rr.rdlength as usize];
+        rr.rdata = if strict {
+            RDataType::from_strict(raw, rdata, *offset, rr.typ)?
+        } else {
+            RDataType::from(raw, rdata, *offset, rr.typ)?
+        };
         *offset += rr.rdlength as usize;
 
         Ok(rr)
@@ -178,6 +304,17 @@ impl RR {
         return self.ttl;
     }
 
+    /// effective_ttl: the TTL is a 31 bit value, the received TTL with the most significant bit set is
+    /// treated as zero, so the absurd TTL is never cached. Not for the OPT, whose TTL carries the flags.
+    ///
+    /// ref: https://www.rfc-editor.org/rfc/rfc2181#section-8
+    pub fn effective_ttl(&self) -> u32 {
+        if self.ttl > MAX_TTL {
+            return 0;
+        }
+        self.ttl
+    }
+
     pub fn with_ttl(&mut self, ttl: u32) -> &mut Self {
         self.ttl = ttl;
         return self;
@@ -196,6 +333,20 @@ impl RR {
         return self;
     }
 
+    /// presentation: the line of the rr in the master file, `name type class ttl rdata` with the numeric
+    /// type and class, the rdata is in its display. The names are absolute, such as `example.com.` and
+    /// `.` of the root.
+    pub fn presentation(&self) -> String {
+        format!(
+            "{}. {} {} {} {}",
+            self.name.trim_end_matches('.'),
+            self.typ,
+            self.class,
+            self.ttl,
+            self.rdata
+        )
+    }
+
     pub fn encode(
         &mut self,
         raw: &mut Vec<u8>,
@@ -229,6 +380,34 @@ impl RR {
         Ok(())
     }
 
+    /**
+    ref: https://www.rfc-editor.org/rfc/rfc4034#section-6.3
+
+    canonical_cmp: compare the RR by canonical DNS name order, then type and class, then the
+    RDATA treated as a left-justified unsigned octet sequence (uncompressed).
+    */
+    pub fn canonical_cmp(&self, other: &RR) -> Ordering {
+        let labels = |name: &str| -> Vec<Vec<u8>> {
+            name.trim_end_matches('.')
+                .rsplit('.')
+                .map(|l| l.to_ascii_lowercase().into_bytes())
+                .collect()
+        };
+        let wire = |rdata: &RDataType| -> Vec<u8> {
+            let mut raw = vec![];
+            rdata
+                .encode(&mut raw, &mut CompressList::new(), false)
+                .map(|_| raw)
+                .unwrap_or_default()
+        };
+
+        labels(&self.name)
+            .cmp(&labels(&other.name))
+            .then(self.typ.cmp(&other.typ))
+            .then(self.class.cmp(&other.class))
+            .then_with(|| wire(&self.rdata).cmp(&wire(&other.rdata)))
+    }
+
     pub fn convert_pseudo(&mut self) -> Result<MetaRR, Error> {
         if self.typ != TYPE_OPT {
             return Err(anyhow!("not pseudo rr"));
@@ -240,6 +419,22 @@ impl RR {
     }
 }
 
+/// the presentation format of the rr in one line in the dig style, `name ttl class type rdata` such
+/// as `example.com.\t\t300\tIN\tA\t10.0.0.1`. The columns are aligned by the tabs as the question:
+/// the ttl at the column 24, the class at 32, the type at 40 and the rdata at 48.
+impl fmt::Display for RR {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let line = align_columns(&[
+            (&format!("{}.", self.name.trim_end_matches('.')), 0),
+            (&self.ttl.to_string(), 24),
+            (&class_mnemonic(self.class), 32),
+            (&type_mnemonic(self.typ), 40),
+            (&self.rdata.to_string(), 48),
+        ]);
+        write!(f, "{}", line)
+    }
+}
+
 /// RRs, RR sets
 #[derive(Debug)]
 pub struct RRs(pub VecRcRf<RR>);
@@ -306,6 +501,61 @@ impl RRs {
         })
     }
 
+    /// canonicalize: lowercase all the owner names, sort the RRs in canonical RRset order,
+    /// and set the TTL of every RR in a RRset to the minimum TTL of the RRset.
+    pub fn canonicalize(&mut self) {
+        for rr in &self.0 {
+            let mut rr = rr.borrow_mut();
+            rr.name = rr.name.to_ascii_lowercase();
+        }
+        self.0.sort_by(|a, b| a.borrow().canonical_cmp(&b.borrow()));
+
+        let mut start = 0;
+        while start < self.0.len() {
+            let mut end = start + 1;
+            let mut min_ttl = self.0[start].borrow().ttl;
+            while end < self.0.len()
+                && is_same_rrset(&self.0[start].borrow(), &self.0[end].borrow())
+            {
+                min_ttl = min_ttl.min(self.0[end].borrow().ttl);
+                end += 1;
+            }
+            for rr in &self.0[start..end] {
+                rr.borrow_mut().ttl = min_ttl;
+            }
+            start = end;
+        }
+    }
+
+    /// cap_ttls: set the TTL of every RR to min(ttl, max).
+    ///
+    /// The zero TTL is kept, it means the RR can only be used for the transaction in progress.
+    /// The OPT pseudo RR is skipped, its TTL field holds the extended RCODE and flags.
+    pub fn cap_ttls(&mut self, max: u32) {
+        for rr in &self.0 {
+            let mut rr = rr.borrow_mut();
+            if rr.typ != TYPE_OPT {
+                rr.ttl = rr.effective_ttl().min(max);
+            }
+        }
+    }
+
+    /// rotate: shift the RRs of each RRset cyclically by one, the first RR becomes the last. The
+    /// RRsets stay in their positions, so the round-robin of the addresses never interleaves the types.
+    pub fn rotate(&mut self) {
+        let mut start = 0;
+        while start < self.0.len() {
+            let mut end = start + 1;
+            while end < self.0.len()
+                && is_same_rrset(&self.0[start].borrow(), &self.0[end].borrow())
+            {
+                end += 1;
+            }
+            self.0[start..end].rotate_left(1);
+            start = end;
+        }
+    }
+
     pub fn encode(
         &mut self,
         raw: &mut Vec<u8>,
@@ -321,9 +571,58 @@ impl RRs {
     }
 }
 
+/// RRs with the same owner name (case-insensitive), class and type form a RRset.
+pub fn is_same_rrset(a: &RR, b: &RR) -> bool {
+    a.typ == b.typ && a.class == b.class && a.name.eq_ignore_ascii_case(&b.name)
+}
+
+/// Follow the CNAME chains in records and return the name at which a cycle is found.
+///
+/// Owner names and targets are compared case-insensitively. Eg: `a -> b -> a` returns `Some("a")`.
+pub fn detect_cname_loop(records: &[RcRf<RR>]) -> Option<String> {
+    let records: Vec<_> = records.iter().map(|rr| rr.borrow()).collect();
+    cname_loop(records.iter().map(|rr| &**rr))
+}
+
+/// Same as [`detect_cname_loop`], for the CNAME chain being followed, such as the zone answers.
+pub fn detect_cname_chain_loop(chain: &[RR]) -> Option<String> {
+    cname_loop(chain.iter())
+}
+
+fn cname_loop<'a>(records: impl Iterator<Item = &'a RR>) -> Option<String> {
+    let mut targets = HashMap::new();
+    let mut owners = vec![];
+    for rr in records {
+        if rr.typ() != TYPE_CNAME {
+            continue;
+        }
+        if let RDataType::CName(cname) = rr.rdata() {
+            let owner = rr.name().to_ascii_lowercase();
+            targets.insert(owner.clone(), cname.0.to_ascii_lowercase());
+            owners.push(owner);
+        }
+    }
+
+    for owner in owners {
+        let mut visited = HashSet::new();
+        let mut name = owner;
+        while let Some(target) = targets.get(&name) {
+            if !visited.insert(name.clone()) {
+                return Some(name);
+            }
+            name = target.clone();
+        }
+    }
+
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dns::{labels::HEADER_LEN, rdata::null::Null, CLASS_CH};
+    use std::{cell::RefCell, rc::Rc};
+
     #[test]
     pub fn test_rr_with_name() {
         let mut rr = RR::new();
@@ -374,6 +673,47 @@ mod tests {
     //     assert_eq!(vec![10_u8, 0, 0, 2], rr.rdata);
     // }
 
+    #[test]
+    pub fn test_rr_from_long_name() {
+        // 4 * (1 + 63) + 1 > 255
+        let mut raw = vec![];
+        for _ in 0..4 {
+            raw.push(63);
+            raw.extend([b'a'; 63]);
+        }
+        // TYPE A, CLASS IN, TTL 60 and the 4 octets rdata
+        raw.extend([0, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 10, 0, 0, 1]);
+        assert_eq!(true, RR::from(&raw, &mut 0, &mut false).is_err());
+
+        // the name of 3 labels is accepted
+        let raw = &raw[64..];
+        let rr = RR::from(raw, &mut 0, &mut false).unwrap();
+        assert_eq!(3, rr.name().split('.').count());
+    }
+
+    #[test]
+    pub fn test_rr_from_owner_pointer() {
+        // the header, the question "a" without the type and class, then the RR of the owner pointer
+        let mut raw = vec![0; HEADER_LEN];
+        raw.extend([0x01, b'a', 0x00]);
+        let start = raw.len();
+        raw.extend([0xc0, HEADER_LEN as u8]);
+        // TYPE A, CLASS IN, TTL 60 and the 4 octets rdata
+        raw.extend([0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 10, 0, 0, 1]);
+        let (mut offset, mut is_compressed) = (start, false);
+        let rr = RR::from(&raw, &mut offset, &mut is_compressed).unwrap();
+        assert_eq!("a", rr.name());
+        assert_eq!(true, is_compressed);
+        assert_eq!(raw.len(), offset);
+
+        // the owner pointer to itself, and forward
+        for target in [start, start + 2] {
+            raw[start + 1] = target as u8;
+            let mut offset = start;
+            assert_eq!(true, RR::from(&raw, &mut offset, &mut false).is_err());
+        }
+    }
+
     #[test]
     pub fn test_rr_is_compressed() {
         let cases = [([192_u8, 12], true, 12), ([6_u8, 13], false, 0)];
@@ -404,4 +744,202 @@ mod tests {
         assert_eq!(true, rr1 == rr2);
         assert_eq!(false, rr1 == rr3);
     }
+
+    #[test]
+    pub fn test_detect_cname_loop() {
+        let new_cname = |name: &str, target: &str| {
+            let mut rr = RR::new();
+            rr.with_name(name)
+                .with_type(TYPE_CNAME)
+                .with_class(1)
+                .with_ttl(300)
+                .with_rdata(RDataType::CName(CName(target.to_string())));
+            Rc::new(RefCell::new(rr))
+        };
+
+        let chain = vec![
+            new_cname("www.example.com", "web.example.com"),
+            new_cname("web.example.com", "host.example.com"),
+        ];
+        assert_eq!(None, detect_cname_loop(&chain));
+
+        let looping = vec![
+            new_cname("a.example.com", "B.example.com"),
+            new_cname("b.example.com", "a.example.com"),
+        ];
+        assert_eq!(
+            Some("a.example.com".to_string()),
+            detect_cname_loop(&looping)
+        );
+        let looping: Vec<RR> = looping.iter().map(|rr| rr.borrow().clone()).collect();
+        assert_eq!(
+            Some("a.example.com".to_string()),
+            detect_cname_chain_loop(&looping)
+        );
+        assert_eq!(None, detect_cname_chain_loop(&looping[..1]));
+    }
+
+    #[test]
+    fn test_rrs_rotate() {
+        let a = |name: &str, last: u8| {
+            Rc::new(RefCell::new(RR::a(
+                name,
+                300,
+                Ipv4Addr::new(10, 0, 0, last),
+            )))
+        };
+        let mut rrs = RRs(vec![
+            Rc::new(RefCell::new(RR::cname(
+                "www.example.com",
+                300,
+                "web.example.com",
+            ))),
+            a("web.example.com", 1),
+            a("web.example.com", 2),
+            a("web.example.com", 3),
+            a("mail.example.com", 4),
+        ]);
+        let order = |rrs: &RRs| {
+            rrs.0
+                .iter()
+                .map(|rr| match rr.borrow().rdata() {
+                    RDataType::A(a) => a.0.octets()[3],
+                    _ => 0,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        rrs.rotate();
+        assert_eq!(vec![0, 2, 3, 1, 4], order(&rrs));
+        rrs.rotate();
+        assert_eq!(vec![0, 3, 1, 2, 4], order(&rrs));
+        rrs.rotate();
+        assert_eq!(vec![0, 1, 2, 3, 4], order(&rrs));
+
+        RRs::new().rotate();
+    }
+
+    #[test]
+    fn test_rr_constructors() {
+        let assert_rr = |rr: &RR, name: &str, typ: Type, rdata: RDataType| {
+            assert_eq!(name, rr.name());
+            assert_eq!(typ, rr.typ());
+            assert_eq!(CLASS_IN, rr.class());
+            assert_eq!(300, rr.ttl());
+            assert_eq!(&rdata, rr.rdata());
+        };
+
+        let ipv4 = Ipv4Addr::new(10, 0, 0, 1);
+        assert_rr(
+            &RR::a("www.example.com", 300, ipv4),
+            "www.example.com",
+            TYPE_A,
+            RDataType::A(A::new(ipv4)),
+        );
+        let ipv6 = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+        assert_rr(
+            &RR::aaaa("www.example.com", 300, ipv6),
+            "www.example.com",
+            TYPE_AAAA,
+            RDataType::AAAA(AAAA::new(ipv6)),
+        );
+        assert_rr(
+            &RR::cname("ftp.example.com", 300, "www.example.com"),
+            "ftp.example.com",
+            TYPE_CNAME,
+            RDataType::CName(CName("www.example.com".to_string())),
+        );
+        assert_rr(
+            &RR::ns("example.com", 300, "ns1.example.com"),
+            "example.com",
+            TYPE_NS,
+            RDataType::NS(NS("ns1.example.com".to_string())),
+        );
+        assert_rr(
+            &RR::ptr("1.0.0.10.in-addr.arpa", 300, "www.example.com"),
+            "1.0.0.10.in-addr.arpa",
+            TYPE_PTR,
+            RDataType::PTR(PTR("www.example.com".to_string())),
+        );
+        let mut mx = MX::new();
+        mx.preference = 10;
+        mx.exchange = "mail.example.com".to_string();
+        assert_rr(
+            &RR::mx("example.com", 300, 10, "mail.example.com"),
+            "example.com",
+            TYPE_MX,
+            RDataType::MX(mx),
+        );
+        assert_rr(
+            &RR::txt("example.com", 300, &["v=spf1", "-all"]),
+            "example.com",
+            TYPE_TXT,
+            RDataType::TXT(TXT(vec!["v=spf1".to_string(), "-all".to_string()])),
+        );
+        let mut soa = SOA::new();
+        soa.mname = "ns1.example.com".to_string();
+        assert_rr(
+            &RR::soa("example.com", 300, soa.clone()),
+            "example.com",
+            TYPE_SOA,
+            RDataType::SOA(soa),
+        );
+    }
+
+    #[test]
+    fn test_rr_effective_ttl() {
+        let mut dns = crate::DNS::new();
+        for ttl in [0xFFFF_FFFF, MAX_TTL] {
+            let rr = RR::a("example.com", ttl, Ipv4Addr::new(10, 0, 0, 1));
+            dns.add_answer_rr(Rc::new(RefCell::new(rr)));
+        }
+        let mut dns = crate::DNS::from(&dns.encode(false).unwrap()).unwrap();
+
+        let answers = &dns.answers().0;
+        assert_eq!(0xFFFF_FFFF, answers[0].borrow().ttl());
+        assert_eq!(0, answers[0].borrow().effective_ttl());
+        assert_eq!(MAX_TTL, answers[1].borrow().effective_ttl());
+        assert_eq!(Some(0), dns.min_ttl());
+
+        dns.cap_ttls(300);
+        assert_eq!(0, dns.answers().0[0].borrow().ttl());
+        assert_eq!(300, dns.answers().0[1].borrow().ttl());
+    }
+
+    #[test]
+    fn test_rr_display() {
+        assert_eq!(
+            "example.com.\t\t3600\tIN\tMX\t10 mail.example.com.",
+            RR::mx("example.com", 3600, 10, "mail.example.com").to_string()
+        );
+        assert_eq!(
+            "www.example.com.\t300\tIN\tAAAA\t2001:db8::1",
+            RR::aaaa("www.example.com.", 300, "2001:db8::1".parse().unwrap()).to_string()
+        );
+        assert_eq!(
+            "example.com.\t\t300\tIN\tTXT\t\"v=spf1\" \"-all\"",
+            RR::txt("example.com", 300, &["v=spf1", "-all"]).to_string()
+        );
+        // a tab after the longer name
+        assert_eq!(
+            "a.very.long.name.example.com.\t300\tIN\tA\t10.0.0.1",
+            RR::a(
+                "a.very.long.name.example.com",
+                300,
+                Ipv4Addr::new(10, 0, 0, 1)
+            )
+            .to_string()
+        );
+
+        let mut rr = RR::new();
+        rr.with_name("example.com")
+            .with_type(65280)
+            .with_class(CLASS_CH)
+            .with_ttl(0)
+            .with_rdata(RDataType::Unknown(Null::from(&[], &[1, 2], 0).unwrap()));
+        assert_eq!(
+            "example.com.\t\t0\tCH\tTYPE65280\t\\# 2 0102",
+            rr.to_string()
+        );
+    }
 }
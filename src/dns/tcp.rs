@@ -0,0 +1,102 @@
+/*!
+ref: https://www.rfc-editor.org/rfc/rfc1035#section-4.2.2
+
+# TCP usage
+Messages sent over TCP connections use server port 53 (decimal).  The message is prefixed with a two
+byte length field which gives the message length, excluding the two byte length field.  This length
+field allows the low-level processing to assemble a complete message before beginning to parse it.
+
+The connection can carry multiple messages, each of them is framed by its own length field.
+ */
+
+use crate::DNS;
+use anyhow::{anyhow, Error};
+use std::io::Read;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// encode_tcp: the dns in the wire format prefixed with the two octets length.
+pub fn encode_tcp(dns: &mut DNS) -> Result<Vec<u8>, Error> {
+    let raw = dns.encode(true)?;
+    let len: u16 = raw
+        .len()
+        .try_into()
+        .map_err(|_| anyhow!("the message is longer than {} octets", u16::MAX))?;
+    let mut framed = Vec::with_capacity(2 + raw.len());
+    framed.extend_from_slice(&len.to_be_bytes());
+    framed.extend_from_slice(&raw);
+
+    Ok(framed)
+}
+
+/// decode_tcp: read one message of the stream by its length prefix, the stream is left at the next
+/// message.
+pub fn decode_tcp(stream: &mut impl Read) -> Result<DNS, Error> {
+    let mut len = [0u8; 2];
+    stream.read_exact(&mut len)?;
+    let mut raw = vec![0u8; u16::from_be_bytes(len) as usize];
+    stream.read_exact(&mut raw)?;
+
+    DNS::from(&raw)
+}
+
+/// read_tcp: the async version of [`decode_tcp`] for the tokio streams.
+pub async fn read_tcp(stream: &mut (impl AsyncRead + Unpin)) -> Result<DNS, Error> {
+    let len = stream.read_u16().await?;
+    let mut raw = vec![0u8; len as usize];
+    stream.read_exact(&mut raw).await?;
+
+    DNS::from(&raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_tcp, encode_tcp, read_tcp};
+    use crate::{
+        dns::{CLASS_IN, TYPE_A, TYPE_MX},
+        DNS,
+    };
+    use std::io::Cursor;
+
+    fn query(name: &str, typ: u16, id: u16) -> DNS {
+        let mut dns = DNS::new();
+        dns.with_ques(name, typ, CLASS_IN);
+        dns.head().with_id(id);
+        dns
+    }
+
+    #[test]
+    fn test_tcp_roundtrip() {
+        let mut first = query("example.com", TYPE_A, 1);
+        let mut second = query("example.org", TYPE_MX, 2);
+        let framed = encode_tcp(&mut first).unwrap();
+        let raw = first.encode(true).unwrap();
+        assert_eq!(
+            raw.len(),
+            u16::from_be_bytes([framed[0], framed[1]]) as usize
+        );
+        assert_eq!(raw, framed[2..]);
+
+        let mut stream = Cursor::new([framed, encode_tcp(&mut second).unwrap()].concat());
+        let mut decoded = decode_tcp(&mut stream).unwrap();
+        assert_eq!(1, decoded.head().id());
+        assert_eq!(true, decoded.ques().0[0].matches(&first.ques().0[0]));
+        let mut decoded = decode_tcp(&mut stream).unwrap();
+        assert_eq!(2, decoded.head().id());
+        assert_eq!(true, decoded.ques().0[0].matches(&second.ques().0[0]));
+        // the end of the stream
+        assert_eq!(true, decode_tcp(&mut stream).is_err());
+
+        // the message shorter than its length
+        let framed = encode_tcp(&mut first).unwrap();
+        let mut stream = Cursor::new(framed[..framed.len() - 1].to_vec());
+        assert_eq!(true, decode_tcp(&mut stream).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tcp_read_async() {
+        let framed = encode_tcp(&mut query("example.com", TYPE_A, 3)).unwrap();
+        let mut stream = &framed[..];
+        assert_eq!(3, read_tcp(&mut stream).await.unwrap().head().id());
+        assert_eq!(true, read_tcp(&mut stream).await.is_err());
+    }
+}
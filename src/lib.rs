@@ -1,6 +1,6 @@
 pub mod dns;
 pub use dns::DNS;
-mod util;
+pub mod util;
 // pub mod server;
 pub mod components;
 pub use components::name_server;
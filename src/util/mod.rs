@@ -1,8 +1,11 @@
 use std::{
     ffi::OsStr,
+    fmt::Write,
     fs::{self, DirEntry},
+    net::IpAddr,
 };
 
+use crate::dns::{Header, Question, RR};
 use anyhow::Error;
 use base64::{
     alphabet::STANDARD,
@@ -39,11 +42,41 @@ pub fn encode_name(name: &str) -> &str {
     return name;
 }
 
+/// decode_name: the name of the master file without the trailing dot of the absolute name, the root
+/// is written as `NONE` or `.`.
 pub fn decode_name(src: &str) -> &str {
-    if src == "NONE" {
+    if src == "NONE" || src == "." {
         return "";
     }
-    return src;
+    return src.trim_end_matches('.');
+}
+
+/**
+reverse_name: the domain name of the address in the reverse zone, the octets of the IPv4 address under
+`in-addr.arpa`, and the nibbles of the IPv6 address under `ip6.arpa`, both in the reverse order.
+
+Example: `2001:db8::1` is `1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa`.
+
+ref: https://www.rfc-editor.org/rfc/rfc3596#section-2.5
+ */
+pub fn reverse_name(ip: IpAddr) -> String {
+    let mut name = String::new();
+    match ip {
+        IpAddr::V4(ip) => {
+            for octet in ip.octets().iter().rev() {
+                write!(name, "{}.", octet).unwrap();
+            }
+            name.push_str("in-addr.arpa");
+        }
+        IpAddr::V6(ip) => {
+            for octet in ip.octets().iter().rev() {
+                write!(name, "{:x}.{:x}.", octet & 0x0f, octet >> 4).unwrap();
+            }
+            name.push_str("ip6.arpa");
+        }
+    }
+
+    name
 }
 
 pub fn visit_dirs(dir: &str) -> Result<Vec<String>, Error> {
@@ -77,6 +110,93 @@ pub fn visit_dirs_with_cb(dir: &str, cb: &dyn Fn(&DirEntry)) -> Result<(), Error
     Ok(())
 }
 
+/// hexdump: dump the bytes like `xxd`, every line contains the offset, 16 bytes in hex and the ascii.
+///
+/// Example:
+/// ```shell
+/// 00000000: 227d 8580 0001 0001 0000 0000 0667 6f6f  "}...........goo
+/// ```
+pub fn hexdump(bytes: &[u8]) -> String {
+    hexdump_from(bytes, 0)
+}
+
+fn hexdump_from(bytes: &[u8], base: usize) -> String {
+    let mut out = String::new();
+    for (i, line) in bytes.chunks(16).enumerate() {
+        let mut hex = String::new();
+        for (j, b) in line.iter().enumerate() {
+            if j != 0 && j % 2 == 0 {
+                hex.push(' ');
+            }
+            write!(hex, "{:02x}", b).unwrap();
+        }
+        let ascii: String = line
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        writeln!(out, "{:08x}: {:<39}  {}", base + i * 16, hex, ascii).unwrap();
+    }
+
+    out
+}
+
+/// annotate_dns: dump the dns packet by segments, every segment(header, question, rr) is labeled
+/// before its hexdump. The bytes that can't be parsed are dumped as unparsed.
+pub fn annotate_dns(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let mut offset = 0;
+    let segment = |out: &mut String, label: &str, start: usize, end: usize| {
+        writeln!(out, "; {} [{}..{})", label, start, end).unwrap();
+        out.push_str(&hexdump_from(&bytes[start..end], start));
+    };
+
+    let head = match Header::from(bytes, &mut offset) {
+        Ok(head) => head,
+        Err(_) => {
+            segment(&mut out, "unparsed", 0, bytes.len());
+            return out;
+        }
+    };
+    segment(&mut out, "header", 0, offset);
+
+    for i in 0..head.qdcount() {
+        let start = offset;
+        if Question::from(bytes, &mut offset).is_err() || offset > bytes.len() {
+            segment(&mut out, "unparsed", start, bytes.len());
+            return out;
+        }
+        segment(&mut out, &format!("question {}", i), start, offset);
+    }
+
+    let sections = [
+        ("answer", head.ancount()),
+        ("authority", head.nscount()),
+        ("additional", head.arcount()),
+    ];
+    let mut is_compressed = false;
+    for (name, count) in sections {
+        for i in 0..count {
+            let start = offset;
+            if RR::from(bytes, &mut offset, &mut is_compressed).is_err() {
+                segment(&mut out, "unparsed", start, bytes.len());
+                return out;
+            }
+            segment(&mut out, &format!("{} {}", name, i), start, offset);
+        }
+    }
+    if offset < bytes.len() {
+        segment(&mut out, "trailing", offset, bytes.len());
+    }
+
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -104,6 +224,48 @@ mod tests {
         assert_eq!("!@#$%^&*()_+ []|';,./?><:\"~`", s);
     }
 
+    #[test]
+    fn test_reverse_name() {
+        assert_eq!(
+            "1.0.0.10.in-addr.arpa",
+            reverse_name("10.0.0.1".parse().unwrap())
+        );
+        assert_eq!(
+            "1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa",
+            reverse_name("2001:db8::1".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_hexdump() {
+        let out = hexdump(b"\x22\x7d\x85\x80\x00\x01google.com\x00\x01\xff");
+        assert_eq!(
+            "00000000: 227d 8580 0001 676f 6f67 6c65 2e63 6f6d  \"}....google.com\n\
+             00000010: 0001 ff                                  ...\n",
+            out
+        );
+    }
+
+    #[test]
+    fn test_annotate_dns() {
+        let mut raw = vec![0_u8, 1, 0x81, 0x80, 0, 1, 0, 1, 0, 0, 0, 0];
+        // question: root, type A, class IN
+        raw.extend([0_u8, 0, 1, 0, 1]);
+        // answer: root, type A, class IN, ttl 60, 10.0.0.1
+        raw.extend([0_u8, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 10, 0, 0, 1]);
+
+        let out = annotate_dns(&raw);
+        assert_eq!(
+            "; header [0..12)\n\
+             00000000: 0001 8180 0001 0001 0000 0000            ............\n\
+             ; question 0 [12..17)\n\
+             0000000c: 0000 0100 01                             .....\n\
+             ; answer 0 [17..32)\n\
+             00000011: 0000 0100 0100 0000 3c00 040a 0000 01    ........<......\n",
+            out
+        );
+    }
+
     #[test]
     fn test_visit_dirs() {
         let filenames = visit_dirs("./").unwrap();